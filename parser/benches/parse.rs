@@ -0,0 +1,43 @@
+//! Throughput benchmark for `parse_module` on multi-megabyte inputs.
+//!
+//! Run with `cargo bench --bench parse`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: usize = 5;
+
+fn synthetic_module(target_bytes: usize) -> String {
+    let mut src = String::from(
+        "module bench.synthetic\n\nimport core.io\nimport core.text { trim, join } as T\n\n",
+    );
+    let mut i = 0;
+    while src.len() < target_bytes {
+        src.push_str(&format!(
+            "/// Record number {i}.\nrecord Item{i} {{\n  title: String\n  tags?: List[String]\n  meta: Map[String, Int]\n}}\n\n\
+             task Build{i}(topic: String, limit: Int = 3) -> Item{i} {{\n  let draft = Writer.run(topic, limit)\n  \
+             let tags = T.join(draft[\"tags\"], \", \")\n  return Item{i} {{ title: \"Item \" + topic, tags: tags, meta: draft.meta }}\n}}\n\n"
+        ));
+        i += 1;
+    }
+    src
+}
+
+fn main() {
+    for megabytes in [1, 4, 16] {
+        let src = synthetic_module(megabytes * 1024 * 1024);
+        let mut best = Duration::MAX;
+        for _ in 0..ITERATIONS {
+            let start = Instant::now();
+            let module =
+                parser::parse_module(black_box(&src)).expect("synthetic module should parse");
+            best = best.min(start.elapsed());
+            black_box(module);
+        }
+        let throughput = src.len() as f64 / (1024.0 * 1024.0) / best.as_secs_f64();
+        println!(
+            "parse_module {megabytes:>2} MiB: best {:>8.2} ms ({throughput:.1} MiB/s)",
+            best.as_secs_f64() * 1000.0
+        );
+    }
+}
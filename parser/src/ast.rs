@@ -1,76 +1,548 @@
 //! Core Abstract Syntax Tree definitions for the HILO language.
 
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod dump;
+
+pub use dump::dump;
+
 pub type Ident = String;
 pub type QualifiedName = Vec<Ident>;
 
+/// Byte range into the source text a node was parsed from.
+pub type Span = std::ops::Range<usize>;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Module {
+    /// `///` lines above the `module` declaration, without their markers.
+    pub docs: Option<String>,
     pub name: Option<QualifiedName>,
     pub imports: Vec<Import>,
     pub items: Vec<Item>,
+    pub comments: Vec<Comment>,
+    /// The whole source for a file, or the declaration of a nested module.
+    pub span: Span,
+}
+
+/// A source comment, retained when `ParseOptions::keep_trivia` is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub kind: CommentKind,
+    pub text: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentKind {
+    Line,
+    Doc,
+    Block,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Import {
+    /// Relative imports keep their leading `.` or `..` segments, so
+    /// `import ../shared.types` has the path `["..", "shared", "types"]`.
     pub path: QualifiedName,
+    /// Version constraint from `import vendor.summarizer @ "1.2"`, as written.
+    pub version: Option<String>,
     pub members: Option<Vec<Ident>>,
     pub alias: Option<Ident>,
 }
 
+impl Import {
+    /// Whether the path resolves against the importing file's directory
+    /// rather than the project's module names.
+    pub fn is_relative(&self) -> bool {
+        matches!(self.path.first().map(String::as_str), Some("." | ".."))
+    }
+
+    /// The path as written, e.g. `lib.text` or `../shared.types`.
+    pub fn path_text(&self) -> String {
+        let relative = self
+            .path
+            .iter()
+            .take_while(|segment| matches!(segment.as_str(), "." | ".."))
+            .count();
+        let prefix: String = self.path[..relative]
+            .iter()
+            .map(|segment| format!("{segment}/"))
+            .collect();
+        format!("{prefix}{}", self.path[relative..].join("."))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Item {
     Record(RecordDecl),
+    Interface(InterfaceDecl),
     Task(TaskDecl),
     Workflow(WorkflowDecl),
     Test(TestDecl),
+    Hook(HookDecl),
+    Prompt(PromptDecl),
+    Tool(ToolDecl),
+    Trigger(TriggerDecl),
+    EventHandler(EventHandlerDecl),
+    Config(ConfigDecl),
+    Secret(SecretDecl),
+    Export(ExportDecl),
+    ReExport(ReExportDecl),
+    /// `module helpers { ... }` nested in a file. Its `name` is the single
+    /// segment after `module`; it has no imports or comments of its own.
+    Module(Module),
+    Extension(ExtensionItem),
     Other(String),
 }
 
+impl Item {
+    /// Source range of the declaration; unrecognised trailing content has none.
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            Item::Record(decl) => Some(&decl.span),
+            Item::Interface(decl) => Some(&decl.span),
+            Item::Task(decl) => Some(&decl.span),
+            Item::Workflow(decl) => Some(&decl.span),
+            Item::Test(decl) => Some(&decl.span),
+            Item::Hook(decl) => Some(&decl.span),
+            Item::Prompt(decl) => Some(&decl.span),
+            Item::Tool(decl) => Some(&decl.span),
+            Item::Trigger(decl) => Some(&decl.span),
+            Item::EventHandler(decl) => Some(&decl.span),
+            Item::Config(decl) => Some(&decl.span),
+            Item::Secret(decl) => Some(&decl.span),
+            Item::Export(decl) => Some(&decl.span),
+            Item::ReExport(decl) => Some(&decl.span),
+            Item::Extension(item) => Some(&item.span),
+            Item::Module(module) => Some(&module.span),
+            Item::Other(_) => None,
+        }
+    }
+
+    /// Declared visibility; items that take no modifier are `Unspecified`.
+    pub fn visibility(&self) -> Visibility {
+        match self {
+            Item::Record(decl) => decl.visibility,
+            Item::Task(decl) => decl.visibility,
+            Item::Workflow(decl) => decl.visibility,
+            _ => Visibility::Unspecified,
+        }
+    }
+}
+
+/// An item introduced by a keyword registered through `ParseOptions::extensions`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionItem {
+    pub keyword: Ident,
+    /// Source text between the keyword and the opening brace.
+    pub header: String,
+    /// Source text inside the balanced braces.
+    pub body: String,
+    pub payload: ExtensionPayload,
+    pub span: Span,
+}
+
+/// Embedder-defined data returned by an item extension callback.
+///
+/// Payloads compare equal only when they share the same allocation.
+#[derive(Clone)]
+pub struct ExtensionPayload(Arc<dyn Any + Send + Sync>);
+
+impl ExtensionPayload {
+    pub fn new<T: Any + Send + Sync>(value: T) -> Self {
+        Self(Arc::new(value))
+    }
+
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+}
+
+impl fmt::Debug for ExtensionPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ExtensionPayload(..)")
+    }
+}
+
+impl PartialEq for ExtensionPayload {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ExtensionPayload {}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RecordDecl {
+    /// `///` lines above the declaration, without their markers. Fields,
+    /// parameters, tasks, and workflows keep theirs the same way.
+    pub docs: Option<String>,
+    /// `@deprecated` and similar markers above the declaration.
+    pub annotations: Vec<Annotation>,
+    pub visibility: Visibility,
     pub name: Ident,
-    pub type_params: Vec<Ident>,
+    pub type_params: Vec<TypeParam>,
+    /// Parent record named by `extends`, whose fields this record inherits.
+    pub extends: Option<QualifiedName>,
+    /// `where T: Serializable` between the header and the body.
+    pub constraints: Vec<TypeParam>,
     pub fields: Vec<RecordField>,
+    /// `fn` declarations in the record body, in source order.
+    pub methods: Vec<TaskDecl>,
+    pub span: Span,
+}
+
+/// A generic parameter of a record or task, such as `T` or
+/// `T: Serializable + Comparable[T]`; a type argument must satisfy every bound.
+/// Entries of a `where` clause use the same shape, always with bounds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeParam {
+    pub name: Ident,
+    pub bounds: Vec<TypeExpr>,
+}
+
+/// `@retry(max: 3, backoff: "exp")` or `@pure` before a task, method,
+/// workflow, record field, or statement. The declaration's own span starts
+/// after its annotations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub name: Ident,
+    pub args: Vec<AnnotationArg>,
+    pub span: Span,
+}
+
+/// One annotation argument, either positional (`30s`) or named (`max: 3`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotationArg {
+    pub name: Option<Ident>,
+    pub value: Expression,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RecordField {
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
     pub name: Ident,
     pub optional: bool,
     pub ty: TypeExpr,
+    pub span: Span,
+}
+
+/// `interface Summarizer { task summarize(text: String) -> String }`: task
+/// signatures that implementations are checked against. `trait` is accepted as
+/// a synonym for `interface`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceDecl {
+    pub name: Ident,
+    pub methods: Vec<MethodSig>,
+    pub span: Span,
+}
+
+/// A callable's signature without a body, as declared in an interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodSig {
+    pub name: Ident,
+    pub is_async: bool,
+    pub type_params: Vec<TypeParam>,
+    pub params: Vec<Param>,
+    pub return_type: Option<TypeExpr>,
+    pub span: Span,
+}
+
+/// `prompt ResearchPrompt { system: """...""", user: """...""" }`: named
+/// prompt sections whose `{placeholder}`s are filled in when rendered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptDecl {
+    pub name: Ident,
+    pub sections: Vec<PromptSection>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptSection {
+    pub name: Ident,
+    /// Section text with escapes decoded and triple-quoted strings dedented;
+    /// placeholders are left in place.
+    pub text: String,
+    /// Names inside `{...}`, such as `topic` or `brief.title`, in order of
+    /// first use.
+    pub placeholders: Vec<String>,
+    pub span: Span,
+}
+
+/// `tool web.search(query: String) -> List[SearchResult]`: the signature of a
+/// tool provided by the runtime, which tasks call by its qualified name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolDecl {
+    pub name: QualifiedName,
+    pub params: Vec<Param>,
+    pub return_type: Option<TypeExpr>,
+    pub span: Span,
+}
+
+/// `export { ProduceBrief, Brief }`: once a module has an export list, only
+/// the listed items and those marked `pub` are visible to importers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportDecl {
+    pub names: Vec<Ident>,
+    pub span: Span,
+}
+
+/// `export core.text { trim }` or `export * from core.text`: names exported by
+/// another module that importers of this one can use as if declared here.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReExportDecl {
+    pub path: QualifiedName,
+    /// Forwarded names; `None` for `*`.
+    pub members: Option<Vec<Ident>>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TaskDecl {
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub visibility: Visibility,
     pub name: Ident,
+    /// Declared `async task` (or `async fn` for methods): calls return a
+    /// pending result that callers `await`.
+    pub is_async: bool,
+    /// `<T>` in `task First<T>(items: List[T]) -> T?`.
+    pub type_params: Vec<TypeParam>,
     pub params: Vec<Param>,
     pub return_type: Option<TypeExpr>,
+    /// `where A: Mergeable[B]` after the return type, adding bounds to the
+    /// declared type parameters.
+    pub constraints: Vec<TypeParam>,
+    pub retry: Option<RetryPolicy>,
+    /// `timeout 45s` after any `retry` clause: the longest a single attempt may run.
+    pub timeout: Option<Duration>,
     pub body: Block,
+    pub span: Span,
+}
+
+/// `retry(max: 3, backoff: exponential(2s))` between a task's or step's
+/// signature and its body. Either argument may be left out, but not both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts after the first failure.
+    pub max: Option<u32>,
+    pub backoff: Option<Backoff>,
+    pub span: Span,
+}
+
+/// How long to wait before each retry, from `fixed(1s)`, `linear(1s)`, or
+/// `exponential(2s)`: the given delay, that delay times the attempt number,
+/// or that delay doubled on every attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+    Fixed(Duration),
+    Linear(Duration),
+    Exponential(Duration),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WorkflowDecl {
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub visibility: Visibility,
+    pub name: Ident,
+    /// The whole body. Its `raw` text still contains the step declarations,
+    /// but they are left out of its `statements`.
+    pub body: Block,
+    /// `step` declarations at the top level of the body, in source order.
+    pub steps: Vec<StepDecl>,
+    /// `on_error { ... }` at the top level of the body, run when the workflow fails.
+    pub on_error: Option<ErrorHandler>,
+    pub span: Span,
+}
+
+/// `step summarize(fetch) { ... }` in a workflow. `inputs` names the steps or
+/// values the step consumes, making the workflow's execution graph explicit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepDecl {
     pub name: Ident,
+    pub inputs: Vec<Ident>,
+    pub retry: Option<RetryPolicy>,
+    pub timeout: Option<Duration>,
+    /// The body with any `on_error` clause left out of its `statements`.
+    pub body: Block,
+    pub on_error: Option<ErrorHandler>,
+    pub span: Span,
+}
+
+/// `on_error { ... }` or `on_error(err) { ... }` inside a workflow or step
+/// body: compensation that runs when the enclosing workflow or step fails,
+/// with the error bound to `binding` when one is named.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorHandler {
+    pub binding: Option<Ident>,
     pub body: Block,
+    pub span: Span,
+}
+
+/// `pub` or `internal` before a record, task, method or workflow. Unmarked
+/// declarations stay importable, as they were before modifiers existed;
+/// `internal` ones are visible only inside their own module.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Visibility {
+    #[default]
+    Unspecified,
+    Public,
+    Internal,
+}
+
+impl Visibility {
+    /// The modifier as written in source, if any.
+    pub fn keyword(self) -> Option<&'static str> {
+        match self {
+            Visibility::Unspecified => None,
+            Visibility::Public => Some("pub"),
+            Visibility::Internal => Some("internal"),
+        }
+    }
+
+    /// Whether other modules may import the declaration.
+    pub fn is_exported(self) -> bool {
+        self != Visibility::Internal
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TestDecl {
     pub name: String,
     pub body: Block,
+    pub span: Span,
+}
+
+/// `hook LogAll around task * { ... }`: code the runtime runs before, after,
+/// or around every task whose name matches `pattern`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookDecl {
+    pub name: Ident,
+    pub position: HookPosition,
+    /// Task name pattern in which `*` matches any run of characters.
+    pub pattern: String,
+    pub body: Block,
+    pub span: Span,
+}
+
+/// `trigger Daily { cron: "0 9 * * *", run: Main }`: invokes the workflow or
+/// task named by `run` on a schedule, given as a five-field cron expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriggerDecl {
+    pub name: Ident,
+    pub cron: String,
+    pub run: QualifiedName,
+    pub span: Span,
+}
+
+/// `on event "document.uploaded" (doc) { ... }`: code the runtime runs for
+/// every external event whose name matches `pattern`, with the event's payload
+/// bound to `binding` when one is named.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventHandlerDecl {
+    /// Event name pattern in which `*` matches any run of characters.
+    pub pattern: String,
+    pub binding: Option<Ident>,
+    pub body: Block,
+    pub span: Span,
+}
+
+/// `config { default_model: "gpt-4o", region: env("HILO_REGION") }`:
+/// module-level settings as key/expression pairs, in source order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDecl {
+    pub entries: Vec<ConfigEntry>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigEntry {
+    pub key: Ident,
+    pub value: Expression,
+    pub span: Span,
+}
+
+/// `secret OPENAI_API_KEY` or `secret db_url from vault("prod/db")`: a value
+/// the runtime fetches from a secret store rather than from source. Without
+/// `from`, the runtime's default store is asked for `name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretDecl {
+    pub name: Ident,
+    pub source: Option<Expression>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookPosition {
+    Before,
+    After,
+    Around,
+}
+
+impl HookPosition {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookPosition::Before => "before",
+            HookPosition::After => "after",
+            HookPosition::Around => "around",
+        }
+    }
+}
+
+impl HookDecl {
+    /// Whether the hook wraps the task named `task`.
+    pub fn applies_to(&self, task: &str) -> bool {
+        let mut parts = self.pattern.split('*');
+        let first = parts.next().unwrap_or_default();
+        let Some(mut rest) = task.strip_prefix(first) else {
+            return false;
+        };
+        let parts: Vec<&str> = parts.collect();
+        let Some((last, middle)) = parts.split_last() else {
+            return rest.is_empty();
+        };
+        for part in middle {
+            match rest.find(part) {
+                Some(at) => rest = &rest[at + part.len()..],
+                None => return false,
+            }
+        }
+        rest.len() >= last.len() && rest.ends_with(last)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Param {
+    pub docs: Option<String>,
     pub name: Ident,
     pub ty: TypeExpr,
-    pub default: Option<String>,
+    /// The value passed when a caller omits the argument, as in `limit: Int = 10`.
+    pub default: Option<Expression>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Block {
     pub raw: String,
     pub statements: Vec<Statement>,
+    /// Source range of `raw`, i.e. the trimmed text between the braces.
+    pub span: Span,
+    /// Top-level statements of `raw` that the enclosing declaration holds
+    /// elsewhere, such as workflow steps and `on_error` clauses; they are not
+    /// part of `statements`.
+    pub lifted: Vec<Span>,
+}
+
+impl Block {
+    /// Parse the statements of `raw`, e.g. for blocks skipped by `ParseOptions::lazy_bodies`.
+    pub fn parse(&self) -> Vec<Statement> {
+        crate::parser::parse_block_statements(&self.raw, self.span.start, &self.lifted)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -83,16 +555,123 @@ pub enum Statement {
     Return {
         value: Option<Expression>,
     },
+    /// `throw ValidationError("missing topic")`: fails the enclosing task with
+    /// the given error unless a surrounding `try` catches it.
+    Throw(Expression),
+    /// `assert add(2, 3) == 5`: fails the enclosing test when the condition
+    /// is false.
+    Assert(Expression),
+    /// A nested `{ ... }` block, optionally introduced by a label such as `start`.
+    Block {
+        label: Option<Ident>,
+        body: Block,
+    },
+    /// `for item in items { ... }`
+    For {
+        binding: Ident,
+        iterable: Expression,
+        body: Block,
+    },
+    /// `while condition { ... }`
+    While {
+        condition: Expression,
+        body: Block,
+    },
+    /// `guard topic != "" else { return None }`: runs `else_body`, which is
+    /// expected to leave the enclosing body, when `condition` is false.
+    Guard {
+        condition: Expression,
+        else_body: Block,
+    },
+    /// `branch on result.status { "ok" => publish, else => retry }`: routes a
+    /// workflow to the step of the first arm whose pattern matches `subject`,
+    /// or to `otherwise` when none does.
+    Branch {
+        subject: Expression,
+        arms: Vec<BranchArm>,
+        otherwise: Option<Ident>,
+    },
+    /// `parallel { ... }`: each branch runs concurrently and the statement
+    /// completes when all of them have. Every top-level statement of the body
+    /// is one branch; a bare `{ ... }` groups several statements into one.
+    Parallel(Vec<Block>),
+    /// `for item in items parallel(max: 5) { ... }`: iterations run
+    /// concurrently, at most `limit` at a time, and their results are collected
+    /// in input order. Without `(max: ...)` the fan-out is unbounded.
+    ParallelFor {
+        binding: Ident,
+        iterable: Expression,
+        limit: Option<Expression>,
+        body: Block,
+    },
+    /// `try { ... } catch (e: ToolError) { ... } finally { ... }`: the first
+    /// catch clause whose type matches handles an error raised in `body`, and
+    /// `finally` runs either way.
+    Try {
+        body: Block,
+        catches: Vec<CatchClause>,
+        finally: Option<Block>,
+    },
+    /// `fn slugify(s: String) -> String { ... }`: a helper visible only inside
+    /// the enclosing body.
+    FnDecl(TaskDecl),
+    /// A statement preceded by decorators, such as `@parallel` before a
+    /// workflow step.
+    Annotated {
+        annotations: Vec<Annotation>,
+        statement: Box<Statement>,
+    },
     Expr(Expression),
 }
 
+/// `catch (e: ToolError) { ... }`; `catch (e)` and a bare `catch` handle any
+/// error, the latter without binding it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatchClause {
+    pub binding: Option<Ident>,
+    pub ty: Option<TypeExpr>,
+    pub body: Block,
+}
+
+/// A literal value, decoded from its source spelling.
+///
+/// Floats compare by their bit patterns, so equality stays reflexive for NaN
+/// and `Literal` can be `Eq`; `0.0` and `-0.0` are different literals.
+#[derive(Debug, Clone)]
+pub enum Literal {
+    /// String contents with escape sequences resolved.
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Null,
+    /// `30s`, `5m`, `250ms`, `2h`, or `1d`.
+    Duration(Duration),
+}
+
+impl PartialEq for Literal {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Literal::Str(a), Literal::Str(b)) => a == b,
+            (Literal::Int(a), Literal::Int(b)) => a == b,
+            (Literal::Float(a), Literal::Float(b)) => a.to_bits() == b.to_bits(),
+            (Literal::Bool(a), Literal::Bool(b)) => a == b,
+            (Literal::Null, Literal::Null) => true,
+            (Literal::Duration(a), Literal::Duration(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Literal {}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Expression {
     Identifier(Ident),
-    Literal(String),
+    Literal(Literal),
     Call {
         target: Box<Expression>,
-        args: Vec<Expression>,
+        args: Vec<CallArg>,
     },
     Member {
         target: Box<Expression>,
@@ -110,11 +689,92 @@ pub enum Expression {
         type_name: QualifiedName,
         fields: Vec<(Ident, Expression)>,
     },
+    /// `!done`, `-offset`, or `not ready`.
+    Unary {
+        op: String,
+        operand: Box<Expression>,
+    },
     Binary {
         left: Box<Expression>,
         op: String,
         right: Box<Expression>,
     },
+    /// `[a, b, ...rest]`
+    List(Vec<Expression>),
+    /// `(a, b)`: two or more values grouped without declaring a record.
+    Tuple(Vec<Expression>),
+    /// `...items` as a call argument or list element, expanding `items` in
+    /// place.
+    Spread(Box<Expression>),
+    /// `await fetch(url)`: waits for an asynchronous call to finish.
+    Await(Box<Expression>),
+    /// `match subject { pattern if guard => value ... }`
+    Match {
+        subject: Box<Expression>,
+        arms: Vec<MatchArm>,
+    },
+    /// `(item) => item.title`, `x => x + 1`, or `(a: Int, b: Int) => { ... }`.
+    Lambda {
+        params: Vec<LambdaParam>,
+        body: Box<ArmBody>,
+    },
+    Raw(String),
+}
+
+/// One call argument, either positional (`topic`) or named (`style: "brief"`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallArg {
+    pub name: Option<Ident>,
+    pub value: Expression,
+}
+
+/// `pattern => step` in a `branch on` statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchArm {
+    pub pattern: Pattern,
+    pub target: Ident,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expression>,
+    pub body: ArmBody,
+}
+
+/// The right-hand side of a match arm or lambda: `=> value` or `=> { ... }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArmBody {
+    Expr(Expression),
+    Block(Block),
+}
+
+/// A lambda parameter; without a type it takes whatever the caller passes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LambdaParam {
+    pub name: Ident,
+    pub ty: Option<TypeExpr>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// `_`
+    Wildcard,
+    /// A lowercase name, bound to the matched value.
+    Binding(Ident),
+    Literal(Literal),
+    /// `Ok(data)`, `Shape.Circle(r)`, or a bare capitalised name such as `None`.
+    Variant {
+        path: QualifiedName,
+        fields: Vec<Pattern>,
+    },
+    /// `Point { x, y: 0 }`; a field without a pattern binds its own name.
+    Record {
+        name: QualifiedName,
+        fields: Vec<(Ident, Option<Pattern>)>,
+    },
+    /// `(first, _)`
+    Tuple(Vec<Pattern>),
     Raw(String),
 }
 
@@ -126,7 +786,14 @@ pub enum TypeExpr {
         arguments: Vec<TypeExpr>,
     },
     List(Box<TypeExpr>),
+    /// `Map[K, V]`, `Map[K -> V]`, or `{ K: V }`.
+    Map {
+        key: Box<TypeExpr>,
+        value: Box<TypeExpr>,
+    },
     Struct(Vec<StructFieldType>),
+    /// `(String, Int)` or `Tuple[String, Int]`, with at least two elements.
+    Tuple(Vec<TypeExpr>),
     Optional(Box<TypeExpr>),
     Unknown(String),
 }
@@ -0,0 +1,675 @@
+//! Indented tree rendering of a module for snapshot tests.
+//!
+//! One node per line, children indented by two spaces. Spans, comments, and
+//! extension payloads are omitted, and field order is fixed here rather than
+//! derived from struct layout, so the output only changes when the parsed
+//! structure does.
+
+use std::fmt::Write as _;
+
+use super::*;
+
+/// Render `module` as a deterministic, diff-friendly tree.
+pub fn dump(module: &Module) -> String {
+    let mut out = Dumper::default();
+    match &module.name {
+        Some(name) => out.line(0, format_args!("Module {}", name.join("."))),
+        None => out.line(0, format_args!("Module")),
+    }
+    out.docs(1, &module.docs);
+    for import in &module.imports {
+        let mut text = format!("Import {}", import.path_text());
+        if let Some(version) = &import.version {
+            let _ = write!(text, " @ {version:?}");
+        }
+        if let Some(members) = &import.members {
+            let _ = write!(text, " {{ {} }}", members.join(", "));
+        }
+        if let Some(alias) = &import.alias {
+            let _ = write!(text, " as {alias}");
+        }
+        out.line(1, format_args!("{text}"));
+    }
+    for item in &module.items {
+        out.item(1, item);
+    }
+    out.text
+}
+
+#[derive(Default)]
+struct Dumper {
+    text: String,
+}
+
+impl Dumper {
+    fn line(&mut self, depth: usize, args: fmt::Arguments<'_>) {
+        for _ in 0..depth {
+            self.text.push_str("  ");
+        }
+        let _ = self.text.write_fmt(args);
+        self.text.push('\n');
+    }
+
+    fn item(&mut self, depth: usize, item: &Item) {
+        match item {
+            Item::Record(decl) => {
+                let mut header = format!(
+                    "Record {}{}{}",
+                    visibility(decl.visibility),
+                    decl.name,
+                    render_type_params(&decl.type_params)
+                );
+                if let Some(parent) = &decl.extends {
+                    let _ = write!(header, " extends {}", parent.join("."));
+                }
+                self.line(depth, format_args!("{header}"));
+                self.docs(depth + 1, &decl.docs);
+                self.annotations(depth + 1, &decl.annotations);
+                self.constraints(depth + 1, &decl.constraints);
+                for field in &decl.fields {
+                    let optional = if field.optional { "?" } else { "" };
+                    let ty = render_type(&field.ty);
+                    self.line(
+                        depth + 1,
+                        format_args!("Field {}{optional}: {ty}", field.name),
+                    );
+                    self.docs(depth + 2, &field.docs);
+                    self.annotations(depth + 2, &field.annotations);
+                }
+                for method in &decl.methods {
+                    self.task(depth + 1, "Method", method);
+                }
+            }
+            Item::Interface(decl) => {
+                self.line(depth, format_args!("Interface {}", decl.name));
+                for method in &decl.methods {
+                    self.signature(
+                        depth + 1,
+                        "Signature",
+                        &format!("{}{}", method.name, render_type_params(&method.type_params)),
+                        method.is_async,
+                        &method.params,
+                        &method.return_type,
+                    );
+                }
+            }
+            Item::Task(decl) => self.task(depth, "Task", decl),
+            Item::Workflow(decl) => {
+                self.line(
+                    depth,
+                    format_args!("Workflow {}{}", visibility(decl.visibility), decl.name),
+                );
+                self.docs(depth + 1, &decl.docs);
+                self.annotations(depth + 1, &decl.annotations);
+                self.block(depth + 1, &decl.body);
+                for step in &decl.steps {
+                    match step.inputs.as_slice() {
+                        [] => self.line(depth + 1, format_args!("Step {}", step.name)),
+                        inputs => self.line(
+                            depth + 1,
+                            format_args!("Step {}({})", step.name, inputs.join(", ")),
+                        ),
+                    }
+                    self.retry(depth + 2, &step.retry);
+                    self.timeout(depth + 2, step.timeout);
+                    self.block(depth + 2, &step.body);
+                    self.on_error(depth + 2, &step.on_error);
+                }
+                self.on_error(depth + 1, &decl.on_error);
+            }
+            Item::Test(decl) => {
+                self.line(depth, format_args!("Test {:?}", decl.name));
+                self.block(depth + 1, &decl.body);
+            }
+            Item::Hook(decl) => {
+                self.line(
+                    depth,
+                    format_args!(
+                        "Hook {} {} task {}",
+                        decl.name,
+                        decl.position.as_str(),
+                        decl.pattern
+                    ),
+                );
+                self.block(depth + 1, &decl.body);
+            }
+            Item::Prompt(decl) => {
+                self.line(depth, format_args!("Prompt {}", decl.name));
+                for section in &decl.sections {
+                    self.line(
+                        depth + 1,
+                        format_args!("Section {} {:?}", section.name, section.text),
+                    );
+                    for placeholder in &section.placeholders {
+                        self.line(depth + 2, format_args!("Placeholder {placeholder}"));
+                    }
+                }
+            }
+            Item::Tool(decl) => self.signature(
+                depth,
+                "Tool",
+                &decl.name.join("."),
+                false,
+                &decl.params,
+                &decl.return_type,
+            ),
+            Item::Trigger(decl) => {
+                self.line(depth, format_args!("Trigger {}", decl.name));
+                self.line(depth + 1, format_args!("Cron {:?}", decl.cron));
+                self.line(depth + 1, format_args!("Run {}", decl.run.join(".")));
+            }
+            Item::Secret(decl) => {
+                self.line(depth, format_args!("Secret {}", decl.name));
+                if let Some(source) = &decl.source {
+                    self.line(depth + 1, format_args!("From"));
+                    self.expression(depth + 2, source);
+                }
+            }
+            Item::Config(decl) => {
+                self.line(depth, format_args!("Config"));
+                for entry in &decl.entries {
+                    self.line(depth + 1, format_args!("Entry {}", entry.key));
+                    self.expression(depth + 2, &entry.value);
+                }
+            }
+            Item::EventHandler(decl) => {
+                match &decl.binding {
+                    Some(binding) => self.line(
+                        depth,
+                        format_args!("EventHandler {:?} ({binding})", decl.pattern),
+                    ),
+                    None => self.line(depth, format_args!("EventHandler {:?}", decl.pattern)),
+                }
+                self.block(depth + 1, &decl.body);
+            }
+            Item::Export(decl) => {
+                self.line(depth, format_args!("Export {}", decl.names.join(", ")));
+            }
+            Item::ReExport(decl) => match &decl.members {
+                Some(members) => self.line(
+                    depth,
+                    format_args!(
+                        "ReExport {} {{ {} }}",
+                        decl.path.join("."),
+                        members.join(", ")
+                    ),
+                ),
+                None => self.line(
+                    depth,
+                    format_args!("ReExport * from {}", decl.path.join(".")),
+                ),
+            },
+            Item::Module(module) => {
+                let name = module.name.as_deref().unwrap_or_default().join(".");
+                self.line(depth, format_args!("Module {name}"));
+                self.docs(depth + 1, &module.docs);
+                for item in &module.items {
+                    self.item(depth + 1, item);
+                }
+            }
+            Item::Extension(item) => {
+                self.line(depth, format_args!("Extension {}", item.keyword));
+                if !item.header.is_empty() {
+                    self.line(depth + 1, format_args!("Header {}", one_line(&item.header)));
+                }
+                self.line(depth + 1, format_args!("Body {}", one_line(&item.body)));
+            }
+            Item::Other(text) => self.line(depth, format_args!("Other {}", one_line(text))),
+        }
+    }
+
+    fn task(&mut self, depth: usize, kind: &str, decl: &TaskDecl) {
+        self.signature(
+            depth,
+            format!("{kind} {}", visibility(decl.visibility)).trim_end(),
+            &format!("{}{}", decl.name, render_type_params(&decl.type_params)),
+            decl.is_async,
+            &decl.params,
+            &decl.return_type,
+        );
+        self.docs(depth + 1, &decl.docs);
+        self.constraints(depth + 1, &decl.constraints);
+        self.retry(depth + 1, &decl.retry);
+        self.timeout(depth + 1, decl.timeout);
+        self.annotations(depth + 1, &decl.annotations);
+        self.block(depth + 1, &decl.body);
+    }
+
+    fn annotations(&mut self, depth: usize, annotations: &[Annotation]) {
+        for annotation in annotations {
+            self.line(depth, format_args!("Annotation {}", annotation.name));
+            for arg in &annotation.args {
+                match &arg.name {
+                    Some(name) => {
+                        self.line(depth + 1, format_args!("Arg {name}"));
+                        self.expression(depth + 2, &arg.value);
+                    }
+                    None => self.expression(depth + 1, &arg.value),
+                }
+            }
+        }
+    }
+
+    fn docs(&mut self, depth: usize, docs: &Option<String>) {
+        if let Some(docs) = docs {
+            self.line(depth, format_args!("Docs {docs:?}"));
+        }
+    }
+
+    fn retry(&mut self, depth: usize, policy: &Option<RetryPolicy>) {
+        if let Some(policy) = policy {
+            let max = policy.max.map(|max| format!(" max={max}"));
+            let backoff = policy
+                .backoff
+                .map(|backoff| format!(" backoff={backoff:?}"));
+            self.line(
+                depth,
+                format_args!(
+                    "Retry{}{}",
+                    max.unwrap_or_default(),
+                    backoff.unwrap_or_default()
+                ),
+            );
+        }
+    }
+
+    fn timeout(&mut self, depth: usize, timeout: Option<Duration>) {
+        if let Some(timeout) = timeout {
+            self.line(depth, format_args!("Timeout {timeout:?}"));
+        }
+    }
+
+    fn on_error(&mut self, depth: usize, handler: &Option<ErrorHandler>) {
+        if let Some(handler) = handler {
+            match &handler.binding {
+                Some(binding) => self.line(depth, format_args!("OnError {binding}")),
+                None => self.line(depth, format_args!("OnError")),
+            }
+            self.block(depth + 1, &handler.body);
+        }
+    }
+
+    fn constraints(&mut self, depth: usize, constraints: &[TypeParam]) {
+        for constraint in constraints {
+            self.line(
+                depth,
+                format_args!("Where {}", render_type_param(constraint)),
+            );
+        }
+    }
+
+    fn signature(
+        &mut self,
+        depth: usize,
+        kind: &str,
+        name: &str,
+        is_async: bool,
+        params: &[Param],
+        return_type: &Option<TypeExpr>,
+    ) {
+        let modifier = if is_async { "async " } else { "" };
+        match return_type {
+            Some(ty) => {
+                let ty = render_type(ty);
+                self.line(depth, format_args!("{kind} {modifier}{name} -> {ty}"));
+            }
+            None => self.line(depth, format_args!("{kind} {modifier}{name}")),
+        }
+        for param in params {
+            let ty = render_type(&param.ty);
+            match &param.default {
+                Some(default) => {
+                    self.line(depth + 1, format_args!("Param {}: {ty} =", param.name));
+                    self.docs(depth + 2, &param.docs);
+                    self.expression(depth + 2, default);
+                }
+                None => {
+                    self.line(depth + 1, format_args!("Param {}: {ty}", param.name));
+                    self.docs(depth + 2, &param.docs);
+                }
+            }
+        }
+    }
+
+    fn block(&mut self, depth: usize, block: &Block) {
+        self.line(depth, format_args!("Block"));
+        for statement in &block.statements {
+            self.statement(depth + 1, statement);
+        }
+    }
+
+    fn statement(&mut self, depth: usize, statement: &Statement) {
+        match statement {
+            Statement::Let { name, ty, value } => {
+                match ty {
+                    Some(ty) => {
+                        let ty = render_type(ty);
+                        self.line(depth, format_args!("Let {name}: {ty}"));
+                    }
+                    None => self.line(depth, format_args!("Let {name}")),
+                }
+                if let Some(value) = value {
+                    self.expression(depth + 1, value);
+                }
+            }
+            Statement::Return { value } => {
+                self.line(depth, format_args!("Return"));
+                if let Some(value) = value {
+                    self.expression(depth + 1, value);
+                }
+            }
+            Statement::Throw(value) => {
+                self.line(depth, format_args!("Throw"));
+                self.expression(depth + 1, value);
+            }
+            Statement::Assert(condition) => {
+                self.line(depth, format_args!("Assert"));
+                self.expression(depth + 1, condition);
+            }
+            Statement::Block { label, body } => {
+                if let Some(label) = label {
+                    self.line(depth, format_args!("Labeled {label}"));
+                    self.block(depth + 1, body);
+                } else {
+                    self.block(depth, body);
+                }
+            }
+            Statement::For {
+                binding,
+                iterable,
+                body,
+            } => {
+                self.line(depth, format_args!("For {binding}"));
+                self.expression(depth + 1, iterable);
+                self.block(depth + 1, body);
+            }
+            Statement::While { condition, body } => {
+                self.line(depth, format_args!("While"));
+                self.expression(depth + 1, condition);
+                self.block(depth + 1, body);
+            }
+            Statement::Guard {
+                condition,
+                else_body,
+            } => {
+                self.line(depth, format_args!("Guard"));
+                self.expression(depth + 1, condition);
+                self.line(depth + 1, format_args!("Else"));
+                self.block(depth + 2, else_body);
+            }
+            Statement::Try {
+                body,
+                catches,
+                finally,
+            } => {
+                self.line(depth, format_args!("Try"));
+                self.block(depth + 1, body);
+                for catch in catches {
+                    match (&catch.binding, &catch.ty) {
+                        (Some(binding), Some(ty)) => self.line(
+                            depth + 1,
+                            format_args!("Catch {binding}: {}", render_type(ty)),
+                        ),
+                        (Some(binding), None) => {
+                            self.line(depth + 1, format_args!("Catch {binding}"));
+                        }
+                        (None, _) => self.line(depth + 1, format_args!("Catch")),
+                    }
+                    self.block(depth + 2, &catch.body);
+                }
+                if let Some(finally) = finally {
+                    self.line(depth + 1, format_args!("Finally"));
+                    self.block(depth + 2, finally);
+                }
+            }
+            Statement::Branch {
+                subject,
+                arms,
+                otherwise,
+            } => {
+                self.line(depth, format_args!("Branch"));
+                self.expression(depth + 1, subject);
+                for arm in arms {
+                    let pattern = render_pattern(&arm.pattern);
+                    self.line(depth + 1, format_args!("Arm {pattern} => {}", arm.target));
+                }
+                if let Some(target) = otherwise {
+                    self.line(depth + 1, format_args!("Else => {target}"));
+                }
+            }
+            Statement::Parallel(branches) => {
+                self.line(depth, format_args!("Parallel"));
+                for branch in branches {
+                    self.block(depth + 1, branch);
+                }
+            }
+            Statement::ParallelFor {
+                binding,
+                iterable,
+                limit,
+                body,
+            } => {
+                self.line(depth, format_args!("ParallelFor {binding}"));
+                self.expression(depth + 1, iterable);
+                if let Some(limit) = limit {
+                    self.line(depth + 1, format_args!("Limit"));
+                    self.expression(depth + 2, limit);
+                }
+                self.block(depth + 1, body);
+            }
+            Statement::FnDecl(decl) => self.task(depth, "Fn", decl),
+            Statement::Annotated {
+                annotations,
+                statement,
+            } => {
+                self.line(depth, format_args!("Annotated"));
+                self.annotations(depth + 1, annotations);
+                self.statement(depth + 1, statement);
+            }
+            Statement::Expr(expr) => self.expression(depth, expr),
+        }
+    }
+
+    fn expression(&mut self, depth: usize, expr: &Expression) {
+        match expr {
+            Expression::Identifier(name) => self.line(depth, format_args!("Identifier {name}")),
+            Expression::Literal(literal) => {
+                self.line(depth, format_args!("Literal {}", render_literal(literal)));
+            }
+            Expression::Call { target, args } => {
+                self.line(depth, format_args!("Call"));
+                self.expression(depth + 1, target);
+                for arg in args {
+                    match &arg.name {
+                        Some(name) => {
+                            self.line(depth + 1, format_args!("Arg {name}"));
+                            self.expression(depth + 2, &arg.value);
+                        }
+                        None => self.expression(depth + 1, &arg.value),
+                    }
+                }
+            }
+            Expression::Member { target, property } => {
+                self.line(depth, format_args!("Member .{property}"));
+                self.expression(depth + 1, target);
+            }
+            Expression::Index { target, index } => {
+                self.line(depth, format_args!("Index"));
+                self.expression(depth + 1, target);
+                self.expression(depth + 1, index);
+            }
+            Expression::OptionalChain { target, property } => {
+                self.line(depth, format_args!("OptionalChain ?.{property}"));
+                self.expression(depth + 1, target);
+            }
+            Expression::StructLiteral { type_name, fields } => {
+                self.line(depth, format_args!("StructLiteral {}", type_name.join(".")));
+                for (name, value) in fields {
+                    self.line(depth + 1, format_args!("Field {name}"));
+                    self.expression(depth + 2, value);
+                }
+            }
+            Expression::Unary { op, operand } => {
+                self.line(depth, format_args!("Unary {op}"));
+                self.expression(depth + 1, operand);
+            }
+            Expression::List(elements) => {
+                self.line(depth, format_args!("List"));
+                for element in elements {
+                    self.expression(depth + 1, element);
+                }
+            }
+            Expression::Tuple(elements) => {
+                self.line(depth, format_args!("Tuple"));
+                for element in elements {
+                    self.expression(depth + 1, element);
+                }
+            }
+            Expression::Spread(operand) => {
+                self.line(depth, format_args!("Spread"));
+                self.expression(depth + 1, operand);
+            }
+            Expression::Await(operand) => {
+                self.line(depth, format_args!("Await"));
+                self.expression(depth + 1, operand);
+            }
+            Expression::Binary { left, op, right } => {
+                self.line(depth, format_args!("Binary {op}"));
+                self.expression(depth + 1, left);
+                self.expression(depth + 1, right);
+            }
+            Expression::Match { subject, arms } => {
+                self.line(depth, format_args!("Match"));
+                self.expression(depth + 1, subject);
+                for arm in arms {
+                    let pattern = render_pattern(&arm.pattern);
+                    self.line(depth + 1, format_args!("Arm {pattern}"));
+                    if let Some(guard) = &arm.guard {
+                        self.line(depth + 2, format_args!("Guard"));
+                        self.expression(depth + 3, guard);
+                    }
+                    self.arm_body(depth + 2, &arm.body);
+                }
+            }
+            Expression::Lambda { params, body } => {
+                let params: Vec<String> = params
+                    .iter()
+                    .map(|param| match &param.ty {
+                        Some(ty) => format!("{}: {}", param.name, render_type(ty)),
+                        None => param.name.clone(),
+                    })
+                    .collect();
+                self.line(depth, format_args!("Lambda ({})", params.join(", ")));
+                self.arm_body(depth + 1, body);
+            }
+            Expression::Raw(text) => self.line(depth, format_args!("Raw {}", one_line(text))),
+        }
+    }
+
+    fn arm_body(&mut self, depth: usize, body: &ArmBody) {
+        match body {
+            ArmBody::Expr(value) => self.expression(depth, value),
+            ArmBody::Block(body) => self.block(depth, body),
+        }
+    }
+}
+
+/// Source-like rendering of a match pattern, e.g. `Ok(data)`.
+fn render_pattern(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Wildcard => "_".to_string(),
+        Pattern::Binding(name) => name.clone(),
+        Pattern::Literal(literal) => render_literal(literal),
+        Pattern::Variant { path, fields } if fields.is_empty() => path.join("."),
+        Pattern::Variant { path, fields } => {
+            let fields: Vec<String> = fields.iter().map(render_pattern).collect();
+            format!("{}({})", path.join("."), fields.join(", "))
+        }
+        Pattern::Record { name, fields } => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(field, pattern)| match pattern {
+                    Some(pattern) => format!("{field}: {}", render_pattern(pattern)),
+                    None => field.clone(),
+                })
+                .collect();
+            format!("{} {{ {} }}", name.join("."), fields.join(", "))
+        }
+        Pattern::Tuple(items) => {
+            let items: Vec<String> = items.iter().map(render_pattern).collect();
+            format!("({})", items.join(", "))
+        }
+        Pattern::Raw(text) => one_line(text),
+    }
+}
+
+fn render_literal(literal: &Literal) -> String {
+    match literal {
+        Literal::Str(text) => format!("{text:?}"),
+        Literal::Int(value) => value.to_string(),
+        Literal::Float(value) => format!("{value:?}"),
+        Literal::Bool(value) => value.to_string(),
+        Literal::Null => "null".to_string(),
+        Literal::Duration(value) => format!("{value:?}"),
+    }
+}
+
+/// `pub ` or `internal ` ahead of a declaration's name.
+fn visibility(visibility: Visibility) -> String {
+    visibility
+        .keyword()
+        .map_or_else(String::new, |keyword| format!("{keyword} "))
+}
+
+/// `<T: Bound + Other, U>`, or nothing for a non-generic declaration.
+fn render_type_params(params: &[TypeParam]) -> String {
+    if params.is_empty() {
+        return String::new();
+    }
+    let params: Vec<String> = params.iter().map(render_type_param).collect();
+    format!("<{}>", params.join(", "))
+}
+
+fn render_type_param(param: &TypeParam) -> String {
+    let bounds: Vec<String> = param.bounds.iter().map(render_type).collect();
+    match bounds.is_empty() {
+        true => param.name.clone(),
+        false => format!("{}: {}", param.name, bounds.join(" + ")),
+    }
+}
+
+/// Source-like rendering of a type, e.g. `List[Map[String, Int]]?`.
+fn render_type(ty: &TypeExpr) -> String {
+    match ty {
+        TypeExpr::Simple(path) => path.join("."),
+        TypeExpr::Generic { base, arguments } => {
+            let args: Vec<String> = arguments.iter().map(render_type).collect();
+            format!("{}[{}]", base.join("."), args.join(", "))
+        }
+        TypeExpr::List(item) => format!("List[{}]", render_type(item)),
+        TypeExpr::Map { key, value } => {
+            format!("Map[{}, {}]", render_type(key), render_type(value))
+        }
+        TypeExpr::Struct(fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|field| {
+                    let optional = if field.optional { "?" } else { "" };
+                    format!("{}{optional}: {}", field.name, render_type(&field.ty))
+                })
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+        TypeExpr::Tuple(elements) => {
+            let elements: Vec<String> = elements.iter().map(render_type).collect();
+            format!("({})", elements.join(", "))
+        }
+        TypeExpr::Optional(inner) => format!("{}?", render_type(inner)),
+        TypeExpr::Unknown(raw) => format!("<unknown {}>", one_line(raw)),
+    }
+}
+
+/// Collapses whitespace runs so multi-line source text stays on one dump line.
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
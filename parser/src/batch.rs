@@ -0,0 +1,61 @@
+//! Parsing many independent sources across worker threads.
+
+use std::num::NonZeroUsize;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::{ast, error::HiloParseError, options::ParseOptions, parser};
+
+/// Parses every source, returning results in input order regardless of which
+/// worker finished first.
+pub(crate) fn parse_many<S>(
+    sources: &[S],
+    options: &ParseOptions,
+) -> Vec<Result<ast::Module, HiloParseError>>
+where
+    S: AsRef<str> + Sync,
+{
+    let workers = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(sources.len());
+    if workers <= 1 {
+        return sources
+            .iter()
+            .map(|source| parser::parse_module(source.as_ref(), options))
+            .collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Option<Result<ast::Module, HiloParseError>>> =
+        (0..sources.len()).map(|_| None).collect();
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut parsed = Vec::new();
+                    loop {
+                        let idx = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(source) = sources.get(idx) else {
+                            break;
+                        };
+                        parsed.push((idx, parser::parse_module(source.as_ref(), options)));
+                    }
+                    parsed
+                })
+            })
+            .collect();
+        for handle in handles {
+            let parsed = handle
+                .join()
+                .unwrap_or_else(|payload| panic::resume_unwind(payload));
+            for (idx, result) in parsed {
+                results[idx] = Some(result);
+            }
+        }
+    });
+    results
+        .into_iter()
+        .map(|result| result.expect("every source is claimed by a worker"))
+        .collect()
+}
@@ -0,0 +1,50 @@
+//! `hilo bundle`: write a project's single-file build.
+
+use std::{env, fs, process::ExitCode};
+
+use parser::bundle::{self, BundleOptions};
+
+const USAGE: &str = "Usage: hilo bundle [project-dir] [--out <file>] [--strip-unused]";
+
+fn main() -> ExitCode {
+    let mut root = None;
+    let mut out = None;
+    let mut options = BundleOptions::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strip-unused" => options.strip_unused = true,
+            "--out" | "-o" => match args.next() {
+                Some(path) => out = Some(path),
+                None => return usage(),
+            },
+            "-h" | "--help" => return usage(),
+            flag if flag.starts_with('-') => return usage(),
+            _ if root.is_some() => return usage(),
+            _ => root = Some(arg),
+        }
+    }
+
+    let bundled = match bundle::bundle_project(root.as_deref().unwrap_or("."), &options) {
+        Ok(bundled) => bundled,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match out {
+        Some(path) => {
+            if let Err(err) = fs::write(&path, bundled) {
+                eprintln!("Error: cannot write {path}: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+        None => print!("{bundled}"),
+    }
+    ExitCode::SUCCESS
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{USAGE}");
+    ExitCode::FAILURE
+}
@@ -0,0 +1,1145 @@
+//! Single-file builds: a module and everything it imports, merged into one
+//! module with no project imports left.
+//!
+//! References are rewritten on the AST, so the result is meant to be rendered
+//! with [`crate::printer::print_module`]; spans and `Block::raw` still point
+//! into the original sources. Expressions the parser keeps as
+//! [`ast::Expression::Raw`] are copied verbatim, and locals that shadow an
+//! item name are renamed along with it. [`bundle_project`] loads a project
+//! from disk and backs the `hilo bundle` command.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{ast, error::HiloParseError, options::ParseOptions, parser, printer, semantic, stdlib};
+
+/// Entry file used when `hilo.toml` is missing or names none.
+pub const DEFAULT_ENTRY: &str = "src/main.hilo";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BundleOptions {
+    /// Drop dependency items that nothing in the entry module, or in a
+    /// dependency's hooks and triggers, reaches.
+    pub strip_unused: bool,
+}
+
+/// Merge `entry` and the modules it transitively imports from `modules`.
+///
+/// Items keep their names unless they collide with an earlier one, in which
+/// case they become `<module_path>_<name>` (e.g. `lib_text_Brief`); entry
+/// items are never renamed. Imports of the embedded `core.*` modules are kept,
+/// since the runtime provides them, and re-exports of them become imports.
+/// Tests and export lists of dependencies are dropped, as are all re-exports
+/// once followed. Importing an item its module does not export by name is an
+/// error; qualified references to one are left unresolved. Relative imports
+/// are reported as unresolved, since modules are matched by declared name.
+pub fn bundle(
+    entry: &ast::Module,
+    modules: &[ast::Module],
+    options: &BundleOptions,
+) -> Result<ast::Module, HiloParseError> {
+    let units = collect_units(entry, modules)?;
+
+    // Final name of every item, keyed by (unit, declared name).
+    let mut names: HashMap<(usize, &str), String> = HashMap::new();
+    let mut taken: HashSet<String> = HashSet::new();
+    for (idx, unit) in units.iter().enumerate() {
+        for item in &unit.items {
+            if idx > 0 && matches!(item, ast::Item::Test(_)) {
+                continue;
+            }
+            let Some(name) = item_name(item) else {
+                continue;
+            };
+            let mut final_name = name.to_string();
+            if taken.contains(&final_name) {
+                let prefix = unit
+                    .name
+                    .as_ref()
+                    .map_or("module".to_string(), |path| path.join("_"));
+                final_name = format!("{prefix}_{name}");
+                let mut counter = 2;
+                while taken.contains(&final_name) {
+                    final_name = format!("{prefix}_{name}{counter}");
+                    counter += 1;
+                }
+            }
+            taken.insert(final_name.clone());
+            names.insert((idx, name), final_name);
+        }
+    }
+
+    let mut imports: Vec<ast::Import> = Vec::new();
+    let mut parts: Vec<Vec<ast::Item>> = Vec::new();
+    for (idx, unit) in units.iter().enumerate() {
+        let resolver = Resolver::new(idx, unit, &units, &names)?;
+        for import in &unit.imports {
+            if is_runtime_module(&import.path) && !imports.contains(import) {
+                imports.push(import.clone());
+            }
+        }
+        for import in runtime_reexports(unit) {
+            if !imports.contains(&import) {
+                imports.push(import);
+            }
+        }
+        let mut items = Vec::new();
+        for item in &unit.items {
+            if idx > 0 && matches!(item, ast::Item::Test(_) | ast::Item::Export(_)) {
+                continue;
+            }
+            if matches!(item, ast::Item::ReExport(_)) {
+                continue;
+            }
+            let mut item = item.clone();
+            resolver.item(&mut item);
+            items.push(item);
+        }
+        parts.push(items);
+    }
+
+    if options.strip_unused {
+        strip_unused(&mut parts);
+    }
+
+    // Dependencies first, in discovery order, then the entry module.
+    let mut items: Vec<ast::Item> = parts.drain(1..).flatten().collect();
+    items.append(&mut parts[0]);
+    Ok(ast::Module {
+        docs: entry.docs.clone(),
+        name: entry.name.clone(),
+        imports,
+        items,
+        comments: Vec::new(),
+        span: 0..0,
+    })
+}
+
+/// Bundle the project rooted at `root` and return the printed single-file build.
+///
+/// The entry file is the `entry` key of `root/hilo.toml` (as written by
+/// `hilo new`), falling back to [`DEFAULT_ENTRY`]. Every other `.hilo` file
+/// under `root` is loaded as a candidate dependency; hidden directories and
+/// `target/` are skipped. Files are parsed strictly, so content the parser does
+/// not recognize fails the build instead of being copied into it, and parse
+/// errors name the file they came from.
+pub fn bundle_project(
+    root: impl AsRef<Path>,
+    options: &BundleOptions,
+) -> Result<String, HiloParseError> {
+    let root = root.as_ref();
+    let entry_path = root.join(manifest_entry(root)?.as_deref().unwrap_or(DEFAULT_ENTRY));
+    let entry = parse_file(&entry_path)?;
+
+    let mut files = Vec::new();
+    collect_sources(root, &mut files)?;
+    files.sort();
+    let entry_file = fs::canonicalize(&entry_path)?;
+    let mut modules = Vec::new();
+    for file in files {
+        if fs::canonicalize(&file)? != entry_file {
+            modules.push(parse_file(&file)?);
+        }
+    }
+    Ok(printer::print_module(&bundle(&entry, &modules, options)?))
+}
+
+/// The `entry = "..."` value from `root/hilo.toml`, if the manifest has one.
+fn manifest_entry(root: &Path) -> Result<Option<String>, HiloParseError> {
+    let manifest = match fs::read_to_string(root.join("hilo.toml")) {
+        Ok(manifest) => manifest,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(manifest.lines().find_map(|line| {
+        let value = line
+            .trim()
+            .strip_prefix("entry")?
+            .trim_start()
+            .strip_prefix('=')?;
+        let value = value.trim().strip_prefix('"')?;
+        Some(value[..value.find('"')?].to_string())
+    }))
+}
+
+fn collect_sources(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), HiloParseError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                collect_sources(&path, files)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext == "hilo") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn parse_file(path: &Path) -> Result<ast::Module, HiloParseError> {
+    let source = fs::read_to_string(path)?;
+    let options = ParseOptions {
+        strict: true,
+        ..ParseOptions::default()
+    };
+    parser::parse_module(&source, &options).map_err(|err| match err {
+        HiloParseError::Io(err) => HiloParseError::Io(err),
+        err => HiloParseError::Parse(format!("{}: {err}", path.display())),
+    })
+}
+
+/// `entry` followed by every project module reachable from its imports and
+/// re-exports, in breadth-first order.
+fn collect_units<'a>(
+    entry: &'a ast::Module,
+    modules: &'a [ast::Module],
+) -> Result<Vec<&'a ast::Module>, HiloParseError> {
+    let mut units = vec![entry];
+    let mut queue = VecDeque::from([entry]);
+    while let Some(unit) = queue.pop_front() {
+        // Modules are matched by declared name; file-relative paths need a loader.
+        if let Some(import) = unit.imports.iter().find(|import| import.is_relative()) {
+            return Err(HiloParseError::UnresolvedImport(import.path_text()));
+        }
+        let reexported = reexports(unit).map(|decl| &decl.path);
+        for path in unit
+            .imports
+            .iter()
+            .map(|import| &import.path)
+            .chain(reexported)
+        {
+            if units.iter().any(|seen| seen.name.as_ref() == Some(path)) {
+                continue;
+            }
+            match modules
+                .iter()
+                .find(|module| module.name.as_ref() == Some(path))
+            {
+                Some(module) => {
+                    units.push(module);
+                    queue.push_back(module);
+                }
+                None if is_runtime_module(path) => {}
+                None => {
+                    return Err(HiloParseError::UnresolvedImport(path.join(".")));
+                }
+            }
+        }
+    }
+    Ok(units)
+}
+
+fn reexports(unit: &ast::Module) -> impl Iterator<Item = &ast::ReExportDecl> {
+    unit.items.iter().filter_map(|item| match item {
+        ast::Item::ReExport(decl) => Some(decl),
+        _ => None,
+    })
+}
+
+/// Imports that keep names re-exported from `core.*` modules available once
+/// the re-exporting module is merged away.
+fn runtime_reexports(unit: &ast::Module) -> Vec<ast::Import> {
+    reexports(unit)
+        .filter(|decl| is_runtime_module(&decl.path))
+        .map(|decl| {
+            let members = decl.members.clone().unwrap_or_else(|| {
+                stdlib::load(&decl.path.join("."))
+                    .and_then(Result::ok)
+                    .map(|module| {
+                        module
+                            .items
+                            .iter()
+                            .filter_map(item_name)
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            });
+            ast::Import {
+                path: decl.path.clone(),
+                version: None,
+                members: Some(members),
+                alias: None,
+            }
+        })
+        .collect()
+}
+
+/// Final names of everything `units[target]` exports: its own visible items
+/// plus whatever it re-exports from other project modules.
+fn exports(
+    target: usize,
+    units: &[&ast::Module],
+    names: &HashMap<(usize, &str), String>,
+    visiting: &mut Vec<usize>,
+) -> Result<HashMap<String, String>, HiloParseError> {
+    if visiting.contains(&target) {
+        return Ok(HashMap::new());
+    }
+    visiting.push(target);
+    let unit = units[target];
+    let mut exported: HashMap<String, String> = names
+        .iter()
+        .filter(|((idx, _), _)| *idx == target)
+        .filter(|((_, name), _)| {
+            unit.items.iter().any(|item| {
+                item_name(item) == Some(*name)
+                    && semantic::is_exported(unit, name, item.visibility())
+            })
+        })
+        .map(|((_, name), final_name)| (name.to_string(), final_name.clone()))
+        .collect();
+    for decl in reexports(unit) {
+        let Some(source) = units
+            .iter()
+            .position(|module| module.name.as_ref() == Some(&decl.path))
+        else {
+            continue;
+        };
+        let forwarded = exports(source, units, names, visiting)?;
+        match &decl.members {
+            Some(members) => {
+                for member in members {
+                    match forwarded.get(member) {
+                        Some(final_name) => {
+                            exported.insert(member.clone(), final_name.clone());
+                        }
+                        None if names.contains_key(&(source, member.as_str())) => {
+                            return Err(HiloParseError::InternalImport {
+                                module: decl.path.join("."),
+                                name: member.clone(),
+                            });
+                        }
+                        None => {}
+                    }
+                }
+            }
+            None => exported.extend(forwarded),
+        }
+    }
+    visiting.pop();
+    Ok(exported)
+}
+
+fn is_runtime_module(path: &ast::QualifiedName) -> bool {
+    stdlib::source(&path.join(".")).is_some()
+}
+
+fn item_name(item: &ast::Item) -> Option<&str> {
+    match item {
+        ast::Item::Record(decl) => Some(&decl.name),
+        ast::Item::Interface(decl) => Some(&decl.name),
+        ast::Item::Task(decl) => Some(&decl.name),
+        ast::Item::Workflow(decl) => Some(&decl.name),
+        ast::Item::Prompt(decl) => Some(&decl.name),
+        ast::Item::Hook(decl) => Some(&decl.name),
+        ast::Item::Trigger(decl) => Some(&decl.name),
+        ast::Item::Module(module) => module.name.as_ref()?.last().map(String::as_str),
+        _ => None,
+    }
+}
+
+/// Maps the names visible in one module to bundled item names.
+struct Resolver {
+    /// Items declared by the module itself.
+    own: HashMap<String, String>,
+    /// Own items and imported members.
+    locals: HashMap<String, String>,
+    /// Module bindings (`text` for `import lib.text`, `T` for `as T`) to the
+    /// final names of that module's items.
+    qualifiers: HashMap<String, HashMap<String, String>>,
+}
+
+impl Resolver {
+    fn new(
+        idx: usize,
+        unit: &ast::Module,
+        units: &[&ast::Module],
+        names: &HashMap<(usize, &str), String>,
+    ) -> Result<Self, HiloParseError> {
+        let own: HashMap<String, String> = names
+            .iter()
+            .filter(|((unit, _), _)| *unit == idx)
+            .map(|((_, name), final_name)| (name.to_string(), final_name.clone()))
+            .collect();
+        let mut locals = own.clone();
+        let mut qualifiers = HashMap::new();
+        for import in &unit.imports {
+            let Some(target) = units
+                .iter()
+                .position(|module| module.name.as_ref() == Some(&import.path))
+            else {
+                continue;
+            };
+            let target_names = exports(target, units, names, &mut Vec::new())?;
+            if let Some(members) = &import.members {
+                for member in members {
+                    if !target_names.contains_key(member)
+                        && names.contains_key(&(target, member.as_str()))
+                    {
+                        return Err(HiloParseError::InternalImport {
+                            module: import.path.join("."),
+                            name: member.clone(),
+                        });
+                    }
+                    if let Some(final_name) = target_names.get(member) {
+                        locals.insert(member.clone(), final_name.clone());
+                    }
+                }
+            }
+            let binding = match (&import.alias, &import.members) {
+                (Some(alias), _) => Some(alias),
+                (None, None) => import.path.last(),
+                (None, Some(_)) => None,
+            };
+            if let Some(binding) = binding {
+                qualifiers.insert(binding.clone(), target_names);
+            }
+        }
+        Ok(Self {
+            own,
+            locals,
+            qualifiers,
+        })
+    }
+
+    fn qualified(&self, binding: &str, member: &str) -> Option<&String> {
+        self.qualifiers.get(binding)?.get(member)
+    }
+
+    fn item(&self, item: &mut ast::Item) {
+        match item {
+            ast::Item::Record(decl) => {
+                if let Some(name) = self.own.get(&decl.name) {
+                    decl.name = name.clone();
+                }
+                if let Some(parent) = &mut decl.extends {
+                    self.path(parent);
+                }
+                self.annotations(&mut decl.annotations);
+                self.type_params(&mut decl.type_params);
+                self.type_params(&mut decl.constraints);
+                for field in &mut decl.fields {
+                    self.annotations(&mut field.annotations);
+                    self.ty(&mut field.ty);
+                }
+                for method in &mut decl.methods {
+                    self.callable(method);
+                }
+            }
+            ast::Item::Interface(decl) => {
+                if let Some(name) = self.own.get(&decl.name) {
+                    decl.name = name.clone();
+                }
+                for method in &mut decl.methods {
+                    self.type_params(&mut method.type_params);
+                    self.params(&mut method.params);
+                    if let Some(ty) = &mut method.return_type {
+                        self.ty(ty);
+                    }
+                }
+            }
+            ast::Item::Task(decl) => {
+                if let Some(name) = self.own.get(&decl.name) {
+                    decl.name = name.clone();
+                }
+                self.callable(decl);
+            }
+            ast::Item::Workflow(decl) => {
+                if let Some(name) = self.own.get(&decl.name) {
+                    decl.name = name.clone();
+                }
+                self.annotations(&mut decl.annotations);
+                self.block(&mut decl.body);
+                for step in &mut decl.steps {
+                    self.block(&mut step.body);
+                    if let Some(handler) = &mut step.on_error {
+                        self.block(&mut handler.body);
+                    }
+                }
+                if let Some(handler) = &mut decl.on_error {
+                    self.block(&mut handler.body);
+                }
+            }
+            ast::Item::Test(decl) => self.block(&mut decl.body),
+            ast::Item::Hook(decl) => {
+                if let Some(name) = self.own.get(&decl.name) {
+                    decl.name = name.clone();
+                }
+                self.block(&mut decl.body);
+            }
+            ast::Item::Prompt(decl) => {
+                if let Some(name) = self.own.get(&decl.name) {
+                    decl.name = name.clone();
+                }
+            }
+            ast::Item::Tool(decl) => {
+                self.params(&mut decl.params);
+                if let Some(ty) = &mut decl.return_type {
+                    self.ty(ty);
+                }
+            }
+            ast::Item::Trigger(decl) => {
+                if let Some(name) = self.own.get(&decl.name) {
+                    decl.name = name.clone();
+                }
+                self.path(&mut decl.run);
+            }
+            ast::Item::EventHandler(decl) => self.block(&mut decl.body),
+            ast::Item::Config(decl) => {
+                for entry in &mut decl.entries {
+                    self.expr(&mut entry.value);
+                }
+            }
+            ast::Item::Secret(decl) => {
+                if let Some(source) = &mut decl.source {
+                    self.expr(source);
+                }
+            }
+            ast::Item::Export(decl) => {
+                for name in &mut decl.names {
+                    if let Some(final_name) = self.own.get(name) {
+                        *name = final_name.clone();
+                    }
+                }
+            }
+            ast::Item::Module(module) => {
+                for item in &mut module.items {
+                    self.item(item);
+                }
+            }
+            ast::Item::ReExport(_) | ast::Item::Extension(_) | ast::Item::Other(_) => {}
+        }
+    }
+
+    fn callable(&self, decl: &mut ast::TaskDecl) {
+        self.annotations(&mut decl.annotations);
+        self.type_params(&mut decl.type_params);
+        self.type_params(&mut decl.constraints);
+        self.params(&mut decl.params);
+        if let Some(ty) = &mut decl.return_type {
+            self.ty(ty);
+        }
+        self.block(&mut decl.body);
+    }
+
+    fn type_params(&self, params: &mut [ast::TypeParam]) {
+        for bound in params.iter_mut().flat_map(|param| &mut param.bounds) {
+            self.ty(bound);
+        }
+    }
+
+    fn params(&self, params: &mut [ast::Param]) {
+        for param in params {
+            self.ty(&mut param.ty);
+            if let Some(default) = &mut param.default {
+                self.expr(default);
+            }
+        }
+    }
+
+    fn annotations(&self, annotations: &mut [ast::Annotation]) {
+        for annotation in annotations {
+            for arg in &mut annotation.args {
+                self.expr(&mut arg.value);
+            }
+        }
+    }
+
+    fn block(&self, block: &mut ast::Block) {
+        if block.statements.is_empty() && !block.raw.is_empty() {
+            block.statements = block.parse();
+        }
+        for statement in &mut block.statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&self, statement: &mut ast::Statement) {
+        match statement {
+            ast::Statement::Let { ty, value, .. } => {
+                if let Some(ty) = ty {
+                    self.ty(ty);
+                }
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            ast::Statement::Return { value } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            ast::Statement::Block { body, .. } => self.block(body),
+            ast::Statement::For {
+                iterable: expr,
+                body,
+                ..
+            }
+            | ast::Statement::While {
+                condition: expr,
+                body,
+            }
+            | ast::Statement::Guard {
+                condition: expr,
+                else_body: body,
+            } => {
+                self.expr(expr);
+                self.block(body);
+            }
+            ast::Statement::Try {
+                body,
+                catches,
+                finally,
+            } => {
+                self.block(body);
+                for catch in catches {
+                    if let Some(ty) = &mut catch.ty {
+                        self.ty(ty);
+                    }
+                    self.block(&mut catch.body);
+                }
+                if let Some(finally) = finally {
+                    self.block(finally);
+                }
+            }
+            ast::Statement::Branch { subject, arms, .. } => {
+                self.expr(subject);
+                for arm in arms {
+                    self.pattern(&mut arm.pattern);
+                }
+            }
+            ast::Statement::Parallel(branches) => {
+                for branch in branches {
+                    self.block(branch);
+                }
+            }
+            ast::Statement::ParallelFor {
+                iterable,
+                limit,
+                body,
+                ..
+            } => {
+                self.expr(iterable);
+                if let Some(limit) = limit {
+                    self.expr(limit);
+                }
+                self.block(body);
+            }
+            ast::Statement::Expr(expr)
+            | ast::Statement::Throw(expr)
+            | ast::Statement::Assert(expr) => self.expr(expr),
+            ast::Statement::FnDecl(decl) => self.callable(decl),
+            ast::Statement::Annotated {
+                annotations,
+                statement,
+            } => {
+                self.annotations(annotations);
+                self.statement(statement);
+            }
+        }
+    }
+
+    fn expr(&self, expr: &mut ast::Expression) {
+        match expr {
+            ast::Expression::Identifier(name) => {
+                if let Some(final_name) = self.locals.get(name) {
+                    *name = final_name.clone();
+                }
+            }
+            ast::Expression::Member { target, property } => {
+                if let ast::Expression::Identifier(binding) = target.as_ref()
+                    && let Some(final_name) = self.qualified(binding, property)
+                {
+                    *expr = ast::Expression::Identifier(final_name.clone());
+                } else {
+                    self.expr(target);
+                }
+            }
+            ast::Expression::Call { target, args } => {
+                self.expr(target);
+                for arg in args {
+                    self.expr(&mut arg.value);
+                }
+            }
+            ast::Expression::Index { target, index } => {
+                self.expr(target);
+                self.expr(index);
+            }
+            ast::Expression::OptionalChain { target, .. }
+            | ast::Expression::Unary {
+                operand: target, ..
+            }
+            | ast::Expression::Await(target)
+            | ast::Expression::Spread(target) => self.expr(target),
+            ast::Expression::List(elements) | ast::Expression::Tuple(elements) => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            ast::Expression::StructLiteral { type_name, fields } => {
+                self.path(type_name);
+                for (_, value) in fields {
+                    self.expr(value);
+                }
+            }
+            ast::Expression::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ast::Expression::Match { subject, arms } => {
+                self.expr(subject);
+                for arm in arms {
+                    self.pattern(&mut arm.pattern);
+                    if let Some(guard) = &mut arm.guard {
+                        self.expr(guard);
+                    }
+                    self.arm_body(&mut arm.body);
+                }
+            }
+            ast::Expression::Lambda { params, body } => {
+                for param in params {
+                    if let Some(ty) = &mut param.ty {
+                        self.ty(ty);
+                    }
+                }
+                self.arm_body(body);
+            }
+            ast::Expression::Literal(_) | ast::Expression::Raw(_) => {}
+        }
+    }
+
+    fn arm_body(&self, body: &mut ast::ArmBody) {
+        match body {
+            ast::ArmBody::Expr(value) => self.expr(value),
+            ast::ArmBody::Block(body) => self.block(body),
+        }
+    }
+
+    fn pattern(&self, pattern: &mut ast::Pattern) {
+        match pattern {
+            ast::Pattern::Variant { path, fields } => {
+                self.path(path);
+                for field in fields {
+                    self.pattern(field);
+                }
+            }
+            ast::Pattern::Record { name, fields } => {
+                self.path(name);
+                for field in fields.iter_mut().filter_map(|(_, field)| field.as_mut()) {
+                    self.pattern(field);
+                }
+            }
+            ast::Pattern::Tuple(items) => {
+                for item in items {
+                    self.pattern(item);
+                }
+            }
+            ast::Pattern::Wildcard
+            | ast::Pattern::Binding(_)
+            | ast::Pattern::Literal(_)
+            | ast::Pattern::Raw(_) => {}
+        }
+    }
+
+    fn ty(&self, ty: &mut ast::TypeExpr) {
+        match ty {
+            ast::TypeExpr::Simple(path) => self.path(path),
+            ast::TypeExpr::Generic { base, arguments } => {
+                self.path(base);
+                for argument in arguments {
+                    self.ty(argument);
+                }
+            }
+            ast::TypeExpr::List(inner) | ast::TypeExpr::Optional(inner) => self.ty(inner),
+            ast::TypeExpr::Map { key, value } => {
+                self.ty(key);
+                self.ty(value);
+            }
+            ast::TypeExpr::Struct(fields) => {
+                for field in fields {
+                    self.ty(&mut field.ty);
+                }
+            }
+            ast::TypeExpr::Tuple(elements) => {
+                for element in elements {
+                    self.ty(element);
+                }
+            }
+            ast::TypeExpr::Unknown(_) => {}
+        }
+    }
+
+    fn path(&self, path: &mut ast::QualifiedName) {
+        let final_name = match path.as_slice() {
+            [name] => self.locals.get(name),
+            [binding, member] => self.qualified(binding, member),
+            _ => None,
+        };
+        if let Some(final_name) = final_name {
+            *path = vec![final_name.clone()];
+        }
+    }
+}
+
+/// Keeps every entry item (`parts[0]`) and the dependency items they reach.
+fn strip_unused(parts: &mut [Vec<ast::Item>]) {
+    let mut used: HashSet<String> = HashSet::new();
+    let dependencies: Vec<&ast::Item> = parts[1..].iter().flatten().collect();
+    // Hooks and triggers run without being referenced, so they stay, along
+    // with everything they use.
+    let mut pending: Vec<&ast::Item> = parts[0]
+        .iter()
+        .chain(
+            dependencies
+                .iter()
+                .copied()
+                .filter(|item| runs_unreferenced(item)),
+        )
+        .collect();
+    while let Some(item) = pending.pop() {
+        let mut refs = Vec::new();
+        item_refs(item, &mut refs);
+        for name in refs {
+            if used.insert(name.clone()) {
+                pending.extend(
+                    dependencies
+                        .iter()
+                        .filter(|dep| item_name(dep) == Some(name.as_str())),
+                );
+            }
+        }
+    }
+    for items in &mut parts[1..] {
+        items.retain(|item| {
+            runs_unreferenced(item) || item_name(item).is_none_or(|name| used.contains(name))
+        });
+    }
+}
+
+fn runs_unreferenced(item: &ast::Item) -> bool {
+    matches!(item, ast::Item::Hook(_) | ast::Item::Trigger(_))
+}
+
+fn item_refs(item: &ast::Item, refs: &mut Vec<String>) {
+    match item {
+        ast::Item::Record(decl) => {
+            refs.extend(
+                decl.extends
+                    .iter()
+                    .flat_map(|parent| parent.first().cloned()),
+            );
+            annotation_refs(&decl.annotations, refs);
+            type_param_refs(&decl.type_params, refs);
+            type_param_refs(&decl.constraints, refs);
+            for field in &decl.fields {
+                annotation_refs(&field.annotations, refs);
+                type_refs(&field.ty, refs);
+            }
+            for method in &decl.methods {
+                callable_refs(method, refs);
+            }
+        }
+        ast::Item::Interface(decl) => {
+            for method in &decl.methods {
+                type_param_refs(&method.type_params, refs);
+                param_refs(&method.params, refs);
+                if let Some(ty) = &method.return_type {
+                    type_refs(ty, refs);
+                }
+            }
+        }
+        ast::Item::Task(decl) => callable_refs(decl, refs),
+        ast::Item::Workflow(decl) => {
+            annotation_refs(&decl.annotations, refs);
+            block_refs(&decl.body, refs);
+            for step in &decl.steps {
+                block_refs(&step.body, refs);
+                if let Some(handler) = &step.on_error {
+                    block_refs(&handler.body, refs);
+                }
+            }
+            if let Some(handler) = &decl.on_error {
+                block_refs(&handler.body, refs);
+            }
+        }
+        ast::Item::Test(decl) => block_refs(&decl.body, refs),
+        ast::Item::Hook(decl) => block_refs(&decl.body, refs),
+        ast::Item::EventHandler(decl) => block_refs(&decl.body, refs),
+        ast::Item::Config(decl) => {
+            for entry in &decl.entries {
+                expr_refs(&entry.value, refs);
+            }
+        }
+        ast::Item::Secret(decl) => {
+            if let Some(source) = &decl.source {
+                expr_refs(source, refs);
+            }
+        }
+        ast::Item::Tool(decl) => {
+            param_refs(&decl.params, refs);
+            if let Some(ty) = &decl.return_type {
+                type_refs(ty, refs);
+            }
+        }
+        ast::Item::Module(module) => {
+            for item in &module.items {
+                item_refs(item, refs);
+            }
+        }
+        ast::Item::Trigger(decl) => refs.extend(decl.run.first().cloned()),
+        ast::Item::Prompt(_)
+        | ast::Item::Export(_)
+        | ast::Item::ReExport(_)
+        | ast::Item::Extension(_)
+        | ast::Item::Other(_) => {}
+    }
+}
+
+fn callable_refs(decl: &ast::TaskDecl, refs: &mut Vec<String>) {
+    annotation_refs(&decl.annotations, refs);
+    type_param_refs(&decl.type_params, refs);
+    type_param_refs(&decl.constraints, refs);
+    param_refs(&decl.params, refs);
+    if let Some(ty) = &decl.return_type {
+        type_refs(ty, refs);
+    }
+    block_refs(&decl.body, refs);
+}
+
+fn type_param_refs(params: &[ast::TypeParam], refs: &mut Vec<String>) {
+    for bound in params.iter().flat_map(|param| &param.bounds) {
+        type_refs(bound, refs);
+    }
+}
+
+fn param_refs(params: &[ast::Param], refs: &mut Vec<String>) {
+    for param in params {
+        type_refs(&param.ty, refs);
+        if let Some(default) = &param.default {
+            expr_refs(default, refs);
+        }
+    }
+}
+
+fn annotation_refs(annotations: &[ast::Annotation], refs: &mut Vec<String>) {
+    for annotation in annotations {
+        for arg in &annotation.args {
+            expr_refs(&arg.value, refs);
+        }
+    }
+}
+
+fn block_refs(block: &ast::Block, refs: &mut Vec<String>) {
+    for statement in &block.statements {
+        statement_refs(statement, refs);
+    }
+}
+
+fn statement_refs(statement: &ast::Statement, refs: &mut Vec<String>) {
+    match statement {
+        ast::Statement::Let { ty, value, .. } => {
+            if let Some(ty) = ty {
+                type_refs(ty, refs);
+            }
+            if let Some(value) = value {
+                expr_refs(value, refs);
+            }
+        }
+        ast::Statement::Return { value } => {
+            if let Some(value) = value {
+                expr_refs(value, refs);
+            }
+        }
+        ast::Statement::Block { body, .. } => block_refs(body, refs),
+        ast::Statement::For {
+            iterable: expr,
+            body,
+            ..
+        }
+        | ast::Statement::While {
+            condition: expr,
+            body,
+        }
+        | ast::Statement::Guard {
+            condition: expr,
+            else_body: body,
+        } => {
+            expr_refs(expr, refs);
+            block_refs(body, refs);
+        }
+        ast::Statement::Try {
+            body,
+            catches,
+            finally,
+        } => {
+            block_refs(body, refs);
+            for catch in catches {
+                if let Some(ty) = &catch.ty {
+                    type_refs(ty, refs);
+                }
+                block_refs(&catch.body, refs);
+            }
+            if let Some(finally) = finally {
+                block_refs(finally, refs);
+            }
+        }
+        ast::Statement::Branch { subject, arms, .. } => {
+            expr_refs(subject, refs);
+            for arm in arms {
+                pattern_refs(&arm.pattern, refs);
+            }
+        }
+        ast::Statement::Parallel(branches) => {
+            for branch in branches {
+                block_refs(branch, refs);
+            }
+        }
+        ast::Statement::ParallelFor {
+            iterable,
+            limit,
+            body,
+            ..
+        } => {
+            expr_refs(iterable, refs);
+            if let Some(limit) = limit {
+                expr_refs(limit, refs);
+            }
+            block_refs(body, refs);
+        }
+        ast::Statement::Expr(expr) | ast::Statement::Throw(expr) | ast::Statement::Assert(expr) => {
+            expr_refs(expr, refs)
+        }
+        ast::Statement::FnDecl(decl) => callable_refs(decl, refs),
+        ast::Statement::Annotated {
+            annotations,
+            statement,
+        } => {
+            annotation_refs(annotations, refs);
+            statement_refs(statement, refs);
+        }
+    }
+}
+
+fn expr_refs(expr: &ast::Expression, refs: &mut Vec<String>) {
+    match expr {
+        ast::Expression::Identifier(name) => refs.push(name.clone()),
+        ast::Expression::Call { target, args } => {
+            expr_refs(target, refs);
+            for arg in args {
+                expr_refs(&arg.value, refs);
+            }
+        }
+        ast::Expression::Member { target, .. }
+        | ast::Expression::OptionalChain { target, .. }
+        | ast::Expression::Unary {
+            operand: target, ..
+        }
+        | ast::Expression::Await(target)
+        | ast::Expression::Spread(target) => expr_refs(target, refs),
+        ast::Expression::List(elements) | ast::Expression::Tuple(elements) => {
+            for element in elements {
+                expr_refs(element, refs);
+            }
+        }
+        ast::Expression::Index { target, index } => {
+            expr_refs(target, refs);
+            expr_refs(index, refs);
+        }
+        ast::Expression::StructLiteral { type_name, fields } => {
+            refs.extend(type_name.first().cloned());
+            for (_, value) in fields {
+                expr_refs(value, refs);
+            }
+        }
+        ast::Expression::Binary { left, right, .. } => {
+            expr_refs(left, refs);
+            expr_refs(right, refs);
+        }
+        ast::Expression::Match { subject, arms } => {
+            expr_refs(subject, refs);
+            for arm in arms {
+                pattern_refs(&arm.pattern, refs);
+                if let Some(guard) = &arm.guard {
+                    expr_refs(guard, refs);
+                }
+                arm_body_refs(&arm.body, refs);
+            }
+        }
+        ast::Expression::Lambda { params, body } => {
+            for param in params {
+                if let Some(ty) = &param.ty {
+                    type_refs(ty, refs);
+                }
+            }
+            arm_body_refs(body, refs);
+        }
+        ast::Expression::Literal(_) | ast::Expression::Raw(_) => {}
+    }
+}
+
+fn pattern_refs(pattern: &ast::Pattern, refs: &mut Vec<String>) {
+    match pattern {
+        ast::Pattern::Variant { path, fields } => {
+            refs.extend(path.first().cloned());
+            for field in fields {
+                pattern_refs(field, refs);
+            }
+        }
+        ast::Pattern::Record { name, fields } => {
+            refs.extend(name.first().cloned());
+            for pattern in fields.iter().filter_map(|(_, pattern)| pattern.as_ref()) {
+                pattern_refs(pattern, refs);
+            }
+        }
+        ast::Pattern::Tuple(items) => {
+            for item in items {
+                pattern_refs(item, refs);
+            }
+        }
+        ast::Pattern::Wildcard
+        | ast::Pattern::Binding(_)
+        | ast::Pattern::Literal(_)
+        | ast::Pattern::Raw(_) => {}
+    }
+}
+
+fn arm_body_refs(body: &ast::ArmBody, refs: &mut Vec<String>) {
+    match body {
+        ast::ArmBody::Expr(value) => expr_refs(value, refs),
+        ast::ArmBody::Block(body) => block_refs(body, refs),
+    }
+}
+
+fn type_refs(ty: &ast::TypeExpr, refs: &mut Vec<String>) {
+    match ty {
+        ast::TypeExpr::Simple(path) => refs.extend(path.first().cloned()),
+        ast::TypeExpr::Generic { base, arguments } => {
+            refs.extend(base.first().cloned());
+            for argument in arguments {
+                type_refs(argument, refs);
+            }
+        }
+        ast::TypeExpr::List(inner) | ast::TypeExpr::Optional(inner) => type_refs(inner, refs),
+        ast::TypeExpr::Map { key, value } => {
+            type_refs(key, refs);
+            type_refs(value, refs);
+        }
+        ast::TypeExpr::Struct(fields) => {
+            for field in fields {
+                type_refs(&field.ty, refs);
+            }
+        }
+        ast::TypeExpr::Tuple(elements) => {
+            for element in elements {
+                type_refs(element, refs);
+            }
+        }
+        ast::TypeExpr::Unknown(_) => {}
+    }
+}
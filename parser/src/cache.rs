@@ -0,0 +1,115 @@
+//! Content-addressed cache of parsed modules.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::{ast, error::HiloParseError, options::ParseOptions, parser};
+
+/// Modules kept by a cache built with [`ParseCache::new`] or `default()`.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// Shares parsed modules between callers that see identical source text.
+///
+/// Entries are keyed by the source text itself, so an unchanged file is never
+/// parsed twice and a hit always belongs to the source it was looked up with.
+/// Once the cache holds `capacity` modules, storing another evicts the oldest
+/// one, which keeps watch-mode callers that see a new revision on every save
+/// from growing without bound. Failed parses are not cached. The cache is safe
+/// to share between threads.
+#[derive(Debug)]
+pub struct ParseCache {
+    options: ParseOptions,
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    modules: HashMap<Arc<str>, Arc<ast::Module>>,
+    /// Cached sources, oldest first.
+    order: VecDeque<Arc<str>>,
+}
+
+impl Default for ParseCache {
+    fn default() -> Self {
+        Self::new(ParseOptions::default())
+    }
+}
+
+impl ParseCache {
+    /// Create an empty cache whose misses are parsed with `options`.
+    pub fn new(options: ParseOptions) -> Self {
+        Self::with_capacity(options, DEFAULT_CAPACITY)
+    }
+
+    /// Like [`ParseCache::new`], keeping at most `capacity` modules (at least one).
+    pub fn with_capacity(options: ParseOptions, capacity: usize) -> Self {
+        Self {
+            options,
+            capacity: capacity.max(1),
+            entries: Mutex::default(),
+        }
+    }
+
+    /// Return the cached module for `source`, parsing and storing it on a miss.
+    pub fn get_or_parse(&self, source: &str) -> Result<Arc<ast::Module>, HiloParseError> {
+        if let Some(module) = self.entries().modules.get(source) {
+            return Ok(Arc::clone(module));
+        }
+        // Parse without holding the lock so independent files don't serialize.
+        let module = Arc::new(parser::parse_module(source, &self.options)?);
+        let mut entries = self.entries();
+        if let Some(existing) = entries.modules.get(source) {
+            return Ok(Arc::clone(existing));
+        }
+        while entries.modules.len() >= self.capacity {
+            let Some(oldest) = entries.order.pop_front() else {
+                break;
+            };
+            entries.modules.remove(&oldest);
+        }
+        let key: Arc<str> = Arc::from(source);
+        entries.order.push_back(Arc::clone(&key));
+        entries.modules.insert(key, Arc::clone(&module));
+        Ok(module)
+    }
+
+    /// Drop the module cached for `source`, returning whether there was one.
+    pub fn remove(&self, source: &str) -> bool {
+        let mut entries = self.entries();
+        if entries.modules.remove(source).is_none() {
+            return false;
+        }
+        entries.order.retain(|cached| cached.as_ref() != source);
+        true
+    }
+
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries().modules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries().modules.is_empty()
+    }
+
+    /// Drop every cached module.
+    pub fn clear(&self) {
+        let mut entries = self.entries();
+        entries.modules.clear();
+        entries.order.clear();
+    }
+
+    fn entries(&self) -> MutexGuard<'_, Entries> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
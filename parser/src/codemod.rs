@@ -0,0 +1,274 @@
+//! Formatting-preserving rewrites.
+//!
+//! A [`Codemod`] inspects a parsed module and returns [`TextEdit`]s against its
+//! source; everything outside the edited ranges, including comments and
+//! layout, is kept byte for byte.
+
+use crate::{HiloParseError, ParseOptions, ast, batch, incremental::TextEdit, parser};
+
+/// A transformation expressed as text edits over a parsed source.
+pub trait Codemod {
+    /// Edits to apply to `source`, which parsed to `module`. Ranges must not
+    /// overlap.
+    fn edits(&self, source: &str, module: &ast::Module) -> Vec<TextEdit>;
+}
+
+impl<F> Codemod for F
+where
+    F: Fn(&str, &ast::Module) -> Vec<TextEdit>,
+{
+    fn edits(&self, source: &str, module: &ast::Module) -> Vec<TextEdit> {
+        self(source, module)
+    }
+}
+
+/// Apply `edits` to `source` in one pass. Edits may be given in any order.
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> Result<String, HiloParseError> {
+    let mut sorted: Vec<&TextEdit> = edits.iter().collect();
+    sorted.sort_by_key(|edit| (edit.range.start, edit.range.end));
+
+    let mut out = String::with_capacity(source.len());
+    let mut copied = 0;
+    for edit in sorted {
+        let range = &edit.range;
+        if range.start < copied {
+            return Err(HiloParseError::InvalidEdit(format!(
+                "edit at {range:?} overlaps a previous edit"
+            )));
+        }
+        if source.get(range.clone()).is_none() {
+            return Err(HiloParseError::InvalidEdit(format!(
+                "edit at {range:?} is not a valid range of the {}-byte source",
+                source.len()
+            )));
+        }
+        out.push_str(&source[copied..range.start]);
+        out.push_str(&edit.text);
+        copied = range.end;
+    }
+    out.push_str(&source[copied..]);
+    Ok(out)
+}
+
+/// Parse `source` and apply `codemod` to it.
+pub fn rewrite(
+    source: &str,
+    codemod: &dyn Codemod,
+    options: &ParseOptions,
+) -> Result<String, HiloParseError> {
+    let module = parser::parse_module(source, options)?;
+    apply_edits(source, &codemod.edits(source, &module))
+}
+
+/// [`rewrite`] every source of a project. Parsing runs on worker threads;
+/// results are returned in input order.
+pub fn rewrite_many<S>(
+    sources: &[S],
+    codemod: &dyn Codemod,
+    options: &ParseOptions,
+) -> Vec<Result<String, HiloParseError>>
+where
+    S: AsRef<str> + Sync,
+{
+    batch::parse_many(sources, options)
+        .into_iter()
+        .zip(sources)
+        .map(|(parsed, source)| {
+            let source = source.as_ref();
+            apply_edits(source, &codemod.edits(source, &parsed?))
+        })
+        .collect()
+}
+
+/// Renames member `from` of `module` to `to`, e.g. `core.text.trim` to
+/// `core.text.strip`.
+///
+/// Rewrites the member in matching import lists, references qualified by the
+/// module's binding (`text.trim`, or `T.trim` under `as T`), and bare
+/// references when the member is imported by name. Bare references are left
+/// alone where the name means something else: everywhere when the module
+/// declares an item of that name, and in the scope of a `let`, `for`, or `fn`
+/// binding or a parameter of that name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameMember {
+    pub module: ast::QualifiedName,
+    pub from: ast::Ident,
+    pub to: ast::Ident,
+}
+
+impl Codemod for RenameMember {
+    fn edits(&self, source: &str, module: &ast::Module) -> Vec<TextEdit> {
+        let tokens = tokens(source);
+        let mut edits = Vec::new();
+        let mut bindings: Vec<&str> = Vec::new();
+        let mut bare = false;
+        let declared = declares(&module.items, &self.from);
+        let shadowed = shadowed_ranges(source, &tokens, &self.from);
+        let rename = |span: &ast::Span| TextEdit {
+            range: span.clone(),
+            text: self.to.clone(),
+        };
+
+        let mut idx = 0;
+        while idx < tokens.len() {
+            let (span, text) = &tokens[idx];
+            if *text == "import" {
+                // import QName ({ members })? (as Alias)?
+                let mut path = Vec::new();
+                idx += 1;
+                while let Some((_, part)) = tokens.get(idx) {
+                    path.push(*part);
+                    idx += 1;
+                    if tokens.get(idx).map(|(_, text)| *text) != Some(".") {
+                        break;
+                    }
+                    idx += 1;
+                }
+                let matches = path.iter().eq(self.module.iter());
+                let mut has_members = false;
+                if tokens.get(idx).map(|(_, text)| *text) == Some("{") {
+                    has_members = true;
+                    idx += 1;
+                    while let Some((span, member)) = tokens.get(idx) {
+                        idx += 1;
+                        match *member {
+                            "}" => break,
+                            member if matches && member == self.from => {
+                                bare = true;
+                                edits.push(rename(span));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                if tokens.get(idx).map(|(_, text)| *text) == Some("as")
+                    && let Some((_, alias)) = tokens.get(idx + 1)
+                {
+                    idx += 2;
+                    if matches {
+                        bindings.push(alias);
+                    }
+                } else if matches
+                    && !has_members
+                    && let Some(last) = path.last()
+                {
+                    bindings.push(last);
+                }
+                continue;
+            }
+
+            if *text == self.from {
+                let prev = |back: usize| idx.checked_sub(back).map(|at| tokens[at].1);
+                let qualified = prev(1) == Some(".")
+                    && prev(2).is_some_and(|binding| bindings.contains(&binding))
+                    && prev(3) != Some(".");
+                let local = declared || shadowed.iter().any(|range| range.contains(&span.start));
+                if qualified || (bare && !local && prev(1) != Some(".")) {
+                    edits.push(rename(span));
+                }
+            }
+            idx += 1;
+        }
+        edits
+    }
+}
+
+/// Whether `items`, or those of a nested module, declare `name` themselves.
+fn declares(items: &[ast::Item], name: &str) -> bool {
+    items.iter().any(|item| match item {
+        ast::Item::Record(decl) => decl.name == name,
+        ast::Item::Interface(decl) => decl.name == name,
+        ast::Item::Task(decl) => decl.name == name,
+        ast::Item::Workflow(decl) => decl.name == name,
+        ast::Item::Prompt(decl) => decl.name == name,
+        ast::Item::Module(module) => declares(&module.items, name),
+        _ => false,
+    })
+}
+
+/// Ranges of `source` in which a local binding named `name` hides an
+/// imported member: from a `let name` or nested `fn name` to the end of the
+/// enclosing braces, and over a `for name in` loop or a task, method, or `fn`
+/// with a parameter `name`, through the end of its body.
+fn shadowed_ranges(source: &str, tokens: &[(ast::Span, &str)], name: &str) -> Vec<ast::Span> {
+    let text = |idx: usize| tokens.get(idx).map(|(_, text)| *text);
+    let mut ranges = Vec::new();
+    // Starts of the ranges that end with each open brace.
+    let mut frames: Vec<Vec<usize>> = vec![Vec::new()];
+    // A range that covers the next brace pair, e.g. a loop or callable body.
+    let mut next_body: Option<usize> = None;
+    for (idx, (span, token)) in tokens.iter().enumerate() {
+        match *token {
+            "{" => frames.push(next_body.take().into_iter().collect()),
+            "}" => {
+                let starts = frames.pop().unwrap_or_default();
+                ranges.extend(starts.into_iter().map(|start| start..span.end));
+                if frames.is_empty() {
+                    frames.push(Vec::new());
+                }
+            }
+            "let" | "fn" | "func" if text(idx + 1) == Some(name) => {
+                if let Some(frame) = frames.last_mut() {
+                    frame.push(span.start);
+                }
+            }
+            "for" if text(idx + 1) == Some(name) && text(idx + 2) == Some("in") => {
+                next_body = Some(span.start);
+            }
+            "task" | "fn" | "func" => {
+                // Parameters start at the first `(` after the name and any type parameters.
+                let Some(open) = (idx + 2..tokens.len())
+                    .take_while(|&at| !matches!(text(at), Some("{" | "}")))
+                    .find(|&at| text(at) == Some("("))
+                else {
+                    continue;
+                };
+                let mut depth = 0;
+                for (at, (_, param)) in tokens.iter().enumerate().skip(open) {
+                    match *param {
+                        "(" | "[" => depth += 1,
+                        ")" | "]" => depth -= 1,
+                        _ if depth == 1 && *param == name && text(at + 1) == Some(":") => {
+                            next_body = Some(span.start);
+                        }
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    for starts in frames {
+        ranges.extend(starts.into_iter().map(|start| start..source.len()));
+    }
+    ranges
+}
+
+/// Identifiers and single punctuation characters of `source`, skipping
+/// whitespace, comments, and string literals.
+pub(crate) fn tokens(source: &str) -> Vec<(ast::Span, &str)> {
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < source.len() {
+        if let Some(next) = parser::skip_comment_or_string(source, idx) {
+            idx = next;
+            continue;
+        }
+        let rest = &source[idx..];
+        let ch = rest.chars().next().unwrap_or_default();
+        let len = if ch == '_' || ch.is_alphabetic() {
+            rest.find(|c: char| c != '_' && !c.is_alphanumeric())
+                .unwrap_or(rest.len())
+        } else {
+            ch.len_utf8()
+        };
+        if !ch.is_whitespace() {
+            tokens.push((idx..idx + len, &source[idx..idx + len]));
+        }
+        idx += len;
+    }
+    tokens
+}
@@ -0,0 +1,628 @@
+//! Public API comparison between two versions of a module.
+//!
+//! The API is the top-level records, interfaces, tasks, workflows, and prompts
+//! that [`semantic::is_exported`] lets importers see: `internal` items never
+//! count, and once the module has an `export` list only listed and `pub` items
+//! do. Forwarded `export` paths are compared as well. Tests and extension items
+//! are not part of the API.
+
+use std::{fmt, time::Duration};
+
+use crate::{ast, printer, semantic};
+
+/// How a change affects dependents of the module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChangeKind {
+    /// Bodies or other details callers cannot observe through the signature.
+    Internal,
+    /// New API that existing callers do not need to know about.
+    Additive,
+    /// Existing callers may stop compiling or behave differently.
+    Breaking,
+}
+
+/// Smallest semver bump that covers a set of changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// Dotted path of the affected declaration, e.g. `Brief.title`.
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiReport {
+    pub changes: Vec<Change>,
+}
+
+impl ApiReport {
+    pub fn breaking(&self) -> impl Iterator<Item = &Change> {
+        self.of_kind(ChangeKind::Breaking)
+    }
+
+    pub fn additive(&self) -> impl Iterator<Item = &Change> {
+        self.of_kind(ChangeKind::Additive)
+    }
+
+    pub fn internal(&self) -> impl Iterator<Item = &Change> {
+        self.of_kind(ChangeKind::Internal)
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.breaking().next().is_some()
+    }
+
+    /// The version bump the changes require.
+    pub fn required_bump(&self) -> Bump {
+        match self.changes.iter().map(|change| change.kind).max() {
+            Some(ChangeKind::Breaking) => Bump::Major,
+            Some(ChangeKind::Additive) => Bump::Minor,
+            _ => Bump::Patch,
+        }
+    }
+
+    fn of_kind(&self, kind: ChangeKind) -> impl Iterator<Item = &Change> {
+        self.changes
+            .iter()
+            .filter(move |change| change.kind == kind)
+    }
+}
+
+impl fmt::Display for ApiReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            let kind = match change.kind {
+                ChangeKind::Breaking => "breaking",
+                ChangeKind::Additive => "additive",
+                ChangeKind::Internal => "internal",
+            };
+            writeln!(f, "{kind}: {}: {}", change.path, change.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Classify the API changes from `old` to `new`.
+pub fn diff(old: &ast::Module, new: &ast::Module) -> ApiReport {
+    let mut report = Report::default();
+    let old_items = exported(old);
+    let new_items = exported(new);
+
+    for (name, old_item) in &old_items {
+        match new_items.iter().find(|(other, _)| other == name) {
+            None => report.push(
+                ChangeKind::Breaking,
+                name,
+                format!("{} removed", kind_name(old_item)),
+            ),
+            Some((_, new_item)) => report.item(name, (old, old_item), (new, new_item)),
+        }
+    }
+    for (name, new_item) in &new_items {
+        if !old_items.iter().any(|(other, _)| other == name) {
+            report.push(
+                ChangeKind::Additive,
+                name,
+                format!("{} added", kind_name(new_item)),
+            );
+        }
+    }
+    let old_forwarded = forwarded(old);
+    let new_forwarded = forwarded(new);
+    for name in &old_forwarded {
+        if !new_forwarded.contains(name) {
+            report.push(ChangeKind::Breaking, name, "re-export removed".to_string());
+        }
+    }
+    for name in &new_forwarded {
+        if !old_forwarded.contains(name) {
+            report.push(ChangeKind::Additive, name, "re-export added".to_string());
+        }
+    }
+    ApiReport {
+        changes: report.changes,
+    }
+}
+
+fn exported(module: &ast::Module) -> Vec<(&str, &ast::Item)> {
+    module
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ast::Item::Record(decl) => Some((decl.name.as_str(), item)),
+            ast::Item::Interface(decl) => Some((decl.name.as_str(), item)),
+            ast::Item::Task(decl) => Some((decl.name.as_str(), item)),
+            ast::Item::Workflow(decl) => Some((decl.name.as_str(), item)),
+            ast::Item::Prompt(decl) => Some((decl.name.as_str(), item)),
+            _ => None,
+        })
+        .filter(|(name, item)| semantic::is_exported(module, name, item.visibility()))
+        .collect()
+}
+
+/// Names the module forwards from others, as `lib.text.trim` or `lib.text.*`.
+fn forwarded(module: &ast::Module) -> Vec<String> {
+    let mut names = Vec::new();
+    for item in &module.items {
+        let ast::Item::ReExport(decl) = item else {
+            continue;
+        };
+        let path = decl.path.join(".");
+        match &decl.members {
+            Some(members) => names.extend(members.iter().map(|member| format!("{path}.{member}"))),
+            None => names.push(format!("{path}.*")),
+        }
+    }
+    names
+}
+
+fn kind_name(item: &ast::Item) -> &'static str {
+    match item {
+        ast::Item::Record(_) => "record",
+        ast::Item::Interface(_) => "interface",
+        ast::Item::Task(_) => "task",
+        ast::Item::Workflow(_) => "workflow",
+        ast::Item::Test(_) => "test",
+        ast::Item::Hook(_) => "hook",
+        ast::Item::Prompt(_) => "prompt",
+        ast::Item::Tool(_) => "tool",
+        ast::Item::Trigger(_) => "trigger",
+        ast::Item::EventHandler(_) => "event handler",
+        ast::Item::Config(_) => "config block",
+        ast::Item::Secret(_) => "secret",
+        ast::Item::Export(_) => "export list",
+        ast::Item::ReExport(_) => "re-export",
+        ast::Item::Module(_) => "module",
+        ast::Item::Extension(_) => "extension item",
+        ast::Item::Other(_) => "item",
+    }
+}
+
+/// `T: Bound, U` — the parameter list without its angle brackets.
+fn type_param_list(params: &[ast::TypeParam]) -> String {
+    let printed = printer::print_type_params(params);
+    printed
+        .strip_prefix('<')
+        .and_then(|inner| inner.strip_suffix('>'))
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// The parts of a task or interface method that callers depend on.
+struct Signature<'a> {
+    is_async: bool,
+    type_params: &'a [ast::TypeParam],
+    params: &'a [ast::Param],
+    return_type: &'a Option<ast::TypeExpr>,
+}
+
+impl<'a> From<&'a ast::TaskDecl> for Signature<'a> {
+    fn from(decl: &'a ast::TaskDecl) -> Self {
+        Self {
+            is_async: decl.is_async,
+            type_params: &decl.type_params,
+            params: &decl.params,
+            return_type: &decl.return_type,
+        }
+    }
+}
+
+impl<'a> From<&'a ast::MethodSig> for Signature<'a> {
+    fn from(method: &'a ast::MethodSig) -> Self {
+        Self {
+            is_async: method.is_async,
+            type_params: &method.type_params,
+            params: &method.params,
+            return_type: &method.return_type,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Report {
+    changes: Vec<Change>,
+}
+
+impl Report {
+    fn push(&mut self, kind: ChangeKind, path: &str, message: String) {
+        self.changes.push(Change {
+            kind,
+            path: path.to_string(),
+            message,
+        });
+    }
+
+    fn item(
+        &mut self,
+        name: &str,
+        (old_module, old): (&ast::Module, &ast::Item),
+        (new_module, new): (&ast::Module, &ast::Item),
+    ) {
+        match (old, new) {
+            (ast::Item::Record(old), ast::Item::Record(new)) => {
+                self.record((old_module, old), (new_module, new))
+            }
+            (ast::Item::Interface(old), ast::Item::Interface(new)) => self.interface(old, new),
+            (ast::Item::Task(old), ast::Item::Task(new)) => self.callable(name, old, new),
+            (ast::Item::Workflow(old), ast::Item::Workflow(new)) => {
+                self.annotations(name, &old.annotations, &new.annotations);
+                self.body(name, &old.body, &new.body)
+            }
+            (ast::Item::Prompt(old), ast::Item::Prompt(new)) => self.prompt(old, new),
+            _ => self.push(
+                ChangeKind::Breaking,
+                name,
+                format!("changed from {} to {}", kind_name(old), kind_name(new)),
+            ),
+        }
+    }
+
+    /// Compares fields including inherited ones, so moving fields into a
+    /// parent record is not a field change. Gaining a parent whose fields the
+    /// record already had is internal; any other change of parent is breaking.
+    fn record(
+        &mut self,
+        (old_module, old): (&ast::Module, &ast::RecordDecl),
+        (new_module, new): (&ast::Module, &ast::RecordDecl),
+    ) {
+        let name = &old.name;
+        let old_fields = semantic::record_fields(old_module, name).unwrap_or_default();
+        let new_fields = semantic::record_fields(new_module, name).unwrap_or_default();
+        if old.type_params != new.type_params {
+            self.push(
+                ChangeKind::Breaking,
+                name,
+                format!(
+                    "type parameters changed from <{}> to <{}>",
+                    type_param_list(&old.type_params),
+                    type_param_list(&new.type_params)
+                ),
+            );
+        }
+        self.constraints(name, &old.constraints, &new.constraints);
+        self.annotations(name, &old.annotations, &new.annotations);
+        let gained_present_parent = old.extends.is_none()
+            && new
+                .extends
+                .as_deref()
+                .and_then(|parent| match parent {
+                    [parent] => semantic::record_fields(new_module, parent),
+                    _ => None,
+                })
+                .is_some_and(|parent_fields| {
+                    parent_fields.iter().all(|field| {
+                        old_fields.iter().any(|old| {
+                            old.name == field.name
+                                && old.ty == field.ty
+                                && old.optional == field.optional
+                        })
+                    })
+                });
+        if gained_present_parent {
+            let parent = new.extends.as_deref().unwrap_or_default().join(".");
+            self.push(
+                ChangeKind::Internal,
+                name,
+                format!("now extends {parent}, whose fields it already had"),
+            );
+        } else if old.extends != new.extends {
+            let render = |parent: &Option<ast::QualifiedName>| {
+                parent
+                    .as_ref()
+                    .map_or_else(|| "nothing".to_string(), |parent| parent.join("."))
+            };
+            self.push(
+                ChangeKind::Breaking,
+                name,
+                format!(
+                    "parent changed from {} to {}",
+                    render(&old.extends),
+                    render(&new.extends)
+                ),
+            );
+        }
+
+        for field in &old_fields {
+            let path = format!("{name}.{}", field.name);
+            let Some(other) = new_fields.iter().find(|other| other.name == field.name) else {
+                self.push(ChangeKind::Breaking, &path, "field removed".to_string());
+                continue;
+            };
+            if field.ty != other.ty {
+                self.push(
+                    ChangeKind::Breaking,
+                    &path,
+                    format!(
+                        "type changed from {} to {}",
+                        printer::print_type(&field.ty),
+                        printer::print_type(&other.ty)
+                    ),
+                );
+            }
+            if field.optional != other.optional {
+                let message = if other.optional {
+                    "field became optional"
+                } else {
+                    "field became required"
+                };
+                self.push(ChangeKind::Breaking, &path, message.to_string());
+            }
+            self.annotations(&path, &field.annotations, &other.annotations);
+        }
+        for field in &new_fields {
+            if old_fields.iter().any(|other| other.name == field.name) {
+                continue;
+            }
+            let path = format!("{name}.{}", field.name);
+            if field.optional {
+                self.push(
+                    ChangeKind::Additive,
+                    &path,
+                    "optional field added".to_string(),
+                );
+            } else {
+                // Existing struct literals do not supply it.
+                self.push(
+                    ChangeKind::Breaking,
+                    &path,
+                    "required field added".to_string(),
+                );
+            }
+        }
+
+        for method in &old.methods {
+            let path = format!("{name}.{}", method.name);
+            match new.methods.iter().find(|other| other.name == method.name) {
+                Some(other) => self.callable(&path, method, other),
+                None => self.push(ChangeKind::Breaking, &path, "method removed".to_string()),
+            }
+        }
+        for method in &new.methods {
+            if !old.methods.iter().any(|other| other.name == method.name) {
+                let path = format!("{name}.{}", method.name);
+                self.push(ChangeKind::Additive, &path, "method added".to_string());
+            }
+        }
+    }
+
+    /// Every implementation must provide every method, so adding one breaks
+    /// implementors just as removing one breaks callers.
+    fn interface(&mut self, old: &ast::InterfaceDecl, new: &ast::InterfaceDecl) {
+        let name = &old.name;
+        for method in &old.methods {
+            let path = format!("{name}.{}", method.name);
+            match new.methods.iter().find(|other| other.name == method.name) {
+                Some(other) => self.signature(&path, method.into(), other.into()),
+                None => self.push(ChangeKind::Breaking, &path, "method removed".to_string()),
+            }
+        }
+        for method in &new.methods {
+            if !old.methods.iter().any(|other| other.name == method.name) {
+                let path = format!("{name}.{}", method.name);
+                self.push(
+                    ChangeKind::Breaking,
+                    &path,
+                    "method added; implementations must provide it".to_string(),
+                );
+            }
+        }
+    }
+
+    /// Callers supply every placeholder when rendering, so a new placeholder
+    /// breaks them; rewording the text around existing ones does not.
+    fn prompt(&mut self, old: &ast::PromptDecl, new: &ast::PromptDecl) {
+        let name = &old.name;
+        for section in &old.sections {
+            let path = format!("{name}.{}", section.name);
+            let Some(other) = new.sections.iter().find(|other| other.name == section.name) else {
+                self.push(ChangeKind::Breaking, &path, "section removed".to_string());
+                continue;
+            };
+            for placeholder in &other.placeholders {
+                if !section.placeholders.contains(placeholder) {
+                    self.push(
+                        ChangeKind::Breaking,
+                        &path,
+                        format!("placeholder `{{{placeholder}}}` added; callers must supply it"),
+                    );
+                }
+            }
+            if other.text != section.text {
+                self.push(ChangeKind::Internal, &path, "text changed".to_string());
+            }
+        }
+        for section in &new.sections {
+            if !old.sections.iter().any(|other| other.name == section.name) {
+                let path = format!("{name}.{}", section.name);
+                self.push(ChangeKind::Additive, &path, "section added".to_string());
+            }
+        }
+    }
+
+    fn callable(&mut self, path: &str, old: &ast::TaskDecl, new: &ast::TaskDecl) {
+        self.signature(path, old.into(), new.into());
+        self.constraints(path, &old.constraints, &new.constraints);
+        self.annotations(path, &old.annotations, &new.annotations);
+        self.retry(path, &old.retry, &new.retry);
+        self.timeout(path, old.timeout, new.timeout);
+        self.body(path, &old.body, &new.body);
+    }
+
+    fn timeout(&mut self, path: &str, old: Option<Duration>, new: Option<Duration>) {
+        if old != new {
+            let show = |timeout: Option<Duration>| match timeout {
+                Some(timeout) => printer::print_expression(&ast::Expression::Literal(
+                    ast::Literal::Duration(timeout),
+                )),
+                None => "none".to_string(),
+            };
+            self.push(
+                ChangeKind::Internal,
+                path,
+                format!("timeout changed from {} to {}", show(old), show(new)),
+            );
+        }
+    }
+
+    /// Like annotations, a `retry` clause changes behaviour but not usage.
+    fn retry(
+        &mut self,
+        path: &str,
+        old: &Option<ast::RetryPolicy>,
+        new: &Option<ast::RetryPolicy>,
+    ) {
+        let same = match (old, new) {
+            (Some(old), Some(new)) => old.max == new.max && old.backoff == new.backoff,
+            (old, new) => old.is_none() && new.is_none(),
+        };
+        if !same {
+            self.push(
+                ChangeKind::Internal,
+                path,
+                "retry policy changed".to_string(),
+            );
+        }
+    }
+
+    /// Annotations carry runtime policies such as retries and timeouts, which
+    /// change behaviour but not how the declaration is used.
+    fn annotations(&mut self, path: &str, old: &[ast::Annotation], new: &[ast::Annotation]) {
+        let same = old.len() == new.len()
+            && old
+                .iter()
+                .zip(new)
+                .all(|(old, new)| old.name == new.name && old.args == new.args);
+        if !same {
+            self.push(
+                ChangeKind::Internal,
+                path,
+                "annotations changed".to_string(),
+            );
+        }
+    }
+
+    /// A `where` clause narrows the accepted type arguments just like an
+    /// inline bound does, so any change to it can break existing uses.
+    fn constraints(&mut self, path: &str, old: &[ast::TypeParam], new: &[ast::TypeParam]) {
+        if old != new {
+            self.push(
+                ChangeKind::Breaking,
+                path,
+                format!(
+                    "where clause changed from `{}` to `{}`",
+                    type_param_list(old),
+                    type_param_list(new)
+                ),
+            );
+        }
+    }
+
+    fn signature(&mut self, path: &str, old: Signature<'_>, new: Signature<'_>) {
+        if old.type_params != new.type_params {
+            self.push(
+                ChangeKind::Breaking,
+                path,
+                format!(
+                    "type parameters changed from <{}> to <{}>",
+                    type_param_list(old.type_params),
+                    type_param_list(new.type_params)
+                ),
+            );
+        }
+        for (idx, param) in old.params.iter().enumerate() {
+            let param_path = format!("{path}({})", param.name);
+            let Some(other) = new.params.get(idx) else {
+                self.push(
+                    ChangeKind::Breaking,
+                    &param_path,
+                    "parameter removed".to_string(),
+                );
+                continue;
+            };
+            if other.name != param.name {
+                self.push(
+                    ChangeKind::Breaking,
+                    &param_path,
+                    format!("parameter renamed to `{}`", other.name),
+                );
+            }
+            if other.ty != param.ty {
+                self.push(
+                    ChangeKind::Breaking,
+                    &param_path,
+                    format!(
+                        "type changed from {} to {}",
+                        printer::print_type(&param.ty),
+                        printer::print_type(&other.ty)
+                    ),
+                );
+            }
+            match (&param.default, &other.default) {
+                (Some(_), None) => self.push(
+                    ChangeKind::Breaking,
+                    &param_path,
+                    "default value removed".to_string(),
+                ),
+                (None, Some(_)) => self.push(
+                    ChangeKind::Additive,
+                    &param_path,
+                    "default value added".to_string(),
+                ),
+                _ => {}
+            }
+        }
+        for param in new.params.iter().skip(old.params.len()) {
+            let param_path = format!("{path}({})", param.name);
+            if param.default.is_some() {
+                self.push(
+                    ChangeKind::Additive,
+                    &param_path,
+                    "parameter with default added".to_string(),
+                );
+            } else {
+                self.push(
+                    ChangeKind::Breaking,
+                    &param_path,
+                    "required parameter added".to_string(),
+                );
+            }
+        }
+
+        if old.is_async != new.is_async {
+            let change = if new.is_async {
+                "made async; callers must now await the result"
+            } else {
+                "no longer async"
+            };
+            self.push(ChangeKind::Breaking, path, change.to_string());
+        }
+        if old.return_type != new.return_type {
+            let render = |ty: &Option<ast::TypeExpr>| {
+                ty.as_ref()
+                    .map_or_else(|| "nothing".to_string(), printer::print_type)
+            };
+            self.push(
+                ChangeKind::Breaking,
+                path,
+                format!(
+                    "return type changed from {} to {}",
+                    render(old.return_type),
+                    render(new.return_type)
+                ),
+            );
+        }
+    }
+
+    fn body(&mut self, path: &str, old: &ast::Block, new: &ast::Block) {
+        if !old.raw.split_whitespace().eq(new.raw.split_whitespace()) {
+            self.push(ChangeKind::Internal, path, "body changed".to_string());
+        }
+    }
+}
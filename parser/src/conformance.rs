@@ -0,0 +1,141 @@
+//! Runs the parser against a shared corpus of expected outputs.
+//!
+//! A corpus is a directory of `<case>.hilo` inputs. Each input is paired with
+//! either `<case>.ast`, holding the expected [`ast::dump`] of a successful
+//! parse, or `<case>.err`, holding the expected error message. Surrounding
+//! whitespace in expectation files is ignored.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{ast, error::HiloParseError, options::ParseOptions, parser};
+
+/// Outcome of running one corpus case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    Failed {
+        expected: String,
+        actual: String,
+    },
+    /// Neither `<case>.ast` nor `<case>.err` exists.
+    MissingExpectation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseResult {
+    pub input: PathBuf,
+    pub outcome: Outcome,
+}
+
+/// Results for every case of a corpus, ordered by input path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub cases: Vec<CaseResult>,
+}
+
+impl Report {
+    pub fn passed(&self) -> usize {
+        self.count(|outcome| *outcome == Outcome::Passed)
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(|outcome| matches!(outcome, Outcome::Failed { .. }))
+    }
+
+    pub fn missing(&self) -> usize {
+        self.count(|outcome| *outcome == Outcome::MissingExpectation)
+    }
+
+    /// Whether every case passed.
+    pub fn is_success(&self) -> bool {
+        self.passed() == self.cases.len()
+    }
+
+    fn count(&self, pred: impl Fn(&Outcome) -> bool) -> usize {
+        self.cases.iter().filter(|case| pred(&case.outcome)).count()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for case in &self.cases {
+            match &case.outcome {
+                Outcome::Passed => {}
+                Outcome::Failed { expected, actual } => writeln!(
+                    f,
+                    "FAIL {}\n--- expected\n{expected}\n--- actual\n{actual}",
+                    case.input.display()
+                )?,
+                Outcome::MissingExpectation => {
+                    writeln!(f, "MISSING {}: no .ast or .err file", case.input.display())?
+                }
+            }
+        }
+        write!(
+            f,
+            "{} passed, {} failed, {} missing expectations",
+            self.passed(),
+            self.failed(),
+            self.missing()
+        )
+    }
+}
+
+/// Run every case in `dir` with strict parsing, so unrecognised input is
+/// reported as an error rather than preserved.
+pub fn run(dir: impl AsRef<Path>) -> Result<Report, HiloParseError> {
+    let options = ParseOptions {
+        strict: true,
+        ..ParseOptions::default()
+    };
+    run_with(dir, &options)
+}
+
+/// Run every case in `dir` with explicit [`ParseOptions`].
+pub fn run_with(dir: impl AsRef<Path>, options: &ParseOptions) -> Result<Report, HiloParseError> {
+    let mut inputs = Vec::new();
+    for entry in fs::read_dir(dir.as_ref())? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "hilo") {
+            inputs.push(path);
+        }
+    }
+    inputs.sort();
+
+    let mut report = Report::default();
+    for input in inputs {
+        let outcome = run_case(&input, options)?;
+        report.cases.push(CaseResult { input, outcome });
+    }
+    Ok(report)
+}
+
+fn run_case(input: &Path, options: &ParseOptions) -> Result<Outcome, HiloParseError> {
+    let source = fs::read_to_string(input)?;
+    let actual = match parser::parse_module(&source, options) {
+        Ok(module) => (ast::dump(&module), "ast"),
+        Err(err) => (err.to_string(), "err"),
+    };
+
+    let mut outcome = Outcome::MissingExpectation;
+    for ext in ["ast", "err"] {
+        let path = input.with_extension(ext);
+        if !path.exists() {
+            continue;
+        }
+        let expected = fs::read_to_string(path)?.trim().to_string();
+        let actual_text = actual.0.trim();
+        outcome = if actual.1 == ext && expected == actual_text {
+            Outcome::Passed
+        } else {
+            Outcome::Failed {
+                expected,
+                actual: actual_text.to_string(),
+            }
+        };
+        break;
+    }
+    Ok(outcome)
+}
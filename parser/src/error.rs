@@ -12,4 +12,19 @@ pub enum HiloParseError {
 
     #[error("parse error: {0}")]
     Parse(String),
+
+    #[error("nesting depth {depth} exceeds the configured limit of {limit}")]
+    DepthLimit { depth: usize, limit: usize },
+
+    #[error("invalid edit: {0}")]
+    InvalidEdit(String),
+
+    #[error("unresolved import `{0}`")]
+    UnresolvedImport(String),
+
+    #[error("`{name}` is internal to module `{module}`")]
+    InternalImport { module: String, name: String },
+
+    #[error("i/o error: {0}")]
+    Io(#[from] std::io::Error),
 }
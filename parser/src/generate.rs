@@ -0,0 +1,1132 @@
+//! Random well-formed HILO modules for fuzzing, benchmarks, and stress tests.
+//!
+//! Modules are built as ASTs and rendered with [`crate::printer`]. Generation
+//! is deterministic for a given [`GenConfig`], and every generated module is
+//! accepted by the parser in strict mode and parses back to the same tree
+//! (ignoring spans).
+//!
+//! (The module is not called `gen`, which is reserved in edition 2024.)
+
+use std::time::Duration;
+
+use crate::{ast, printer};
+
+/// Size and feature mix of a generated module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenConfig {
+    /// Seed for the generator; equal configs produce equal modules.
+    pub seed: u64,
+    pub imports: usize,
+    pub records: usize,
+    pub interfaces: usize,
+    pub tools: usize,
+    pub tasks: usize,
+    pub workflows: usize,
+    pub tests: usize,
+    pub prompts: usize,
+    /// Upper bound on statements per block.
+    pub max_statements: usize,
+    /// Upper bound on expression and block nesting.
+    pub max_depth: usize,
+    /// Emit `fn` methods inside records.
+    pub methods: bool,
+    /// Emit record type parameters and generic, list, and struct types.
+    pub generics: bool,
+    /// Emit optional fields and `T?` types.
+    pub optionals: bool,
+}
+
+impl Default for GenConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0x4849_4c4f,
+            imports: 2,
+            records: 3,
+            interfaces: 1,
+            tools: 1,
+            tasks: 4,
+            workflows: 1,
+            tests: 1,
+            prompts: 1,
+            max_statements: 5,
+            max_depth: 3,
+            methods: true,
+            generics: true,
+            optionals: true,
+        }
+    }
+}
+
+impl GenConfig {
+    /// A default-sized config with a different seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            ..Self::default()
+        }
+    }
+}
+
+/// Generate a module.
+pub fn module(config: &GenConfig) -> ast::Module {
+    Generator::new(config).module()
+}
+
+/// Generate a module and render it as source text.
+pub fn source(config: &GenConfig) -> String {
+    printer::print_module(&module(config))
+}
+
+const NOUNS: &[&str] = &[
+    "Source", "Brief", "Page", "Summary", "Query", "Result", "Report", "Draft", "Citation",
+    "Topic", "Claim", "Note",
+];
+const VERBS: &[&str] = &[
+    "fetch",
+    "summarize",
+    "rank",
+    "collect",
+    "draft",
+    "review",
+    "extract",
+    "classify",
+    "merge",
+    "publish",
+];
+const MEMBERS: &[&str] = &[
+    "title", "url", "body", "score", "tags", "author", "count", "limit", "text", "items",
+];
+const WORDS: &[&str] = &[
+    "alpha",
+    "beta",
+    "search",
+    "latest",
+    "news",
+    "weekly digest",
+    "error",
+    "ok",
+];
+const BOUNDS: &[&str] = &["Serializable", "Comparable", "Hashable"];
+const PRIMITIVES: &[&str] = &["String", "Int", "Float", "Bool", "Duration"];
+const MODULES: &[&[&str]] = &[
+    &["std", "http"],
+    &["std", "json"],
+    &["std", "text"],
+    &["std", "io"],
+    &["tools", "search"],
+];
+const OPERATORS: &[&str] = &[
+    "+", "-", "*", "/", "%", "==", "!=", "<", "<=", ">", ">=", "&&", "||", "??", "and", "or",
+];
+const UNARY_OPERATORS: &[&str] = &["!", "-", "not"];
+
+/// Names visible while generating a body.
+#[derive(Default, Clone)]
+struct Scope {
+    locals: Vec<ast::Ident>,
+}
+
+struct Generator<'a> {
+    config: &'a GenConfig,
+    rng: Rng,
+    records: Vec<(ast::Ident, Vec<ast::Ident>)>,
+    tasks: Vec<ast::Ident>,
+    fresh: usize,
+}
+
+impl<'a> Generator<'a> {
+    fn new(config: &'a GenConfig) -> Self {
+        Self {
+            config,
+            rng: Rng::new(config.seed),
+            records: Vec::new(),
+            tasks: Vec::new(),
+            fresh: 0,
+        }
+    }
+
+    fn module(mut self) -> ast::Module {
+        let name = vec!["generated".to_string(), self.lower_name(VERBS)];
+        let docs = self
+            .rng
+            .chance(3)
+            .then(|| format!("Generated module `{}`.", name.join(".")));
+        let imports = (0..self.config.imports).map(|_| self.import()).collect();
+
+        // Names are chosen up front so bodies can refer to later items.
+        self.records = (0..self.config.records)
+            .map(|idx| (numbered(NOUNS, idx), self.members()))
+            .collect();
+        self.tasks = (0..self.config.tasks)
+            .map(|idx| numbered(VERBS, idx))
+            .collect();
+
+        let mut items = Vec::new();
+        for idx in 0..self.config.records {
+            items.push(ast::Item::Record(self.record(idx)));
+        }
+        for idx in 0..self.config.interfaces {
+            let name = numbered(&["Summarizer", "Reviewer", "Fetcher"], idx);
+            items.push(ast::Item::Interface(self.interface(name)));
+        }
+        for _ in 0..self.config.tools {
+            items.push(ast::Item::Tool(self.tool()));
+        }
+        for idx in 0..self.config.tasks {
+            let name = self.tasks[idx].clone();
+            items.push(ast::Item::Task(self.callable(name, &Scope::default())));
+        }
+        for idx in 0..self.config.workflows {
+            items.push(ast::Item::Workflow(ast::WorkflowDecl {
+                docs: self.docs(),
+                annotations: self.annotations(),
+                visibility: self.visibility(),
+                name: numbered(&["Pipeline", "Flow", "Job"], idx),
+                body: self.workflow_body(),
+                steps: self.workflow_steps(),
+                on_error: self.error_handler(),
+                span: 0..0,
+            }));
+        }
+        if self.config.workflows > 0 && self.rng.chance(3) {
+            let target = self.rng.range(0, self.config.workflows);
+            items.push(ast::Item::Trigger(ast::TriggerDecl {
+                name: numbered(&["Daily", "Nightly", "Hourly"], 0),
+                cron: format!("{} {} * * *", self.rng.range(0, 60), self.rng.range(0, 24)),
+                run: vec![numbered(&["Pipeline", "Flow", "Job"], target)],
+                span: 0..0,
+            }));
+        }
+        if self.rng.chance(4) {
+            let source = self.rng.chance(2).then(|| ast::Expression::Call {
+                target: Box::new(ast::Expression::Identifier("vault".to_string())),
+                args: vec![ast::CallArg {
+                    name: None,
+                    value: ast::Expression::Literal(ast::Literal::Str(format!(
+                        "prod/{}",
+                        self.pick(WORDS)
+                    ))),
+                }],
+            });
+            items.push(ast::Item::Secret(ast::SecretDecl {
+                name: self.lower_name(&["api_key", "db_url", "token"]),
+                source,
+                span: 0..0,
+            }));
+        }
+        if self.rng.chance(4) {
+            let entries = (0..self.rng.range(1, 4))
+                .map(|_| ast::ConfigEntry {
+                    key: self.lower_name(MEMBERS),
+                    value: self.atom(&Scope::default()),
+                    span: 0..0,
+                })
+                .collect();
+            items.push(ast::Item::Config(ast::ConfigDecl {
+                entries,
+                span: 0..0,
+            }));
+        }
+        if self.rng.chance(4) {
+            let mut scope = Scope::default();
+            let binding = self.rng.chance(2).then(|| {
+                let name = self.lower_name(MEMBERS);
+                scope.locals.push(name.clone());
+                name
+            });
+            let noun = self.pick(WORDS);
+            let pattern = match self.rng.range(0, 3) {
+                0 => format!("{noun}.*"),
+                _ => format!("{noun}.{}", self.pick(&["created", "updated", "uploaded"])),
+            };
+            items.push(ast::Item::EventHandler(ast::EventHandlerDecl {
+                pattern,
+                binding,
+                body: self.block(&scope, 0),
+                span: 0..0,
+            }));
+        }
+        for idx in 0..self.config.prompts {
+            let name = numbered(&["ResearchPrompt", "ReviewPrompt"], idx);
+            items.push(ast::Item::Prompt(self.prompt(name)));
+        }
+        for idx in 0..self.config.tests {
+            let words = format!("{} {}", self.pick(VERBS), self.pick(WORDS));
+            items.push(ast::Item::Test(ast::TestDecl {
+                name: format!("{words} {idx}"),
+                body: self.block(&Scope::default(), 0),
+                span: 0..0,
+            }));
+        }
+        if self.rng.chance(6) {
+            let helpers = (0..self.rng.range(1, 3))
+                .map(|_| ast::Item::Task(self.helper()))
+                .collect();
+            items.push(ast::Item::Module(ast::Module {
+                docs: self.docs(),
+                name: Some(vec![self.lower_name(&["helpers", "util", "text"])]),
+                imports: Vec::new(),
+                items: helpers,
+                comments: Vec::new(),
+                span: 0..0,
+            }));
+        }
+        if !self.tasks.is_empty() && self.rng.chance(4) {
+            let count = self.rng.range(1, self.tasks.len() + 1);
+            items.push(ast::Item::Export(ast::ExportDecl {
+                names: self.tasks[..count].to_vec(),
+                span: 0..0,
+            }));
+        }
+        if self.rng.chance(6) {
+            items.push(ast::Item::ReExport(ast::ReExportDecl {
+                path: self.pick(MODULES).iter().map(|s| s.to_string()).collect(),
+                members: self
+                    .rng
+                    .chance(2)
+                    .then(|| vec![self.pick(VERBS).to_string()]),
+                span: 0..0,
+            }));
+        }
+
+        ast::Module {
+            docs,
+            name: Some(name),
+            imports,
+            items,
+            comments: Vec::new(),
+            span: 0..0,
+        }
+    }
+
+    fn import(&mut self) -> ast::Import {
+        let relative: &[&str] = match self.rng.range(0, 8) {
+            0 => &["."],
+            1 => &[".."],
+            _ => &[],
+        };
+        let path: ast::QualifiedName = relative
+            .iter()
+            .chain(self.pick(MODULES))
+            .map(|s| s.to_string())
+            .collect();
+        let members = self.rng.chance(2).then(|| {
+            (0..self.rng.range(1, 3))
+                .map(|_| self.pick(VERBS).to_string())
+                .collect()
+        });
+        let version = self
+            .rng
+            .chance(6)
+            .then(|| format!("{}.{}", self.rng.range(0, 3), self.rng.range(0, 10)));
+        let alias = self.rng.chance(4).then(|| self.pick(NOUNS).to_string());
+        ast::Import {
+            path,
+            version,
+            members,
+            alias,
+        }
+    }
+
+    fn record(&mut self, idx: usize) -> ast::RecordDecl {
+        let (name, members) = self.records[idx].clone();
+        let type_params = self.type_params();
+        let fields = members
+            .iter()
+            .map(|member| ast::RecordField {
+                docs: self.docs(),
+                annotations: self.annotations(),
+                name: member.clone(),
+                optional: self.config.optionals && self.rng.chance(4),
+                ty: self.ty(0),
+                span: 0..0,
+            })
+            .collect();
+        let scope = Scope {
+            locals: members.clone(),
+        };
+        let methods = if self.config.methods {
+            (0..self.rng.range(0, 2))
+                .map(|_| {
+                    let name = self.lower_name(VERBS);
+                    self.callable(name, &scope)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        ast::RecordDecl {
+            docs: self.docs(),
+            annotations: self.annotations(),
+            visibility: self.visibility(),
+            name,
+            constraints: self.constraints(&type_params),
+            type_params,
+            extends: None,
+            fields,
+            methods,
+            span: 0..0,
+        }
+    }
+
+    fn interface(&mut self, name: ast::Ident) -> ast::InterfaceDecl {
+        let methods = (0..self.rng.range(1, 4))
+            .map(|_| ast::MethodSig {
+                name: self.lower_name(VERBS),
+                is_async: self.rng.chance(4),
+                type_params: self.type_params(),
+                params: (0..self.rng.range(0, 3))
+                    .map(|_| ast::Param {
+                        docs: None,
+                        name: self.lower_name(MEMBERS),
+                        ty: self.ty(0),
+                        default: None,
+                        span: 0..0,
+                    })
+                    .collect(),
+                return_type: self.rng.chance(2).then(|| self.ty(0)),
+                span: 0..0,
+            })
+            .collect();
+        ast::InterfaceDecl {
+            name,
+            methods,
+            span: 0..0,
+        }
+    }
+
+    fn prompt(&mut self, name: ast::Ident) -> ast::PromptDecl {
+        let sections = ["system", "user"]
+            .into_iter()
+            .map(|section| {
+                let mut text = format!("{} the {}", self.pick(VERBS), self.pick(WORDS));
+                let mut placeholders = Vec::new();
+                for _ in 0..self.rng.range(0, 3) {
+                    let placeholder = if self.rng.chance(2) {
+                        format!("{}.{}", self.lower_name(NOUNS), self.pick(MEMBERS))
+                    } else {
+                        self.pick(MEMBERS).to_string()
+                    };
+                    text.push_str(&format!(" for {{{placeholder}}}"));
+                    if !placeholders.contains(&placeholder) {
+                        placeholders.push(placeholder);
+                    }
+                }
+                if self.rng.chance(4) {
+                    text.push_str(".\nReply as JSON: {{\"ok\": true}}");
+                }
+                ast::PromptSection {
+                    name: section.to_string(),
+                    text,
+                    placeholders,
+                    span: 0..0,
+                }
+            })
+            .collect();
+        ast::PromptDecl {
+            name,
+            sections,
+            span: 0..0,
+        }
+    }
+
+    fn tool(&mut self) -> ast::ToolDecl {
+        let mut name: ast::QualifiedName = self
+            .pick(MODULES)
+            .iter()
+            .map(|part| part.to_string())
+            .collect();
+        name.push(self.lower_name(VERBS));
+        ast::ToolDecl {
+            name,
+            params: (0..self.rng.range(0, 3))
+                .map(|_| ast::Param {
+                    docs: None,
+                    name: self.lower_name(MEMBERS),
+                    ty: self.ty(0),
+                    default: None,
+                    span: 0..0,
+                })
+                .collect(),
+            return_type: self.rng.chance(2).then(|| self.ty(0)),
+            span: 0..0,
+        }
+    }
+
+    fn callable(&mut self, name: ast::Ident, outer: &Scope) -> ast::TaskDecl {
+        let mut scope = outer.clone();
+        let params = (0..self.rng.range(0, 3))
+            .map(|_| {
+                let name = self.lower_name(MEMBERS);
+                scope.locals.push(name.clone());
+                ast::Param {
+                    docs: self.docs(),
+                    name,
+                    ty: self.ty(0),
+                    default: self.rng.chance(4).then(|| self.atom(outer)),
+                    span: 0..0,
+                }
+            })
+            .collect();
+        let return_type = self.rng.chance(2).then(|| self.ty(0));
+        let mut body = self.block(&scope, 0);
+        if self.rng.chance(6) {
+            body.statements
+                .insert(0, ast::Statement::FnDecl(self.helper()));
+        }
+        if return_type.is_some() {
+            let value = self.expression(&scope, 0);
+            body.statements
+                .push(ast::Statement::Return { value: Some(value) });
+        }
+        let type_params = self.type_params();
+        ast::TaskDecl {
+            docs: self.docs(),
+            annotations: self.annotations(),
+            visibility: self.visibility(),
+            name,
+            is_async: self.rng.chance(4),
+            constraints: self.constraints(&type_params),
+            type_params,
+            params,
+            return_type,
+            retry: self.retry_policy(),
+            timeout: self.timeout(),
+            body,
+            span: 0..0,
+        }
+    }
+
+    /// `fn name(value: String) -> String { return value }`, nested in a body.
+    fn helper(&mut self) -> ast::TaskDecl {
+        let param = self.lower_name(MEMBERS);
+        ast::TaskDecl {
+            docs: None,
+            annotations: Vec::new(),
+            visibility: ast::Visibility::Unspecified,
+            name: self.lower_name(VERBS),
+            is_async: false,
+            type_params: Vec::new(),
+            params: vec![ast::Param {
+                docs: None,
+                name: param.clone(),
+                ty: ast::TypeExpr::Simple(vec!["String".to_string()]),
+                default: None,
+                span: 0..0,
+            }],
+            return_type: Some(ast::TypeExpr::Simple(vec!["String".to_string()])),
+            constraints: Vec::new(),
+            retry: None,
+            timeout: None,
+            body: block(vec![ast::Statement::Return {
+                value: Some(ast::Expression::Identifier(param)),
+            }]),
+            span: 0..0,
+        }
+    }
+
+    /// Usually none; otherwise one of `@pure`, `@timeout(30s)`, or
+    /// `@retry(max: 3, backoff: "exp")`.
+    fn annotations(&mut self) -> Vec<ast::Annotation> {
+        if !self.rng.chance(4) {
+            return Vec::new();
+        }
+        let literal = |literal| ast::Expression::Literal(literal);
+        let (name, args) = match self.rng.range(0, 3) {
+            0 => ("pure", Vec::new()),
+            1 => (
+                "timeout",
+                vec![ast::AnnotationArg {
+                    name: None,
+                    value: literal(ast::Literal::Duration(Duration::from_secs(
+                        self.rng.range(1, 120) as u64,
+                    ))),
+                }],
+            ),
+            _ => (
+                "retry",
+                vec![
+                    ast::AnnotationArg {
+                        name: Some("max".to_string()),
+                        value: literal(ast::Literal::Int(self.rng.range(1, 5) as i64)),
+                    },
+                    ast::AnnotationArg {
+                        name: Some("backoff".to_string()),
+                        value: literal(ast::Literal::Str(self.pick(&["exp", "linear"]).into())),
+                    },
+                ],
+            ),
+        };
+        vec![ast::Annotation {
+            name: name.to_string(),
+            args,
+            span: 0..0,
+        }]
+    }
+
+    /// Workflow bodies are sequences of labelled steps, sometimes followed by
+    /// a `branch on` between them or a `parallel` fan-out.
+    fn workflow_body(&mut self) -> ast::Block {
+        let mut labels = Vec::new();
+        let mut statements = Vec::new();
+        for _ in 0..self.rng.range(1, 4) {
+            let annotations = self.annotations();
+            let label = self.lower_name(&["start", "gather", "review", "finish"]);
+            labels.push(label.clone());
+            let step = ast::Statement::Block {
+                label: Some(label),
+                body: self.block(&Scope::default(), 1),
+            };
+            statements.push(if annotations.is_empty() {
+                step
+            } else {
+                ast::Statement::Annotated {
+                    annotations,
+                    statement: Box::new(step),
+                }
+            });
+        }
+        if self.rng.chance(3) {
+            let arms = (0..self.rng.range(0, 3))
+                .map(|_| ast::BranchArm {
+                    pattern: ast::Pattern::Literal(ast::Literal::Str(self.pick(WORDS).to_string())),
+                    target: labels[self.rng.range(0, labels.len())].clone(),
+                })
+                .collect();
+            let otherwise = self
+                .rng
+                .chance(2)
+                .then(|| labels[self.rng.range(0, labels.len())].clone());
+            statements.push(ast::Statement::Branch {
+                subject: ast::Expression::Identifier("status".to_string()),
+                arms,
+                otherwise,
+            });
+        }
+        if self.rng.chance(3) {
+            let branches = (0..self.rng.range(2, 4))
+                .map(|_| self.block(&Scope::default(), 1))
+                .collect();
+            statements.push(ast::Statement::Parallel(branches));
+        }
+        block(statements)
+    }
+
+    /// Up to three `step`s, each taking some of the earlier ones as inputs.
+    fn workflow_steps(&mut self) -> Vec<ast::StepDecl> {
+        let mut steps: Vec<ast::StepDecl> = Vec::new();
+        for _ in 0..self.rng.range(0, 4) {
+            let inputs = steps
+                .iter()
+                .filter(|_| self.rng.chance(2))
+                .map(|step| step.name.clone())
+                .collect();
+            steps.push(ast::StepDecl {
+                name: self.lower_name(&["fetch", "summarize", "publish"]),
+                inputs,
+                retry: self.retry_policy(),
+                timeout: self.timeout(),
+                body: self.block(&Scope::default(), 1),
+                on_error: self.error_handler(),
+                span: 0..0,
+            });
+        }
+        steps
+    }
+
+    /// An occasional `retry(...)` clause with one or both arguments.
+    fn retry_policy(&mut self) -> Option<ast::RetryPolicy> {
+        if !self.rng.chance(5) {
+            return None;
+        }
+        let delay = Duration::from_secs(self.rng.range(1, 30) as u64);
+        let backoff = match self.rng.range(0, 4) {
+            0 => None,
+            1 => Some(ast::Backoff::Fixed(delay)),
+            2 => Some(ast::Backoff::Linear(delay)),
+            _ => Some(ast::Backoff::Exponential(delay)),
+        };
+        Some(ast::RetryPolicy {
+            max: (backoff.is_none() || self.rng.chance(2)).then(|| self.rng.range(1, 6) as u32),
+            backoff,
+            span: 0..0,
+        })
+    }
+
+    /// An occasional `timeout` of up to ten minutes.
+    fn timeout(&mut self) -> Option<Duration> {
+        self.rng
+            .chance(5)
+            .then(|| Duration::from_secs(self.rng.range(1, 600) as u64))
+    }
+
+    /// An occasional `on_error` clause, usually binding the error.
+    fn error_handler(&mut self) -> Option<ast::ErrorHandler> {
+        if !self.rng.chance(4) {
+            return None;
+        }
+        let mut scope = Scope::default();
+        let binding = (!self.rng.chance(3)).then(|| {
+            let name = self.lower_name(MEMBERS);
+            scope.locals.push(name.clone());
+            name
+        });
+        Some(ast::ErrorHandler {
+            binding,
+            body: self.block(&scope, 1),
+            span: 0..0,
+        })
+    }
+
+    fn block(&mut self, outer: &Scope, depth: usize) -> ast::Block {
+        let mut scope = outer.clone();
+        let count = self.rng.range(0, self.config.max_statements + 1);
+        let statements = (0..count)
+            .map(|_| self.statement(&mut scope, depth))
+            .collect();
+        block(statements)
+    }
+
+    fn statement(&mut self, scope: &mut Scope, depth: usize) -> ast::Statement {
+        match self.rng.range(0, 10) {
+            0 if depth < self.config.max_depth => ast::Statement::Block {
+                label: self.rng.chance(2).then(|| self.lower_name(MEMBERS)),
+                body: self.block(scope, depth + 1),
+            },
+            1 => ast::Statement::Expr(self.call(scope, 0)),
+            2 if depth < self.config.max_depth => {
+                let iterable = self.atom(scope);
+                let parallel = self.rng.chance(2);
+                let limit = (parallel && self.rng.chance(2)).then(|| {
+                    ast::Expression::Literal(ast::Literal::Int(self.rng.range(1, 9) as i64))
+                });
+                let binding = self.lower_name(MEMBERS);
+                let mut inner = scope.clone();
+                inner.locals.push(binding.clone());
+                let body = self.block(&inner, depth + 1);
+                if parallel {
+                    ast::Statement::ParallelFor {
+                        binding,
+                        iterable,
+                        limit,
+                        body,
+                    }
+                } else {
+                    ast::Statement::For {
+                        binding,
+                        iterable,
+                        body,
+                    }
+                }
+            }
+            3 if depth < self.config.max_depth => ast::Statement::While {
+                condition: self.expression(scope, 1),
+                body: self.block(scope, depth + 1),
+            },
+            4 if depth < self.config.max_depth => {
+                ast::Statement::Expr(self.match_expression(scope, depth))
+            }
+            5 if depth < self.config.max_depth => self.try_statement(scope, depth),
+            6 => ast::Statement::Throw(self.call(scope, 0)),
+            7 => ast::Statement::Guard {
+                condition: self.expression(scope, 1),
+                else_body: block(vec![ast::Statement::Return { value: None }]),
+            },
+            8 => ast::Statement::Assert(self.expression(scope, 1)),
+            _ => {
+                let value = self.expression(scope, 0);
+                let ty = self.rng.chance(3).then(|| self.ty(0));
+                let name = self.lower_name(MEMBERS);
+                scope.locals.push(name.clone());
+                ast::Statement::Let {
+                    name,
+                    ty,
+                    value: Some(value),
+                }
+            }
+        }
+    }
+
+    fn expression(&mut self, scope: &Scope, depth: usize) -> ast::Expression {
+        if depth >= self.config.max_depth {
+            return self.atom(scope);
+        }
+        match self.rng.range(0, 10) {
+            0 => self.call(scope, depth),
+            1 => self.postfix(scope, depth),
+            2 => self.struct_literal(scope, depth),
+            3 => ast::Expression::Binary {
+                left: Box::new(self.expression(scope, depth + 1)),
+                op: self.pick(OPERATORS).to_string(),
+                right: Box::new(self.expression(scope, depth + 1)),
+            },
+            4 => ast::Expression::Unary {
+                op: self.pick(UNARY_OPERATORS).to_string(),
+                operand: Box::new(self.expression(scope, depth + 1)),
+            },
+            5 => self.lambda(scope, depth),
+            6 => ast::Expression::Await(Box::new(self.call(scope, depth))),
+            7 => ast::Expression::List(self.elements(scope, depth)),
+            8 => ast::Expression::Tuple(
+                (0..self.rng.range(2, 4))
+                    .map(|_| self.expression(scope, depth + 1))
+                    .collect(),
+            ),
+            _ => self.atom(scope),
+        }
+    }
+
+    fn lambda(&mut self, scope: &Scope, depth: usize) -> ast::Expression {
+        let mut inner = scope.clone();
+        let params = (0..self.rng.range(1, 3))
+            .map(|_| {
+                let name = self.lower_name(MEMBERS);
+                inner.locals.push(name.clone());
+                let ty = self.rng.chance(2).then(|| self.ty(depth + 1));
+                ast::LambdaParam { name, ty }
+            })
+            .collect();
+        // Statements restart the expression depth, so a generated block body
+        // stays a single `return` to keep the output bounded.
+        let body = if self.rng.chance(3) {
+            ast::ArmBody::Block(block(vec![ast::Statement::Return {
+                value: Some(self.atom(&inner)),
+            }]))
+        } else {
+            ast::ArmBody::Expr(self.expression(&inner, depth + 1))
+        };
+        ast::Expression::Lambda {
+            params,
+            body: Box::new(body),
+        }
+    }
+
+    /// `try` with up to two catch clauses, and a `finally` block when there
+    /// are none.
+    fn try_statement(&mut self, scope: &Scope, depth: usize) -> ast::Statement {
+        let body = self.block(scope, depth + 1);
+        let catches: Vec<ast::CatchClause> = (0..self.rng.range(0, 3))
+            .map(|_| {
+                let mut inner = scope.clone();
+                let binding = (!self.rng.chance(4)).then(|| {
+                    let name = self.lower_name(MEMBERS);
+                    inner.locals.push(name.clone());
+                    name
+                });
+                let ty = (binding.is_some() && !self.rng.chance(3)).then(|| self.ty(0));
+                ast::CatchClause {
+                    binding,
+                    ty,
+                    body: self.block(&inner, depth + 1),
+                }
+            })
+            .collect();
+        let finally =
+            (catches.is_empty() || self.rng.chance(3)).then(|| self.block(scope, depth + 1));
+        ast::Statement::Try {
+            body,
+            catches,
+            finally,
+        }
+    }
+
+    /// Up to two call arguments or list elements, occasionally spread.
+    fn elements(&mut self, scope: &Scope, depth: usize) -> Vec<ast::Expression> {
+        (0..self.rng.range(0, 3))
+            .map(|_| {
+                if self.rng.chance(5) {
+                    ast::Expression::Spread(Box::new(self.identifier(scope)))
+                } else {
+                    self.expression(scope, depth + 1)
+                }
+            })
+            .collect()
+    }
+
+    fn call(&mut self, scope: &Scope, depth: usize) -> ast::Expression {
+        let target = if self.tasks.is_empty() || self.rng.chance(4) {
+            ast::Expression::Member {
+                target: Box::new(self.identifier(scope)),
+                property: self.pick(VERBS).to_string(),
+            }
+        } else {
+            let idx = self.rng.range(0, self.tasks.len());
+            ast::Expression::Identifier(self.tasks[idx].clone())
+        };
+        let args = self
+            .elements(scope, depth)
+            .into_iter()
+            .map(|value| ast::CallArg {
+                name: (!matches!(value, ast::Expression::Spread(_)) && self.rng.chance(4))
+                    .then(|| self.pick(MEMBERS).to_string()),
+                value,
+            })
+            .collect();
+        ast::Expression::Call {
+            target: Box::new(target),
+            args,
+        }
+    }
+
+    /// Member access, optional chaining, or indexing on a name.
+    fn postfix(&mut self, scope: &Scope, depth: usize) -> ast::Expression {
+        let target = Box::new(self.identifier(scope));
+        match self.rng.range(0, 3) {
+            0 => ast::Expression::Member {
+                target,
+                property: self.pick(MEMBERS).to_string(),
+            },
+            1 => ast::Expression::OptionalChain {
+                target,
+                property: self.pick(MEMBERS).to_string(),
+            },
+            _ => ast::Expression::Index {
+                target,
+                index: Box::new(self.expression(scope, depth + 1)),
+            },
+        }
+    }
+
+    fn struct_literal(&mut self, scope: &Scope, depth: usize) -> ast::Expression {
+        if self.records.is_empty() {
+            return self.atom(scope);
+        }
+        let idx = self.rng.range(0, self.records.len());
+        let (name, members) = self.records[idx].clone();
+        let fields = members
+            .into_iter()
+            .map(|member| (member, self.expression(scope, depth + 1)))
+            .collect();
+        ast::Expression::StructLiteral {
+            type_name: vec![name],
+            fields,
+        }
+    }
+
+    fn match_expression(&mut self, scope: &Scope, depth: usize) -> ast::Expression {
+        let subject = self.atom(scope);
+        let arms = (0..self.rng.range(1, 4))
+            .map(|_| {
+                let mut inner = scope.clone();
+                let pattern = match self.rng.range(0, 4) {
+                    0 => ast::Pattern::Wildcard,
+                    1 => match self.atom(&Scope::default()) {
+                        ast::Expression::Literal(literal) => ast::Pattern::Literal(literal),
+                        _ => ast::Pattern::Wildcard,
+                    },
+                    _ => {
+                        let binding = self.lower_name(MEMBERS);
+                        inner.locals.push(binding.clone());
+                        ast::Pattern::Variant {
+                            path: vec![self.pick(&["Ok", "Err", "Some"]).to_string()],
+                            fields: vec![ast::Pattern::Binding(binding)],
+                        }
+                    }
+                };
+                let guard = self.rng.chance(3).then(|| self.atom(&inner));
+                let body = if self.rng.chance(2) {
+                    ast::ArmBody::Block(self.block(&inner, depth + 1))
+                } else {
+                    ast::ArmBody::Expr(self.call(&inner, 1))
+                };
+                ast::MatchArm {
+                    pattern,
+                    guard,
+                    body,
+                }
+            })
+            .collect();
+        ast::Expression::Match {
+            subject: Box::new(subject),
+            arms,
+        }
+    }
+
+    fn atom(&mut self, scope: &Scope) -> ast::Expression {
+        if !scope.locals.is_empty() && self.rng.chance(2) {
+            return self.identifier(scope);
+        }
+        let literal = match self.rng.range(0, 6) {
+            0 => ast::Literal::Int(self.rng.range(0, 1000) as i64),
+            1 => ast::Literal::Float(self.rng.range(0, 100) as f64 / 4.0),
+            2 => ast::Literal::Bool(self.rng.chance(2)),
+            3 => ast::Literal::Duration(Duration::from_secs(
+                self.rng.range(1, 60) as u64 * [1, 60, 3_600][self.rng.range(0, 3)],
+            )),
+            4 if self.config.optionals => ast::Literal::Null,
+            _ => ast::Literal::Str(self.pick(WORDS).to_string()),
+        };
+        ast::Expression::Literal(literal)
+    }
+
+    fn identifier(&mut self, scope: &Scope) -> ast::Expression {
+        let name = if scope.locals.is_empty() {
+            self.lower_name(MEMBERS)
+        } else {
+            let idx = self.rng.range(0, scope.locals.len());
+            scope.locals[idx].clone()
+        };
+        ast::Expression::Identifier(name)
+    }
+
+    /// A one- or two-line doc comment on roughly one declaration in five.
+    fn docs(&mut self) -> Option<String> {
+        if !self.rng.chance(5) {
+            return None;
+        }
+        let summary = format!("{} the {}.", self.pick(VERBS), self.pick(NOUNS));
+        if self.rng.chance(2) {
+            return Some(format!("{summary}\n\nSee also `{}`.", self.pick(NOUNS)));
+        }
+        Some(summary)
+    }
+
+    fn visibility(&mut self) -> ast::Visibility {
+        match self.rng.range(0, 6) {
+            0 => ast::Visibility::Public,
+            1 => ast::Visibility::Internal,
+            _ => ast::Visibility::Unspecified,
+        }
+    }
+
+    /// `<T>` or `<T: Bound + Other>` on roughly one declaration in four.
+    fn type_params(&mut self) -> Vec<ast::TypeParam> {
+        if !self.config.generics || !self.rng.chance(4) {
+            return Vec::new();
+        }
+        let bounds = (0..self.rng.range(0, 3))
+            .map(|_| simple(self.pick(BOUNDS)))
+            .collect();
+        vec![ast::TypeParam {
+            name: "T".to_string(),
+            bounds,
+        }]
+    }
+
+    /// `where T: Bound` for some of the declared type parameters.
+    fn constraints(&mut self, type_params: &[ast::TypeParam]) -> Vec<ast::TypeParam> {
+        let mut constraints = Vec::new();
+        for param in type_params {
+            if self.rng.chance(3) {
+                constraints.push(ast::TypeParam {
+                    name: param.name.clone(),
+                    bounds: vec![simple(self.pick(BOUNDS))],
+                });
+            }
+        }
+        constraints
+    }
+
+    fn ty(&mut self, depth: usize) -> ast::TypeExpr {
+        let nested = self.config.generics && depth < self.config.max_depth;
+        let ty = match self.rng.range(0, 7) {
+            0 if nested => ast::TypeExpr::List(Box::new(self.ty(depth + 1))),
+            1 if nested => ast::TypeExpr::Map {
+                key: Box::new(simple("String")),
+                value: Box::new(self.ty(depth + 1)),
+            },
+            2 if nested => ast::TypeExpr::Struct(
+                (0..self.rng.range(1, 3))
+                    .map(|_| ast::StructFieldType {
+                        name: self.lower_name(MEMBERS),
+                        optional: self.config.optionals && self.rng.chance(3),
+                        ty: self.ty(depth + 1),
+                    })
+                    .collect(),
+            ),
+            3 if nested => ast::TypeExpr::Tuple(
+                (0..self.rng.range(2, 4))
+                    .map(|_| self.ty(depth + 1))
+                    .collect(),
+            ),
+            4 if !self.records.is_empty() => {
+                let idx = self.rng.range(0, self.records.len());
+                simple(&self.records[idx].0.clone())
+            }
+            _ => simple(self.pick(PRIMITIVES)),
+        };
+        if self.config.optionals && self.rng.chance(5) {
+            ast::TypeExpr::Optional(Box::new(ty))
+        } else {
+            ty
+        }
+    }
+
+    /// Distinct lower-case member names for one record.
+    fn members(&mut self) -> Vec<ast::Ident> {
+        let mut members = Vec::new();
+        for _ in 0..self.rng.range(1, 5) {
+            let member = self.pick(MEMBERS).to_string();
+            if !members.contains(&member) {
+                members.push(member);
+            }
+        }
+        members
+    }
+
+    /// A pool word with a counter suffix, so names never collide with keywords
+    /// or each other.
+    fn lower_name(&mut self, pool: &[&str]) -> ast::Ident {
+        self.fresh += 1;
+        format!("{}{}", self.pick(pool).to_lowercase(), self.fresh)
+    }
+
+    fn pick<T: Copy>(&mut self, pool: &[T]) -> T {
+        pool[self.rng.range(0, pool.len())]
+    }
+}
+
+/// `pool[idx]`, suffixed with a round number once the pool is exhausted.
+fn numbered(pool: &[&str], idx: usize) -> ast::Ident {
+    let mut name = pool[idx % pool.len()].to_string();
+    if let Some(first) = name.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    match idx / pool.len() {
+        0 => name,
+        round => format!("{name}{round}"),
+    }
+}
+
+fn simple(name: &str) -> ast::TypeExpr {
+    ast::TypeExpr::Simple(vec![name.to_string()])
+}
+
+fn block(statements: Vec<ast::Statement>) -> ast::Block {
+    ast::Block {
+        raw: String::new(),
+        statements,
+        span: 0..0,
+        lifted: Vec::new(),
+    }
+}
+
+/// xorshift64*: small, fast, and stable across platforms and releases.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // A zero state would stay zero forever.
+        Self((seed ^ 0x9e37_79b9_7f4a_7c15).max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Uniform-ish value in `low..high`; `low` when the range is empty.
+    fn range(&mut self, low: usize, high: usize) -> usize {
+        if high <= low {
+            return low;
+        }
+        low + (self.next() % (high - low) as u64) as usize
+    }
+
+    /// True with probability `1 / n`.
+    fn chance(&mut self, n: u64) -> bool {
+        self.next().is_multiple_of(n)
+    }
+}
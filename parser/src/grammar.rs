@@ -0,0 +1,873 @@
+//! Machine-readable description of the syntax this parser accepts.
+//!
+//! The rules mirror the hand-written scanner in `parser.rs` rather than the
+//! full draft grammar in `hilo/spec/GRAMMAR.md`; keyword and operator sets are
+//! read from the parser's own tables. Update the rules here whenever the
+//! parser learns new syntax; a test fails when `parse_items` dispatches on an
+//! item keyword that no rule mentions.
+
+use std::fmt::Write as _;
+
+use serde_json::{Value, json};
+
+use crate::parser;
+
+/// A set of named productions plus the reserved words of the language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grammar {
+    pub rules: Vec<Rule>,
+    pub keywords: Vec<&'static str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub name: &'static str,
+    pub expr: Expr,
+}
+
+/// Right-hand side of a production.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    /// Literal text, e.g. `"record"`.
+    Terminal(String),
+    /// Reference to another rule.
+    Rule(&'static str),
+    /// A lexical class such as `IDENT`, `STRING`, `INT`, `DURATION`, or
+    /// `PATTERN` (an identifier that may contain `*` wildcards).
+    Token(&'static str),
+    Sequence(Vec<Expr>),
+    Choice(Vec<Expr>),
+    Optional(Box<Expr>),
+    Repeat(Box<Expr>),
+}
+
+impl Grammar {
+    /// Render as EBNF in the notation of `hilo/spec/GRAMMAR.md`.
+    pub fn to_ebnf(&self) -> String {
+        let width = self
+            .rules
+            .iter()
+            .map(|rule| rule.name.len())
+            .max()
+            .unwrap_or(0);
+        let mut out = String::new();
+        for rule in &self.rules {
+            let _ = writeln!(out, "{:<width$} = {} ;", rule.name, ebnf(&rule.expr, false));
+        }
+        out
+    }
+
+    /// Render as JSON: `{ "rules": [{ "name", "expr" }], "keywords": [...] }`,
+    /// where each `expr` is a node tagged by `type`, suitable for railroad
+    /// diagram generators.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "rules": self
+                .rules
+                .iter()
+                .map(|rule| json!({ "name": rule.name, "expr": expr_json(&rule.expr) }))
+                .collect::<Vec<_>>(),
+            "keywords": self.keywords,
+        })
+    }
+}
+
+/// Describe the grammar accepted by [`crate::parse_module`].
+pub fn describe() -> Grammar {
+    use Expr::*;
+
+    let rules = vec![
+        rule(
+            "Program",
+            seq([
+                opt(Rule("ModuleDecl")),
+                many(Rule("Import")),
+                many(Rule("Item")),
+            ]),
+        ),
+        rule("ModuleDecl", seq([t("module"), Rule("QName")])),
+        rule(
+            "Import",
+            seq([
+                t("import"),
+                Rule("ImportPath"),
+                opt(seq([t("@"), Token("STRING")])),
+                opt(Choice(vec![
+                    seq([Rule("ImportAlias"), opt(Rule("ImportList"))]),
+                    seq([Rule("ImportList"), opt(Rule("ImportAlias"))]),
+                ])),
+            ]),
+        ),
+        rule(
+            "ImportPath",
+            seq([
+                opt(Choice(vec![t("./"), seq([t("../"), many(t("../"))])])),
+                Rule("QName"),
+            ]),
+        ),
+        rule("ImportAlias", seq([t("as"), Token("IDENT")])),
+        rule(
+            "ImportList",
+            seq([
+                t("{"),
+                Token("IDENT"),
+                many(seq([t(","), Token("IDENT")])),
+                t("}"),
+            ]),
+        ),
+        rule(
+            "QName",
+            seq([Token("IDENT"), many(seq([t("."), Token("IDENT")]))]),
+        ),
+        rule(
+            "Item",
+            Choice(vec![
+                Rule("RecordDecl"),
+                Rule("Interface"),
+                Rule("TaskDecl"),
+                Rule("WorkflowDecl"),
+                Rule("TestDecl"),
+                Rule("HookDecl"),
+                Rule("PromptDecl"),
+                Rule("ToolDecl"),
+                Rule("TriggerDecl"),
+                Rule("EventHandler"),
+                Rule("ConfigBlock"),
+                Rule("SecretDecl"),
+                Rule("ExportList"),
+                Rule("ReExport"),
+                Rule("NestedModule"),
+            ]),
+        ),
+        rule(
+            "NestedModule",
+            seq([
+                t("module"),
+                Token("IDENT"),
+                t("{"),
+                many(Rule("Item")),
+                t("}"),
+            ]),
+        ),
+        rule(
+            "ExportList",
+            seq([
+                t("export"),
+                t("{"),
+                Token("IDENT"),
+                many(seq([t(","), Token("IDENT")])),
+                t("}"),
+            ]),
+        ),
+        rule(
+            "ReExport",
+            seq([
+                t("export"),
+                Choice(vec![
+                    seq([t("*"), t("from"), Rule("QName")]),
+                    seq([Rule("QName"), Rule("ImportList")]),
+                ]),
+            ]),
+        ),
+        rule(
+            "RecordDecl",
+            seq([
+                many(Rule("Annotation")),
+                opt(Rule("Visibility")),
+                t("record"),
+                Token("IDENT"),
+                opt(Rule("TypeParams")),
+                opt(seq([t("extends"), Rule("QName")])),
+                opt(Rule("WhereClause")),
+                t("{"),
+                many(Rule("RecordMember")),
+                t("}"),
+            ]),
+        ),
+        rule("Visibility", Choice(vec![t("pub"), t("internal")])),
+        rule(
+            "TypeParams",
+            seq([
+                t("<"),
+                Rule("TypeParam"),
+                many(seq([t(","), Rule("TypeParam")])),
+                t(">"),
+            ]),
+        ),
+        rule(
+            "TypeParam",
+            seq([
+                Token("IDENT"),
+                opt(seq([
+                    t(":"),
+                    Rule("Type"),
+                    many(seq([t("+"), Rule("Type")])),
+                ])),
+            ]),
+        ),
+        rule(
+            "WhereClause",
+            seq([
+                t("where"),
+                Rule("Constraint"),
+                many(seq([t(","), Rule("Constraint")])),
+            ]),
+        ),
+        rule(
+            "RetryClause",
+            seq([
+                t("retry"),
+                t("("),
+                Rule("RetryArg"),
+                opt(seq([t(","), Rule("RetryArg")])),
+                t(")"),
+            ]),
+        ),
+        rule(
+            "RetryArg",
+            Choice(vec![
+                seq([t("max"), t(":"), Token("INT")]),
+                seq([
+                    t("backoff"),
+                    t(":"),
+                    Choice(vec![t("fixed"), t("linear"), t("exponential")]),
+                    t("("),
+                    Token("DURATION"),
+                    t(")"),
+                ]),
+            ]),
+        ),
+        rule(
+            "Constraint",
+            seq([
+                Token("IDENT"),
+                t(":"),
+                Rule("Type"),
+                many(seq([t("+"), Rule("Type")])),
+            ]),
+        ),
+        rule(
+            "RecordMember",
+            Choice(vec![Rule("FieldDecl"), Rule("MethodDecl")]),
+        ),
+        rule(
+            "FieldDecl",
+            seq([
+                many(Rule("Annotation")),
+                Token("IDENT"),
+                opt(t("?")),
+                t(":"),
+                Rule("Type"),
+                opt(seq([t("="), Rule("Expr")])),
+            ]),
+        ),
+        rule(
+            "MethodDecl",
+            seq([
+                many(Rule("Annotation")),
+                opt(Rule("Visibility")),
+                opt(t("async")),
+                Choice(vec![t("fn"), t("func")]),
+                Rule("Signature"),
+                opt(Rule("WhereClause")),
+                opt(Rule("RetryClause")),
+                opt(seq([t("timeout"), Token("DURATION")])),
+                Rule("Block"),
+            ]),
+        ),
+        rule(
+            "Interface",
+            seq([
+                Choice(vec![t("interface"), t("trait")]),
+                Token("IDENT"),
+                t("{"),
+                many(Rule("InterfaceSig")),
+                t("}"),
+            ]),
+        ),
+        rule(
+            "InterfaceSig",
+            seq([
+                opt(t("async")),
+                Choice(vec![t("task"), t("fn"), t("func")]),
+                Rule("Signature"),
+                opt(t(";")),
+            ]),
+        ),
+        rule(
+            "TaskDecl",
+            seq([
+                many(Rule("Annotation")),
+                opt(Rule("Visibility")),
+                opt(t("async")),
+                t("task"),
+                Rule("Signature"),
+                opt(Rule("WhereClause")),
+                opt(Rule("RetryClause")),
+                opt(seq([t("timeout"), Token("DURATION")])),
+                Rule("Block"),
+            ]),
+        ),
+        rule(
+            "Annotation",
+            seq([
+                t("@"),
+                Token("IDENT"),
+                opt(seq([
+                    t("("),
+                    opt(seq([
+                        Rule("AnnotArg"),
+                        many(seq([t(","), Rule("AnnotArg")])),
+                    ])),
+                    t(")"),
+                ])),
+            ]),
+        ),
+        rule(
+            "AnnotArg",
+            seq([opt(seq([Token("IDENT"), t(":")])), Rule("Expr")]),
+        ),
+        rule(
+            "Signature",
+            seq([
+                Token("IDENT"),
+                opt(Rule("TypeParams")),
+                t("("),
+                opt(Rule("ParamList")),
+                t(")"),
+                opt(seq([t("->"), Rule("Type")])),
+            ]),
+        ),
+        rule(
+            "ParamList",
+            seq([Rule("Param"), many(seq([t(","), Rule("Param")]))]),
+        ),
+        rule(
+            "Param",
+            seq([
+                Token("IDENT"),
+                t(":"),
+                Rule("Type"),
+                opt(seq([t("="), Rule("Expr")])),
+            ]),
+        ),
+        rule(
+            "WorkflowDecl",
+            seq([
+                many(Rule("Annotation")),
+                opt(Rule("Visibility")),
+                t("workflow"),
+                Token("IDENT"),
+                Rule("WorkflowBody"),
+            ]),
+        ),
+        rule(
+            "WorkflowBody",
+            seq([
+                t("{"),
+                many(Choice(vec![
+                    Rule("StepDecl"),
+                    Rule("OnError"),
+                    seq([many(Rule("Annotation")), Rule("Statement")]),
+                ])),
+                t("}"),
+            ]),
+        ),
+        rule(
+            "StepDecl",
+            seq([
+                t("step"),
+                Token("IDENT"),
+                opt(seq([
+                    t("("),
+                    opt(seq([Token("IDENT"), many(seq([t(","), Token("IDENT")]))])),
+                    t(")"),
+                ])),
+                opt(Rule("RetryClause")),
+                opt(seq([t("timeout"), Token("DURATION")])),
+                Rule("StepBody"),
+            ]),
+        ),
+        rule(
+            "StepBody",
+            seq([
+                t("{"),
+                many(Choice(vec![
+                    Rule("OnError"),
+                    seq([many(Rule("Annotation")), Rule("Statement")]),
+                ])),
+                t("}"),
+            ]),
+        ),
+        rule(
+            "OnError",
+            seq([
+                t("on_error"),
+                opt(seq([t("("), Token("IDENT"), t(")")])),
+                Rule("Block"),
+            ]),
+        ),
+        rule(
+            "TestDecl",
+            seq([
+                t("test"),
+                Choice(vec![Token("STRING"), Token("IDENT")]),
+                Rule("Block"),
+            ]),
+        ),
+        rule(
+            "HookDecl",
+            seq([
+                t("hook"),
+                Token("IDENT"),
+                Choice(vec![t("before"), t("after"), t("around")]),
+                t("task"),
+                Token("PATTERN"),
+                Rule("Block"),
+            ]),
+        ),
+        rule(
+            "EventHandler",
+            seq([
+                t("on"),
+                t("event"),
+                Token("STRING"),
+                opt(seq([t("("), Token("IDENT"), t(")")])),
+                Rule("Block"),
+            ]),
+        ),
+        rule(
+            "ConfigBlock",
+            seq([
+                t("config"),
+                t("{"),
+                many(seq([Token("IDENT"), t(":"), Rule("Expr"), opt(t(","))])),
+                t("}"),
+            ]),
+        ),
+        rule(
+            "SecretDecl",
+            seq([
+                t("secret"),
+                Token("IDENT"),
+                opt(seq([t("from"), Rule("Expr")])),
+                opt(t(";")),
+            ]),
+        ),
+        rule(
+            "PromptDecl",
+            seq([
+                t("prompt"),
+                Token("IDENT"),
+                t("{"),
+                many(Rule("PromptField")),
+                t("}"),
+            ]),
+        ),
+        rule(
+            "PromptField",
+            seq([Token("IDENT"), t(":"), Token("STRING"), opt(t(","))]),
+        ),
+        rule(
+            "TriggerDecl",
+            seq([
+                t("trigger"),
+                Token("IDENT"),
+                t("{"),
+                many(Choice(vec![
+                    seq([t("cron"), t(":"), Token("STRING"), opt(t(","))]),
+                    seq([t("run"), t(":"), Rule("QName"), opt(t(","))]),
+                ])),
+                t("}"),
+            ]),
+        ),
+        rule(
+            "ToolDecl",
+            seq([
+                t("tool"),
+                Rule("QName"),
+                t("("),
+                opt(Rule("ParamList")),
+                t(")"),
+                opt(seq([t("->"), Rule("Type")])),
+            ]),
+        ),
+        rule("Type", seq([Rule("TypeAtom"), opt(t("?"))])),
+        rule(
+            "TypeAtom",
+            Choice(vec![
+                Rule("StructType"),
+                Rule("MapType"),
+                seq([t("("), Rule("TypeList"), t(")")]),
+                seq([
+                    Rule("QName"),
+                    opt(Choice(vec![
+                        seq([t("<"), Rule("TypeList"), t(">")]),
+                        seq([t("["), Rule("TypeList"), t("]")]),
+                    ])),
+                ]),
+            ]),
+        ),
+        rule(
+            "MapType",
+            seq([
+                t("Map"),
+                t("["),
+                Rule("Type"),
+                Choice(vec![t(","), t("->")]),
+                Rule("Type"),
+                t("]"),
+            ]),
+        ),
+        rule(
+            "TypeList",
+            seq([Rule("Type"), many(seq([t(","), Rule("Type")]))]),
+        ),
+        rule(
+            "StructType",
+            seq([
+                t("{"),
+                opt(seq([
+                    Rule("StructField"),
+                    many(seq([t(","), Rule("StructField")])),
+                ])),
+                t("}"),
+            ]),
+        ),
+        rule(
+            "StructField",
+            seq([Token("IDENT"), opt(t("?")), t(":"), Rule("Type")]),
+        ),
+        rule(
+            "Block",
+            seq([
+                t("{"),
+                many(seq([many(Rule("Annotation")), Rule("Statement")])),
+                t("}"),
+            ]),
+        ),
+        rule(
+            "Statement",
+            Choice(vec![
+                Rule("LetStmt"),
+                Rule("ReturnStmt"),
+                Rule("ThrowStmt"),
+                Rule("AssertStmt"),
+                Rule("ForStmt"),
+                Rule("WhileStmt"),
+                Rule("TryStmt"),
+                Rule("GuardStmt"),
+                Rule("ParallelStmt"),
+                Rule("BranchStmt"),
+                Rule("BlockStmt"),
+                Rule("MethodDecl"),
+                Rule("Expr"),
+            ]),
+        ),
+        rule(
+            "LetStmt",
+            seq([
+                t("let"),
+                Token("IDENT"),
+                opt(seq([t(":"), Rule("Type")])),
+                opt(seq([t("="), Rule("Expr")])),
+            ]),
+        ),
+        rule("ReturnStmt", seq([t("return"), opt(Rule("Expr"))])),
+        rule("ThrowStmt", seq([t("throw"), Rule("Expr")])),
+        rule("AssertStmt", seq([t("assert"), Rule("Expr")])),
+        rule("BlockStmt", seq([opt(Token("IDENT")), Rule("Block")])),
+        rule("WhileStmt", seq([t("while"), Rule("Expr"), Rule("Block")])),
+        rule("ParallelStmt", seq([t("parallel"), Rule("Block")])),
+        rule(
+            "BranchStmt",
+            seq([
+                t("branch"),
+                t("on"),
+                Rule("Expr"),
+                t("{"),
+                many(seq([Rule("BranchArm"), opt(t(","))])),
+                t("}"),
+            ]),
+        ),
+        rule(
+            "BranchArm",
+            seq([
+                Choice(vec![Rule("Pattern"), t("else")]),
+                t("=>"),
+                Token("IDENT"),
+            ]),
+        ),
+        rule(
+            "GuardStmt",
+            seq([t("guard"), Rule("Expr"), t("else"), Rule("Block")]),
+        ),
+        rule(
+            "TryStmt",
+            seq([
+                t("try"),
+                Rule("Block"),
+                Choice(vec![
+                    seq([
+                        Rule("CatchClause"),
+                        many(Rule("CatchClause")),
+                        opt(Rule("Finally")),
+                    ]),
+                    Rule("Finally"),
+                ]),
+            ]),
+        ),
+        rule(
+            "CatchClause",
+            seq([
+                t("catch"),
+                opt(seq([
+                    t("("),
+                    Token("IDENT"),
+                    opt(seq([t(":"), Rule("Type")])),
+                    t(")"),
+                ])),
+                Rule("Block"),
+            ]),
+        ),
+        rule("Finally", seq([t("finally"), Rule("Block")])),
+        rule(
+            "ForStmt",
+            seq([
+                t("for"),
+                Token("IDENT"),
+                t("in"),
+                Rule("Expr"),
+                opt(seq([
+                    t("parallel"),
+                    opt(seq([t("("), t("max"), t(":"), Rule("Expr"), t(")")])),
+                ])),
+                Rule("Block"),
+            ]),
+        ),
+        rule(
+            "Expr",
+            seq([Rule("Unary"), many(seq([Rule("BinaryOp"), Rule("Unary")]))]),
+        ),
+        rule(
+            "BinaryOp",
+            Choice(parser::BINARY_OPERATORS.iter().map(|op| t(op)).collect()),
+        ),
+        rule(
+            "Unary",
+            seq([
+                many(Choice(vec![Rule("UnaryOp"), t("await")])),
+                Rule("Postfix"),
+            ]),
+        ),
+        rule(
+            "UnaryOp",
+            Choice(parser::UNARY_OPERATORS.iter().map(|op| t(op)).collect()),
+        ),
+        rule(
+            "Postfix",
+            seq([
+                Rule("Primary"),
+                many(Choice(vec![
+                    seq([t("("), opt(Rule("ArgList")), t(")")]),
+                    seq([t("["), Rule("Expr"), t("]")]),
+                    seq([t("."), Token("IDENT")]),
+                    seq([t("?."), Token("IDENT")]),
+                    seq([t("{"), opt(Rule("FieldInits")), t("}")]),
+                ])),
+            ]),
+        ),
+        rule(
+            "ArgList",
+            seq([Rule("Arg"), many(seq([t(","), Rule("Arg")]))]),
+        ),
+        rule(
+            "Arg",
+            Choice(vec![
+                seq([Token("IDENT"), Choice(vec![t(":"), t("=")]), Rule("Expr")]),
+                Rule("Element"),
+            ]),
+        ),
+        rule(
+            "Elements",
+            seq([Rule("Element"), many(seq([t(","), Rule("Element")]))]),
+        ),
+        rule("Element", seq([opt(t("...")), Rule("Expr")])),
+        rule(
+            "FieldInits",
+            seq([Rule("FieldInit"), many(seq([t(","), Rule("FieldInit")]))]),
+        ),
+        rule("FieldInit", seq([Token("IDENT"), t(":"), Rule("Expr")])),
+        rule(
+            "Primary",
+            Choice(vec![
+                Rule("Match"),
+                Rule("Lambda"),
+                seq([t("("), Rule("Expr"), t(")")]),
+                seq([t("["), opt(Rule("Elements")), t("]")]),
+                seq([
+                    t("("),
+                    Rule("Expr"),
+                    t(","),
+                    Rule("Expr"),
+                    many(seq([t(","), Rule("Expr")])),
+                    t(")"),
+                ]),
+                Token("IDENT"),
+                Rule("Literal"),
+            ]),
+        ),
+        rule(
+            "Lambda",
+            seq([
+                Choice(vec![
+                    seq([t("("), opt(Rule("LambdaParams")), t(")")]),
+                    Token("IDENT"),
+                ]),
+                t("=>"),
+                Choice(vec![Rule("Block"), Rule("Expr")]),
+            ]),
+        ),
+        rule(
+            "LambdaParams",
+            seq([
+                Rule("LambdaParam"),
+                many(seq([t(","), Rule("LambdaParam")])),
+            ]),
+        ),
+        rule(
+            "LambdaParam",
+            seq([Token("IDENT"), opt(seq([t(":"), Rule("Type")]))]),
+        ),
+        rule(
+            "Match",
+            seq([
+                t("match"),
+                Rule("Expr"),
+                t("{"),
+                many(Rule("MatchArm")),
+                t("}"),
+            ]),
+        ),
+        rule(
+            "MatchArm",
+            seq([
+                Rule("Pattern"),
+                opt(seq([t("if"), Rule("Expr")])),
+                t("=>"),
+                Choice(vec![Rule("Block"), Rule("Expr")]),
+                opt(t(",")),
+            ]),
+        ),
+        rule(
+            "Pattern",
+            Choice(vec![
+                t("_"),
+                Rule("Literal"),
+                seq([t("("), Rule("PatternList"), t(")")]),
+                seq([
+                    Rule("QName"),
+                    opt(Choice(vec![
+                        seq([t("("), opt(Rule("PatternList")), t(")")]),
+                        seq([
+                            t("{"),
+                            Rule("FieldPat"),
+                            many(seq([t(","), Rule("FieldPat")])),
+                            t("}"),
+                        ]),
+                    ])),
+                ]),
+            ]),
+        ),
+        rule(
+            "PatternList",
+            seq([Rule("Pattern"), many(seq([t(","), Rule("Pattern")]))]),
+        ),
+        rule(
+            "FieldPat",
+            seq([Token("IDENT"), opt(seq([t(":"), Rule("Pattern")]))]),
+        ),
+        rule(
+            "Literal",
+            Choice(vec![
+                Token("STRING"),
+                Token("INT"),
+                Token("FLOAT"),
+                Token("DURATION"),
+                t("true"),
+                t("false"),
+                t("null"),
+            ]),
+        ),
+    ];
+
+    Grammar {
+        rules,
+        keywords: parser::RESERVED_KEYWORDS.to_vec(),
+    }
+}
+
+fn rule(name: &'static str, expr: Expr) -> Rule {
+    Rule { name, expr }
+}
+
+fn t(text: &str) -> Expr {
+    Expr::Terminal(text.to_string())
+}
+
+fn seq<const N: usize>(items: [Expr; N]) -> Expr {
+    Expr::Sequence(items.into())
+}
+
+fn opt(expr: Expr) -> Expr {
+    Expr::Optional(Box::new(expr))
+}
+
+fn many(expr: Expr) -> Expr {
+    Expr::Repeat(Box::new(expr))
+}
+
+fn ebnf(expr: &Expr, nested: bool) -> String {
+    match expr {
+        Expr::Terminal(text) => format!("{text:?}"),
+        Expr::Rule(name) | Expr::Token(name) => name.to_string(),
+        Expr::Sequence(items) => {
+            let body = items
+                .iter()
+                .map(|item| ebnf(item, true))
+                .collect::<Vec<_>>()
+                .join(" ");
+            if nested && items.len() > 1 {
+                format!("( {body} )")
+            } else {
+                body
+            }
+        }
+        Expr::Choice(items) => {
+            let body = items
+                .iter()
+                .map(|item| ebnf(item, false))
+                .collect::<Vec<_>>()
+                .join(" | ");
+            if nested { format!("( {body} )") } else { body }
+        }
+        Expr::Optional(inner) => format!("{}?", ebnf(inner, true)),
+        Expr::Repeat(inner) => format!("{}*", ebnf(inner, true)),
+    }
+}
+
+fn expr_json(expr: &Expr) -> Value {
+    match expr {
+        Expr::Terminal(text) => json!({ "type": "terminal", "text": text }),
+        Expr::Rule(name) => json!({ "type": "rule", "name": name }),
+        Expr::Token(name) => json!({ "type": "token", "name": name }),
+        Expr::Sequence(items) => {
+            json!({ "type": "sequence", "items": items.iter().map(expr_json).collect::<Vec<_>>() })
+        }
+        Expr::Choice(items) => {
+            json!({ "type": "choice", "items": items.iter().map(expr_json).collect::<Vec<_>>() })
+        }
+        Expr::Optional(inner) => json!({ "type": "optional", "item": expr_json(inner) }),
+        Expr::Repeat(inner) => json!({ "type": "repeat", "item": expr_json(inner) }),
+    }
+}
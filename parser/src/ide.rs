@@ -0,0 +1,396 @@
+//! Editor-facing queries over HILO sources and parsed modules.
+
+use crate::{ast, parser};
+
+/// One entry of a document outline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub span: ast::Span,
+    pub children: Vec<DocumentSymbol>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Record,
+    Interface,
+    Field,
+    Method,
+    Task,
+    Parameter,
+    Workflow,
+    /// A `step` declaration or labelled block in a workflow body, such as
+    /// `step fetch { ... }` or `start { ... }`.
+    Step,
+    Test,
+    Hook,
+    /// A `prompt` declaration; its sections are listed as fields.
+    Prompt,
+    Tool,
+    Trigger,
+    /// An `on event "pattern"` handler, named by its pattern.
+    EventHandler,
+    /// A `config` block; its keys are listed as fields.
+    Config,
+    Secret,
+    /// A nested `module name { ... }`, outlined like a file.
+    Module,
+    Extension,
+}
+
+/// Hierarchical outline of `module`: records with their fields and methods,
+/// tasks with their parameters, workflows with their steps, prompts with their
+/// sections, tools with their parameters, tests, and nested modules.
+pub fn document_symbols(module: &ast::Module) -> Vec<DocumentSymbol> {
+    module
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ast::Item::Record(decl) => Some(DocumentSymbol {
+                name: decl.name.clone(),
+                kind: SymbolKind::Record,
+                span: decl.span.clone(),
+                children: decl
+                    .fields
+                    .iter()
+                    .map(|field| leaf(&field.name, SymbolKind::Field, &field.span))
+                    .chain(
+                        decl.methods
+                            .iter()
+                            .map(|method| task_symbol(method, SymbolKind::Method)),
+                    )
+                    .collect(),
+            }),
+            ast::Item::Interface(decl) => Some(DocumentSymbol {
+                name: decl.name.clone(),
+                kind: SymbolKind::Interface,
+                span: decl.span.clone(),
+                children: decl
+                    .methods
+                    .iter()
+                    .map(|method| DocumentSymbol {
+                        name: method.name.clone(),
+                        kind: SymbolKind::Method,
+                        span: method.span.clone(),
+                        children: params(&method.params),
+                    })
+                    .collect(),
+            }),
+            ast::Item::Task(decl) => Some(task_symbol(decl, SymbolKind::Task)),
+            ast::Item::Workflow(decl) => Some(DocumentSymbol {
+                name: decl.name.clone(),
+                kind: SymbolKind::Workflow,
+                span: decl.span.clone(),
+                children: workflow_steps(&decl.body)
+                    .into_iter()
+                    .chain(decl.steps.iter().map(|step| DocumentSymbol {
+                        name: step.name.clone(),
+                        kind: SymbolKind::Step,
+                        span: step.span.clone(),
+                        children: workflow_steps(&step.body),
+                    }))
+                    .collect(),
+            }),
+            ast::Item::Test(decl) => Some(DocumentSymbol {
+                name: decl.name.clone(),
+                kind: SymbolKind::Test,
+                span: decl.span.clone(),
+                children: Vec::new(),
+            }),
+            ast::Item::Hook(decl) => Some(DocumentSymbol {
+                name: decl.name.clone(),
+                kind: SymbolKind::Hook,
+                span: decl.span.clone(),
+                children: Vec::new(),
+            }),
+            ast::Item::Prompt(decl) => Some(DocumentSymbol {
+                name: decl.name.clone(),
+                kind: SymbolKind::Prompt,
+                span: decl.span.clone(),
+                children: decl
+                    .sections
+                    .iter()
+                    .map(|section| leaf(&section.name, SymbolKind::Field, &section.span))
+                    .collect(),
+            }),
+            ast::Item::Tool(decl) => Some(DocumentSymbol {
+                name: decl.name.join("."),
+                kind: SymbolKind::Tool,
+                span: decl.span.clone(),
+                children: params(&decl.params),
+            }),
+            ast::Item::Trigger(decl) => Some(DocumentSymbol {
+                name: decl.name.clone(),
+                kind: SymbolKind::Trigger,
+                span: decl.span.clone(),
+                children: Vec::new(),
+            }),
+            ast::Item::EventHandler(decl) => Some(DocumentSymbol {
+                name: decl.pattern.clone(),
+                kind: SymbolKind::EventHandler,
+                span: decl.span.clone(),
+                children: Vec::new(),
+            }),
+            ast::Item::Config(decl) => Some(DocumentSymbol {
+                name: "config".to_string(),
+                kind: SymbolKind::Config,
+                span: decl.span.clone(),
+                children: decl
+                    .entries
+                    .iter()
+                    .map(|entry| leaf(&entry.key, SymbolKind::Field, &entry.span))
+                    .collect(),
+            }),
+            ast::Item::Secret(decl) => Some(leaf(&decl.name, SymbolKind::Secret, &decl.span)),
+            ast::Item::Module(nested) => Some(DocumentSymbol {
+                name: nested.name.as_deref().unwrap_or_default().join("."),
+                kind: SymbolKind::Module,
+                span: nested.span.clone(),
+                children: document_symbols(nested),
+            }),
+            ast::Item::Extension(item) => Some(DocumentSymbol {
+                name: item.keyword.clone(),
+                kind: SymbolKind::Extension,
+                span: item.span.clone(),
+                children: Vec::new(),
+            }),
+            ast::Item::Export(_) | ast::Item::ReExport(_) | ast::Item::Other(_) => None,
+        })
+        .collect()
+}
+
+fn task_symbol(decl: &ast::TaskDecl, kind: SymbolKind) -> DocumentSymbol {
+    DocumentSymbol {
+        name: decl.name.clone(),
+        kind,
+        span: decl.span.clone(),
+        children: params(&decl.params),
+    }
+}
+
+fn params(params: &[ast::Param]) -> Vec<DocumentSymbol> {
+    params
+        .iter()
+        .map(|param| leaf(&param.name, SymbolKind::Parameter, &param.span))
+        .collect()
+}
+
+/// Labelled blocks of a workflow body, descending into nested steps.
+fn workflow_steps(body: &ast::Block) -> Vec<DocumentSymbol> {
+    let parsed;
+    let statements = if body.statements.is_empty() && !body.raw.is_empty() {
+        parsed = body.parse();
+        &parsed
+    } else {
+        &body.statements
+    };
+    statements
+        .iter()
+        .map(|statement| match statement {
+            ast::Statement::Annotated { statement, .. } => statement.as_ref(),
+            statement => statement,
+        })
+        .filter_map(|statement| match statement {
+            ast::Statement::Block {
+                label: Some(label),
+                body,
+            } => Some(DocumentSymbol {
+                name: label.clone(),
+                kind: SymbolKind::Step,
+                span: body.span.clone(),
+                children: workflow_steps(body),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+fn leaf(name: &str, kind: SymbolKind, span: &ast::Span) -> DocumentSymbol {
+    DocumentSymbol {
+        name: name.to_string(),
+        kind,
+        span: span.clone(),
+        children: Vec::new(),
+    }
+}
+
+/// A region an editor can collapse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldingRange {
+    pub span: ast::Span,
+    pub kind: FoldingKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FoldingKind {
+    /// Bracketed content spanning several lines: item bodies, blocks, literals.
+    Region,
+    /// A multi-line string literal.
+    Literal,
+    /// A block comment or a run of line comments on consecutive lines.
+    Comment,
+}
+
+/// Foldable regions of `source`, ordered by start offset.
+///
+/// Computed from bracket, string, and comment boundaries alone, so it works on
+/// sources that do not parse.
+pub fn folding_ranges(source: &str) -> Vec<FoldingRange> {
+    let scan = scan(source);
+    let mut ranges: Vec<FoldingRange> = scan
+        .brackets
+        .iter()
+        .map(|span| (span, FoldingKind::Region))
+        .chain(scan.strings.iter().map(|span| (span, FoldingKind::Literal)))
+        .filter(|(span, _)| spans_lines(source, span))
+        .map(|(span, kind)| FoldingRange {
+            span: span.clone(),
+            kind,
+        })
+        .collect();
+
+    let mut run: Option<ast::Span> = None;
+    for comment in &scan.comments {
+        let is_line = source[comment.clone()].starts_with("//");
+        match &mut run {
+            Some(current)
+                if is_line
+                    && source[current.end..comment.start].trim().is_empty()
+                    && source[current.end..comment.start].matches('\n').count() <= 1 =>
+            {
+                current.end = comment.end;
+            }
+            _ => {
+                push_comment_fold(source, &mut ranges, run.take());
+                if is_line {
+                    run = Some(comment.clone());
+                } else {
+                    push_comment_fold(source, &mut ranges, Some(comment.clone()));
+                }
+            }
+        }
+    }
+    push_comment_fold(source, &mut ranges, run);
+
+    ranges.sort_by_key(|range| (range.span.start, std::cmp::Reverse(range.span.end)));
+    ranges
+}
+
+/// Nested ranges around `offset` for expand-selection, innermost first: the
+/// identifier, string literal, line, and bracketed regions (contents, then
+/// including the brackets) containing it, ending with the whole source.
+pub fn selection_ranges(source: &str, offset: usize) -> Vec<ast::Span> {
+    let offset = offset.min(source.len());
+    let scan = scan(source);
+    let mut candidates = Vec::new();
+
+    let is_word = |ch: char| ch == '_' || ch.is_alphanumeric();
+    let word_start = source[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, ch)| is_word(ch))
+        .last()
+        .map_or(offset, |(idx, _)| idx);
+    let word_end = source[offset..]
+        .char_indices()
+        .find(|&(_, ch)| !is_word(ch))
+        .map_or(source.len(), |(idx, _)| offset + idx);
+    if word_start < word_end {
+        candidates.push(word_start..word_end);
+    }
+
+    for span in &scan.strings {
+        if span.start <= offset && offset < span.end {
+            candidates.push(span.start + 1..span.end.saturating_sub(1).max(span.start + 1));
+            candidates.push(span.clone());
+        }
+    }
+
+    let line_start = source[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+    let line_end = source[offset..]
+        .find('\n')
+        .map_or(source.len(), |idx| offset + idx);
+    candidates.push(trimmed(source, line_start..line_end));
+
+    for span in &scan.brackets {
+        if span.start < offset && offset < span.end {
+            candidates.push(trimmed(source, span.start + 1..span.end - 1));
+            candidates.push(span.clone());
+        }
+    }
+    candidates.push(0..source.len());
+
+    candidates.sort_by_key(|span| span.len());
+    let mut ranges: Vec<ast::Span> = Vec::new();
+    for span in candidates {
+        let nests = ranges
+            .last()
+            .is_none_or(|last| span.start <= last.start && last.end <= span.end && span != *last);
+        if nests && span.start <= offset && offset <= span.end {
+            ranges.push(span);
+        }
+    }
+    ranges
+}
+
+/// Bracket pairs (including the brackets), string literals, and comments of a source.
+#[derive(Default)]
+struct Scan {
+    brackets: Vec<ast::Span>,
+    strings: Vec<ast::Span>,
+    comments: Vec<ast::Span>,
+}
+
+fn scan(source: &str) -> Scan {
+    let mut scan = Scan::default();
+    let mut open = Vec::new();
+    let mut idx = 0;
+    while idx < source.len() {
+        if let Some(next) = parser::skip_comment_or_string(source, idx) {
+            let span = trimmed_end(source, idx..next);
+            if source[idx..].starts_with('"') {
+                scan.strings.push(span);
+            } else {
+                scan.comments.push(span);
+            }
+            idx = next;
+            continue;
+        }
+        let ch = source[idx..].chars().next().unwrap_or_default();
+        match ch {
+            '(' | '[' | '{' => open.push(idx),
+            ')' | ']' | '}' => {
+                if let Some(start) = open.pop() {
+                    scan.brackets.push(start..idx + 1);
+                }
+            }
+            _ => {}
+        }
+        idx += ch.len_utf8();
+    }
+    scan
+}
+
+fn push_comment_fold(source: &str, ranges: &mut Vec<FoldingRange>, span: Option<ast::Span>) {
+    if let Some(span) = span.filter(|span| spans_lines(source, span)) {
+        ranges.push(FoldingRange {
+            span,
+            kind: FoldingKind::Comment,
+        });
+    }
+}
+
+fn spans_lines(source: &str, span: &ast::Span) -> bool {
+    source[span.clone()].contains('\n')
+}
+
+fn trimmed(source: &str, span: ast::Span) -> ast::Span {
+    let text = &source[span.clone()];
+    let start = span.start + (text.len() - text.trim_start().len());
+    start..start + text.trim().len()
+}
+
+fn trimmed_end(source: &str, span: ast::Span) -> ast::Span {
+    span.start..span.start + source[span].trim_end().len()
+}
@@ -0,0 +1,429 @@
+//! Incremental reparsing for editor integrations.
+//!
+//! Items whose spans lie entirely before or after an edit are reused (the
+//! latter shifted by the edit's length delta); only the declarations touched
+//! by the edit are scanned again. Edits reaching the module header or the gap
+//! before a reused item, and regions that no longer parse cleanly or end inside
+//! a comment or string, fall back to a full parse.
+
+use std::ops::Range;
+
+use crate::{ast, error::HiloParseError, options::ParseOptions, parser};
+
+/// A parsed module together with the source and options that produced it.
+#[derive(Debug, Clone)]
+pub struct ParsedModule {
+    pub source: String,
+    pub module: ast::Module,
+    /// Indices into `module.items` that were re-parsed to produce this module.
+    pub changed: Range<usize>,
+    options: ParseOptions,
+    body_start: usize,
+}
+
+/// Replacement of `range` in the previous source with `text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: ast::Span,
+    pub text: String,
+}
+
+impl ParsedModule {
+    pub fn parse(
+        source: impl Into<String>,
+        options: &ParseOptions,
+    ) -> Result<Self, HiloParseError> {
+        let source = source.into();
+        parser::check_depth(&source, options)?;
+        let (docs, name, imports, body_start) = parser::parse_header(&source)?;
+        let items = parser::parse_items(&source, body_start, options)?;
+        let module = parser::finish_module(&source, docs, name, imports, items, options)?;
+        Ok(Self {
+            changed: 0..module.items.len(),
+            source,
+            module,
+            options: options.clone(),
+            body_start,
+        })
+    }
+
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+}
+
+/// Apply `edit` to `previous.source` and parse the result, reusing untouched items.
+pub fn reparse(previous: &ParsedModule, edit: &TextEdit) -> Result<ParsedModule, HiloParseError> {
+    let range = edit.range.clone();
+    if range.start > range.end
+        || range.end > previous.source.len()
+        || !previous.source.is_char_boundary(range.start)
+        || !previous.source.is_char_boundary(range.end)
+    {
+        return Err(HiloParseError::InvalidEdit(format!(
+            "range {range:?} is not valid for a source of {} bytes",
+            previous.source.len()
+        )));
+    }
+
+    let mut source = previous.source.clone();
+    source.replace_range(range, &edit.text);
+    if range_is_header(previous, edit) {
+        return ParsedModule::parse(source, &previous.options);
+    }
+    parser::check_depth(&source, &previous.options)?;
+
+    let Some((items, changed)) = reparse_items(previous, edit, &source)? else {
+        return ParsedModule::parse(source, &previous.options);
+    };
+    let module = parser::finish_module(
+        &source,
+        previous.module.docs.clone(),
+        previous.module.name.clone(),
+        previous.module.imports.clone(),
+        items,
+        &previous.options,
+    )?;
+    Ok(ParsedModule {
+        source,
+        module,
+        changed,
+        options: previous.options.clone(),
+        body_start: previous.body_start,
+    })
+}
+
+fn range_is_header(previous: &ParsedModule, edit: &TextEdit) -> bool {
+    edit.range.start <= previous.body_start
+}
+
+type ReparsedItems = (Vec<ast::Item>, Range<usize>);
+
+fn reparse_items(
+    previous: &ParsedModule,
+    edit: &TextEdit,
+    source: &str,
+) -> Result<Option<ReparsedItems>, HiloParseError> {
+    let mut spans = Vec::with_capacity(previous.module.items.len());
+    for item in &previous.module.items {
+        match item.span() {
+            Some(span) => spans.push(span.clone()),
+            None => return Ok(None),
+        }
+    }
+
+    let before = spans
+        .iter()
+        .take_while(|span| span.end < edit.range.start)
+        .count();
+    // An edit to an item's doc comments reparses the item itself.
+    let after = spans
+        .iter()
+        .position(|span| parser::rewind_doc_comments(&previous.source, span.start) > edit.range.end)
+        .unwrap_or(spans.len());
+    let delta = edit.text.len() as isize - edit.range.len() as isize;
+
+    // Text edited between the last reparsed item and the next reused one can
+    // change what that item starts with, e.g. a new `///` line above it.
+    if after < spans.len() {
+        let gap_start = match after {
+            0 => previous.body_start,
+            n => spans[n - 1].end,
+        };
+        if edit.range.end >= gap_start {
+            return Ok(None);
+        }
+    }
+
+    let region_start = match before {
+        0 => previous.body_start,
+        n => spans[n - 1].end,
+    };
+    let region_end = match spans.get(after) {
+        Some(span) => span.start.wrapping_add_signed(delta),
+        None => source.len(),
+    };
+    if parser::ends_inside_comment_or_string(source, region_start, region_end) {
+        // Cutting the source at `region_end` would close the comment early.
+        return Ok(None);
+    }
+    let region = parser::parse_items(&source[..region_end], region_start, &previous.options)?;
+    if after < spans.len() && region.iter().any(|item| item.span().is_none()) {
+        // Unbalanced input inside the region may swallow later items.
+        return Ok(None);
+    }
+
+    let changed = before..before + region.len();
+    let mut items = previous.module.items[..before].to_vec();
+    items.extend(region);
+    items.extend(
+        previous.module.items[after..]
+            .iter()
+            .cloned()
+            .map(|mut item| {
+                shift_item(&mut item, delta);
+                item
+            }),
+    );
+    Ok(Some((items, changed)))
+}
+
+fn shift_item(item: &mut ast::Item, delta: isize) {
+    match item {
+        ast::Item::Record(decl) => {
+            shift_annotations(&mut decl.annotations, delta);
+            for field in &mut decl.fields {
+                shift_annotations(&mut field.annotations, delta);
+                shift_span(&mut field.span, delta);
+            }
+            for method in &mut decl.methods {
+                shift_task(method, delta);
+            }
+            shift_span(&mut decl.span, delta);
+        }
+        ast::Item::Interface(decl) => {
+            for method in &mut decl.methods {
+                shift_params(&mut method.params, delta);
+                shift_span(&mut method.span, delta);
+            }
+            shift_span(&mut decl.span, delta);
+        }
+        ast::Item::Task(decl) => shift_task(decl, delta),
+        ast::Item::Workflow(decl) => {
+            shift_annotations(&mut decl.annotations, delta);
+            shift_block(&mut decl.body, delta);
+            for step in &mut decl.steps {
+                shift_retry(&mut step.retry, delta);
+                shift_block(&mut step.body, delta);
+                shift_error_handler(&mut step.on_error, delta);
+                shift_span(&mut step.span, delta);
+            }
+            shift_error_handler(&mut decl.on_error, delta);
+            shift_span(&mut decl.span, delta);
+        }
+        ast::Item::Test(decl) => {
+            shift_block(&mut decl.body, delta);
+            shift_span(&mut decl.span, delta);
+        }
+        ast::Item::Hook(decl) => {
+            shift_block(&mut decl.body, delta);
+            shift_span(&mut decl.span, delta);
+        }
+        ast::Item::Prompt(decl) => {
+            for section in &mut decl.sections {
+                shift_span(&mut section.span, delta);
+            }
+            shift_span(&mut decl.span, delta);
+        }
+        ast::Item::Tool(decl) => {
+            shift_params(&mut decl.params, delta);
+            shift_span(&mut decl.span, delta);
+        }
+        ast::Item::Trigger(decl) => shift_span(&mut decl.span, delta),
+        ast::Item::Secret(decl) => {
+            if let Some(source) = &mut decl.source {
+                shift_expression(source, delta);
+            }
+            shift_span(&mut decl.span, delta);
+        }
+        ast::Item::Config(decl) => {
+            for entry in &mut decl.entries {
+                shift_expression(&mut entry.value, delta);
+                shift_span(&mut entry.span, delta);
+            }
+            shift_span(&mut decl.span, delta);
+        }
+        ast::Item::EventHandler(decl) => {
+            shift_block(&mut decl.body, delta);
+            shift_span(&mut decl.span, delta);
+        }
+        ast::Item::Export(decl) => shift_span(&mut decl.span, delta),
+        ast::Item::ReExport(decl) => shift_span(&mut decl.span, delta),
+        ast::Item::Module(module) => {
+            for item in &mut module.items {
+                shift_item(item, delta);
+            }
+            shift_span(&mut module.span, delta);
+        }
+        ast::Item::Extension(item) => shift_span(&mut item.span, delta),
+        ast::Item::Other(_) => {}
+    }
+}
+
+fn shift_task(decl: &mut ast::TaskDecl, delta: isize) {
+    shift_annotations(&mut decl.annotations, delta);
+    shift_params(&mut decl.params, delta);
+    shift_retry(&mut decl.retry, delta);
+    shift_block(&mut decl.body, delta);
+    shift_span(&mut decl.span, delta);
+}
+
+fn shift_retry(policy: &mut Option<ast::RetryPolicy>, delta: isize) {
+    if let Some(policy) = policy {
+        shift_span(&mut policy.span, delta);
+    }
+}
+
+fn shift_params(params: &mut [ast::Param], delta: isize) {
+    for param in params {
+        if let Some(default) = &mut param.default {
+            shift_expression(default, delta);
+        }
+        shift_span(&mut param.span, delta);
+    }
+}
+
+fn shift_annotations(annotations: &mut [ast::Annotation], delta: isize) {
+    for annotation in annotations {
+        for arg in &mut annotation.args {
+            shift_expression(&mut arg.value, delta);
+        }
+        shift_span(&mut annotation.span, delta);
+    }
+}
+
+fn shift_block(block: &mut ast::Block, delta: isize) {
+    shift_span(&mut block.span, delta);
+    for lifted in &mut block.lifted {
+        shift_span(lifted, delta);
+    }
+    for statement in &mut block.statements {
+        shift_statement(statement, delta);
+    }
+}
+
+fn shift_error_handler(handler: &mut Option<ast::ErrorHandler>, delta: isize) {
+    if let Some(handler) = handler {
+        shift_block(&mut handler.body, delta);
+        shift_span(&mut handler.span, delta);
+    }
+}
+
+fn shift_statement(statement: &mut ast::Statement, delta: isize) {
+    match statement {
+        ast::Statement::Let { value, .. } | ast::Statement::Return { value } => {
+            if let Some(value) = value {
+                shift_expression(value, delta);
+            }
+        }
+        ast::Statement::Block { body, .. } => shift_block(body, delta),
+        ast::Statement::For {
+            iterable: expr,
+            body,
+            ..
+        }
+        | ast::Statement::While {
+            condition: expr,
+            body,
+        }
+        | ast::Statement::Guard {
+            condition: expr,
+            else_body: body,
+        } => {
+            shift_expression(expr, delta);
+            shift_block(body, delta);
+        }
+        ast::Statement::Branch { subject, .. } => shift_expression(subject, delta),
+        ast::Statement::Parallel(branches) => {
+            for branch in branches {
+                shift_block(branch, delta);
+            }
+        }
+        ast::Statement::ParallelFor {
+            iterable,
+            limit,
+            body,
+            ..
+        } => {
+            shift_expression(iterable, delta);
+            if let Some(limit) = limit {
+                shift_expression(limit, delta);
+            }
+            shift_block(body, delta);
+        }
+        ast::Statement::Try {
+            body,
+            catches,
+            finally,
+        } => {
+            shift_block(body, delta);
+            for catch in catches {
+                shift_block(&mut catch.body, delta);
+            }
+            if let Some(finally) = finally {
+                shift_block(finally, delta);
+            }
+        }
+        ast::Statement::Expr(expr) | ast::Statement::Throw(expr) | ast::Statement::Assert(expr) => {
+            shift_expression(expr, delta)
+        }
+        ast::Statement::FnDecl(decl) => shift_task(decl, delta),
+        ast::Statement::Annotated {
+            annotations,
+            statement,
+        } => {
+            shift_annotations(annotations, delta);
+            shift_statement(statement, delta);
+        }
+    }
+}
+
+/// Expressions carry no spans of their own, but match arms can hold blocks.
+fn shift_expression(expr: &mut ast::Expression, delta: isize) {
+    match expr {
+        ast::Expression::Call { target, args } => {
+            shift_expression(target, delta);
+            for arg in args {
+                shift_expression(&mut arg.value, delta);
+            }
+        }
+        ast::Expression::Member { target, .. }
+        | ast::Expression::OptionalChain { target, .. }
+        | ast::Expression::Unary {
+            operand: target, ..
+        }
+        | ast::Expression::Await(target)
+        | ast::Expression::Spread(target) => shift_expression(target, delta),
+        ast::Expression::List(elements) | ast::Expression::Tuple(elements) => {
+            for element in elements {
+                shift_expression(element, delta);
+            }
+        }
+        ast::Expression::Index {
+            target: left,
+            index: right,
+        }
+        | ast::Expression::Binary { left, right, .. } => {
+            shift_expression(left, delta);
+            shift_expression(right, delta);
+        }
+        ast::Expression::StructLiteral { fields, .. } => {
+            for (_, value) in fields {
+                shift_expression(value, delta);
+            }
+        }
+        ast::Expression::Match { subject, arms } => {
+            shift_expression(subject, delta);
+            for arm in arms {
+                if let Some(guard) = &mut arm.guard {
+                    shift_expression(guard, delta);
+                }
+                shift_arm_body(&mut arm.body, delta);
+            }
+        }
+        ast::Expression::Lambda { body, .. } => shift_arm_body(body, delta),
+        ast::Expression::Identifier(_) | ast::Expression::Literal(_) | ast::Expression::Raw(_) => {}
+    }
+}
+
+fn shift_arm_body(body: &mut ast::ArmBody, delta: isize) {
+    match body {
+        ast::ArmBody::Expr(value) => shift_expression(value, delta),
+        ast::ArmBody::Block(body) => shift_block(body, delta),
+    }
+}
+
+fn shift_span(span: &mut ast::Span, delta: isize) {
+    span.start = span.start.wrapping_add_signed(delta);
+    span.end = span.end.wrapping_add_signed(delta);
+}
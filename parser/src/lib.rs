@@ -1,12 +1,79 @@
 pub mod ast;
+mod batch;
+pub mod bundle;
+pub mod cache;
+pub mod codemod;
+pub mod compat;
+pub mod conformance;
 pub mod error;
+pub mod generate;
+pub mod grammar;
+pub mod ide;
+pub mod incremental;
+pub mod lint;
+mod mapped;
+pub mod options;
 mod parser;
+pub mod printer;
+pub mod schema;
+pub mod semantic;
+pub mod stdlib;
+pub mod transform;
 
+use std::path::Path;
+
+pub use cache::ParseCache;
 pub use error::HiloParseError;
+pub use incremental::{ParsedModule, TextEdit, reparse};
+pub use options::{ItemExtensions, LanguageVersion, ParseOptions};
 
 /// Parse a HILO source file into an abstract syntax tree.
 pub fn parse_module(source: &str) -> Result<ast::Module, HiloParseError> {
-    parser::parse_module(source)
+    parser::parse_module(source, &ParseOptions::default())
+}
+
+/// Parse a HILO source file with explicit [`ParseOptions`].
+pub fn parse_module_with(
+    source: &str,
+    options: &ParseOptions,
+) -> Result<ast::Module, HiloParseError> {
+    parser::parse_module(source, options)
+}
+
+/// Parse independent sources on a pool of worker threads.
+///
+/// Results are returned in the same order as `sources`.
+pub fn parse_many<S>(sources: &[S]) -> Vec<Result<ast::Module, HiloParseError>>
+where
+    S: AsRef<str> + Sync,
+{
+    batch::parse_many(sources, &ParseOptions::default())
+}
+
+/// Parse independent sources on a pool of worker threads with explicit [`ParseOptions`].
+pub fn parse_many_with<S>(
+    sources: &[S],
+    options: &ParseOptions,
+) -> Vec<Result<ast::Module, HiloParseError>>
+where
+    S: AsRef<str> + Sync,
+{
+    batch::parse_many(sources, options)
+}
+
+/// Parse a HILO file through a read-only memory map instead of reading it into a `String`.
+///
+/// The file must not be modified while it is being parsed.
+pub fn parse_mmap(path: impl AsRef<Path>) -> Result<ast::Module, HiloParseError> {
+    mapped::parse_mmap(path.as_ref(), &ParseOptions::default())
+}
+
+/// Parse a memory-mapped HILO file with explicit [`ParseOptions`].
+pub fn parse_mmap_with(
+    path: impl AsRef<Path>,
+    options: &ParseOptions,
+) -> Result<ast::Module, HiloParseError> {
+    mapped::parse_mmap(path.as_ref(), options)
 }
 
 #[cfg(test)]
@@ -130,7 +197,7 @@ mod tests {
                 assert_eq!(task.params.len(), 1);
                 assert_eq!(task.params[0].name, "topic");
                 assert!(task.body.raw.contains("Writer.run"));
-                match task.body.statements.get(0) {
+                match task.body.statements.first() {
                     Some(ast::Statement::Let { name, value, .. }) => {
                         assert_eq!(name, "research");
                         let value_expr = value.as_ref().expect("let should have expression");
@@ -147,7 +214,7 @@ mod tests {
                                 }
                                 assert_eq!(args.len(), 1);
                                 assert!(
-                                    matches!(args[0], ast::Expression::Identifier(ref id) if id == "topic")
+                                    matches!(args[0].value, ast::Expression::Identifier(ref id) if id == "topic")
                                 );
                             }
                             other => panic!("expected call expression, got {:?}", other),
@@ -155,13 +222,36 @@ mod tests {
                     }
                     other => panic!("expected let statement, got {:?}", other),
                 }
-                assert!(
-                    task.body
-                        .statements
-                        .iter()
-                        .any(|stmt| matches!(stmt, ast::Statement::Return { .. })),
-                    "expected a return statement in task body"
+                assert_eq!(
+                    task.body.statements.len(),
+                    2,
+                    "the multi-line match should stay a single statement"
                 );
+                match &task.body.statements[1] {
+                    ast::Statement::Expr(ast::Expression::Match { subject, arms }) => {
+                        assert!(
+                            matches!(subject.as_ref(), ast::Expression::Identifier(id) if id == "research")
+                        );
+                        assert_eq!(arms.len(), 2);
+                        assert_eq!(
+                            arms[0].pattern,
+                            ast::Pattern::Variant {
+                                path: vec![String::from("Ok")],
+                                fields: vec![ast::Pattern::Binding(String::from("data"))],
+                            }
+                        );
+                        match &arms[0].body {
+                            ast::ArmBody::Block(body) => assert!(matches!(
+                                body.statements.last(),
+                                Some(ast::Statement::Return {
+                                    value: Some(ast::Expression::StructLiteral { .. })
+                                })
+                            )),
+                            other => panic!("expected block arm, got {:?}", other),
+                        }
+                    }
+                    other => panic!("expected match statement, got {:?}", other),
+                }
             }
             other => panic!("expected task, got {:?}", other),
         }
@@ -170,22 +260,46 @@ mod tests {
             ast::Item::Workflow(flow) => {
                 assert_eq!(flow.name, "Main");
                 assert!(flow.body.raw.contains("start"));
-                assert!(!flow.body.statements.is_empty());
+                match flow.body.statements.as_slice() {
+                    [ast::Statement::Block { label, body }] => {
+                        assert_eq!(label.as_deref(), Some("start"));
+                        assert_eq!(body.statements.len(), 4);
+                        assert!(matches!(
+                            &body.statements[0],
+                            ast::Statement::Let { name, .. } if name == "brief"
+                        ));
+                    }
+                    other => panic!("expected a single start block, got {:?}", other),
+                }
             }
             other => panic!("expected workflow, got {:?}", other),
         }
+    }
 
-        let return_expr = module
-            .items
-            .iter()
-            .find_map(|item| match item {
-                ast::Item::Task(task) => task.body.statements.iter().find_map(|stmt| match stmt {
-                    ast::Statement::Return { value: Some(expr) } => Some(expr.clone()),
-                    _ => None,
-                }),
-                _ => None,
-            })
-            .expect("expected return expression");
+    #[test]
+    fn parses_multiline_struct_literal_return() {
+        let src = r#"
+            task Compose(topic: String) -> Brief {
+              let data = Researcher.run(topic)
+              return Brief {
+                title: "Brief: " + topic,
+                body: rr["revised"],
+                sources: data["sources"]
+              }
+            }
+        "#;
+
+        let module = parse_module(src).expect("parser should succeed");
+        let task = match &module.items[0] {
+            ast::Item::Task(task) => task,
+            other => panic!("expected task, got {:?}", other),
+        };
+        assert_eq!(task.body.statements.len(), 2);
+
+        let return_expr = match &task.body.statements[1] {
+            ast::Statement::Return { value: Some(expr) } => expr.clone(),
+            other => panic!("expected return statement, got {:?}", other),
+        };
 
         match return_expr {
             ast::Expression::StructLiteral { type_name, fields } => {
@@ -201,7 +315,7 @@ mod tests {
                             matches!(target.as_ref(), ast::Expression::Identifier(id) if id == "data")
                         );
                         assert!(
-                            matches!(index.as_ref(), ast::Expression::Literal(lit) if lit == "\"sources\"")
+                            matches!(index.as_ref(), ast::Expression::Literal(ast::Literal::Str(lit)) if lit == "sources")
                         );
                     }
                     other => panic!("expected index expression, got {:?}", other),
@@ -211,6 +325,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn block_statements_respect_nesting_and_comments() {
+        let src = r#"
+            task Demo() {
+              // leading comment
+              let config = Settings {
+                retries: 3,
+                label: "a; b // c"
+              }; log(config) /* trailing */
+              {
+                let inner = 1
+                guarded { value: inner }
+              }
+              return config
+            }
+        "#;
+
+        let module = parse_module(src).expect("parser should succeed");
+        let task = match &module.items[0] {
+            ast::Item::Task(task) => task,
+            other => panic!("expected task, got {:?}", other),
+        };
+        let statements = &task.body.statements;
+        assert_eq!(statements.len(), 4, "got {:?}", statements);
+        match &statements[0] {
+            ast::Statement::Let {
+                name,
+                value: Some(ast::Expression::StructLiteral { fields, .. }),
+                ..
+            } => {
+                assert_eq!(name, "config");
+                assert_eq!(fields.len(), 2);
+            }
+            other => panic!("expected struct literal let, got {:?}", other),
+        }
+        assert!(matches!(
+            &statements[1],
+            ast::Statement::Expr(ast::Expression::Call { .. })
+        ));
+        match &statements[2] {
+            ast::Statement::Block { label: None, body } => {
+                assert_eq!(body.statements.len(), 2);
+                assert!(matches!(
+                    &body.statements[1],
+                    ast::Statement::Expr(ast::Expression::StructLiteral { .. })
+                ));
+            }
+            other => panic!("expected nested block, got {:?}", other),
+        }
+        assert!(matches!(
+            &statements[3],
+            ast::Statement::Return { value: Some(_) }
+        ));
+    }
+
     #[test]
     fn parses_complex_type_shapes() {
         let src = r#"
@@ -229,7 +398,13 @@ mod tests {
         };
 
         assert_eq!(record.name, "Complex");
-        assert_eq!(record.type_params, vec![String::from("T")]);
+        assert_eq!(
+            record.type_params,
+            vec![ast::TypeParam {
+                name: String::from("T"),
+                bounds: Vec::new(),
+            }]
+        );
         assert_eq!(record.fields.len(), 2);
 
         let items_field = &record.fields[0];
@@ -238,11 +413,10 @@ mod tests {
         match &items_field.ty {
             ast::TypeExpr::List(inner) => match inner.as_ref() {
                 ast::TypeExpr::Optional(inner) => match inner.as_ref() {
-                    ast::TypeExpr::Generic { base, arguments } => {
-                        assert_eq!(base, &vec![String::from("Map")]);
-                        assert_eq!(arguments.len(), 2);
+                    ast::TypeExpr::Map { key, .. } => {
+                        assert_eq!(key.as_ref(), &ast::TypeExpr::Simple(vec!["String".into()]));
                     }
-                    other => panic!("expected generic map, got {:?}", other),
+                    other => panic!("expected map, got {:?}", other),
                 },
                 other => panic!("expected optional inner, got {:?}", other),
             },
@@ -277,7 +451,7 @@ mod tests {
             other => panic!("expected task, got {:?}", other),
         };
 
-        match task.body.statements.get(0) {
+        match task.body.statements.first() {
             Some(ast::Statement::Let {
                 value: Some(expr), ..
             }) => match expr {
@@ -292,7 +466,7 @@ mod tests {
                         other => panic!("expected optional chain target, got {:?}", other),
                     }
                     assert!(
-                        matches!(index.as_ref(), ast::Expression::Literal(lit) if lit == "\"items\"")
+                        matches!(index.as_ref(), ast::Expression::Literal(ast::Literal::Str(lit)) if lit == "items")
                     );
                 }
                 other => panic!("expected index expression, got {:?}", other),
@@ -300,4 +474,4076 @@ mod tests {
             other => panic!("expected let statement, got {:?}", other),
         }
     }
+
+    #[test]
+    fn parse_options_control_strictness_trivia_and_depth() {
+        let src = r#"
+            // leading note
+            task Demo() {
+              let x = call("a // not a comment")
+            }
+            agent Mystery { }
+        "#;
+
+        let lenient = parse_module(src).expect("lenient parse should succeed");
+        assert!(matches!(lenient.items.last(), Some(ast::Item::Other(_))));
+        assert!(lenient.comments.is_empty());
+
+        let strict = ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        };
+        let err = parse_module_with(src, &strict).expect_err("strict parse should fail");
+        assert!(err.to_string().contains("agent Mystery"));
+
+        let trivia = ParseOptions {
+            keep_trivia: true,
+            ..ParseOptions::default()
+        };
+        let module = parse_module_with(src, &trivia).expect("parser should succeed");
+        assert_eq!(module.comments.len(), 1);
+        assert_eq!(module.comments[0].kind, ast::CommentKind::Line);
+        assert_eq!(module.comments[0].text, "// leading note");
+        assert_eq!(&src[module.comments[0].span.clone()], "// leading note");
+
+        let shallow = ParseOptions {
+            max_depth: Some(1),
+            ..ParseOptions::default()
+        };
+        match parse_module_with(src, &shallow) {
+            Err(HiloParseError::DepthLimit { depth, limit }) => {
+                assert_eq!(depth, 2);
+                assert_eq!(limit, 1);
+            }
+            other => panic!("expected depth limit error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn registered_extension_keywords_produce_extension_items() {
+        #[derive(Debug, PartialEq)]
+        struct Dataset {
+            name: String,
+            columns: usize,
+        }
+
+        let src = r#"
+            /// Raw click stream.
+            dataset Clicks(source: "s3") {
+              user: String
+              at: Time
+            }
+            task Demo() {
+              return 1
+            }
+        "#;
+
+        let mut options = ParseOptions::default();
+        options
+            .extensions
+            .register("dataset", |keyword, header, body| {
+                assert_eq!(keyword, "dataset");
+                let name = header.split('(').next().unwrap_or_default().trim();
+                Ok(ast::ExtensionPayload::new(Dataset {
+                    name: name.to_string(),
+                    columns: body.lines().filter(|l| l.contains(':')).count(),
+                }))
+            });
+
+        let module = parse_module_with(src, &options).expect("parser should succeed");
+        assert_eq!(module.items.len(), 2);
+        match &module.items[0] {
+            ast::Item::Extension(ext) => {
+                assert_eq!(ext.keyword, "dataset");
+                assert_eq!(ext.header, r#"Clicks(source: "s3")"#);
+                assert_eq!(
+                    ext.payload.downcast_ref::<Dataset>(),
+                    Some(&Dataset {
+                        name: String::from("Clicks"),
+                        columns: 2
+                    })
+                );
+            }
+            other => panic!("expected extension item, got {:?}", other),
+        }
+        assert!(matches!(&module.items[1], ast::Item::Task(task) if task.name == "Demo"));
+
+        let mut failing = ParseOptions::default();
+        failing
+            .extensions
+            .register("dataset", |_, _, _| Err(String::from("missing source")));
+        let err = parse_module_with(src, &failing).expect_err("callback error should surface");
+        assert!(err.to_string().contains("missing source"));
+
+        let plain = parse_module(src).expect("parser should succeed");
+        assert!(matches!(&plain.items[0], ast::Item::Other(_)));
+    }
+
+    #[test]
+    fn reparse_reuses_items_outside_the_edit() {
+        let src = "module demo\n\nrecord A {\n  x: Int\n}\n\ntask B() {\n  return 1\n}\n\nworkflow C {\n  B()\n}\n";
+        let parsed = ParsedModule::parse(src, &ParseOptions::default()).expect("parse");
+        assert_eq!(parsed.changed, 0..3);
+        let task_span = parsed.module.items[1].span().cloned().expect("task span");
+        assert!(src[task_span.clone()].starts_with("task B()"));
+        assert!(src[task_span].ends_with('}'));
+
+        let at = src.find("return 1").unwrap() + "return ".len();
+        let edit = TextEdit {
+            range: at..at + 1,
+            text: String::from("42 + 1"),
+        };
+        let next = reparse(&parsed, &edit).expect("reparse");
+        assert_eq!(next.changed, 1..2);
+        assert_eq!(next.module.items[0], parsed.module.items[0]);
+        match &next.module.items[1] {
+            ast::Item::Task(task) => assert!(task.body.raw.contains("42 + 1")),
+            other => panic!("expected task, got {:?}", other),
+        }
+        let flow_span = next.module.items[2].span().cloned().expect("workflow span");
+        assert!(next.source[flow_span].starts_with("workflow C"));
+        assert_eq!(
+            next.module,
+            parse_module(&next.source).expect("full parse"),
+            "incremental result should match a full parse"
+        );
+
+        let broken = TextEdit {
+            range: at..at,
+            text: String::from("{"),
+        };
+        let fallback = reparse(&parsed, &broken).expect("reparse");
+        assert_eq!(fallback.changed, 0..fallback.module.items.len());
+        assert_eq!(
+            fallback.module,
+            parse_module(&fallback.source).expect("full parse")
+        );
+
+        let header = TextEdit {
+            range: 7..11,
+            text: String::from("other"),
+        };
+        let renamed = reparse(&parsed, &header).expect("reparse");
+        assert_eq!(renamed.module.name, Some(vec![String::from("other")]));
+
+        let invalid = TextEdit {
+            range: 0..src.len() + 1,
+            text: String::new(),
+        };
+        assert!(matches!(
+            reparse(&parsed, &invalid),
+            Err(HiloParseError::InvalidEdit(_))
+        ));
+    }
+
+    #[test]
+    fn reparse_matches_a_full_parse_for_every_small_edit() {
+        let src = "module demo\n\n/// A record.\nrecord A {\n  x: Int\n}\n\n// note\ntask B() {\n  return \"b\"\n}\n\nworkflow C {\n  B()\n}\n";
+        let options = ParseOptions::default();
+        let parsed = ParsedModule::parse(src, &options).expect("parse");
+        let insertions = ["/*", "*/", "///", "/// doc\n", "\"", "{", "}", "x", "\n"];
+        let offsets = (0..=src.len()).filter(|&at| src.is_char_boundary(at));
+        for at in offsets {
+            let deletion = (at < src.len()).then(|| TextEdit {
+                range: at..at + 1,
+                text: String::new(),
+            });
+            let edits = insertions
+                .iter()
+                .map(|text| TextEdit {
+                    range: at..at,
+                    text: text.to_string(),
+                })
+                .chain(deletion);
+            for edit in edits {
+                let incremental = reparse(&parsed, &edit).map(|next| next.module);
+                let mut source = src.to_string();
+                source.replace_range(edit.range.clone(), &edit.text);
+                let full = ParsedModule::parse(source, &options).map(|full| full.module);
+                assert_eq!(incremental.ok(), full.ok(), "edit {edit:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn parse_many_preserves_input_order() {
+        let sources: Vec<String> = (0..64)
+            .map(|i| {
+                if i == 17 {
+                    String::from("module broken\nagent Unknown {}")
+                } else {
+                    format!("module m{i}\ntask T{i}() {{\n  return {i}\n}}\n")
+                }
+            })
+            .collect();
+
+        let strict = ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        };
+        let results = parse_many_with(&sources, &strict);
+        assert_eq!(results.len(), sources.len());
+        for (i, (result, source)) in results.iter().zip(&sources).enumerate() {
+            if i == 17 {
+                assert!(result.is_err());
+                continue;
+            }
+            let module = result.as_ref().expect("parser should succeed");
+            assert_eq!(module.name, Some(vec![format!("m{i}")]));
+            assert_eq!(module, &parse_module(source).unwrap());
+        }
+        assert!(parse_many(&sources).iter().all(Result::is_ok));
+    }
+
+    #[test]
+    fn lazy_bodies_defer_statement_parsing() {
+        let project = include_str!("../../project/src/main.hilo");
+        let steps = "workflow Publish {\n  let topic = \"hilo\"\n  step draft {\n    let brief = Write(topic)\n    on_error { log(\"draft\") }\n  }\n  on_error(err) {\n    notify(err)\n  }\n  step send(draft) retry(max: 2) {}\n  done(topic)\n}\n";
+        let options = ParseOptions {
+            lazy_bodies: true,
+            ..ParseOptions::default()
+        };
+        for src in [project, steps] {
+            let lazy = parse_module_with(src, &options).expect("parser should succeed");
+            let eager = parse_module(src).expect("parser should succeed");
+            assert_eq!(lazy.items.len(), eager.items.len());
+
+            for (lazy_item, eager_item) in lazy.items.iter().zip(&eager.items) {
+                match (lazy_item, eager_item) {
+                    (ast::Item::Task(lazy), ast::Item::Task(eager)) => {
+                        assert_eq!(lazy.params, eager.params);
+                        assert_eq!(lazy.return_type, eager.return_type);
+                        assert!(lazy.body.statements.is_empty());
+                        assert_eq!(lazy.body.raw, eager.body.raw);
+                        assert_eq!(lazy.body.parse(), eager.body.statements);
+                    }
+                    (ast::Item::Workflow(lazy), ast::Item::Workflow(eager)) => {
+                        assert!(lazy.body.statements.is_empty());
+                        assert_eq!(lazy.body.parse(), eager.body.statements);
+                        assert_eq!(lazy.steps.len(), eager.steps.len());
+                        for (lazy, eager) in lazy.steps.iter().zip(&eager.steps) {
+                            assert_eq!(lazy.body.parse(), eager.body.statements);
+                        }
+                        assert_eq!(lazy.on_error.is_some(), eager.on_error.is_some());
+                    }
+                    (lazy, eager) => assert_eq!(lazy, eager),
+                }
+            }
+        }
+
+        let module = parse_module(steps).unwrap();
+        let ast::Item::Workflow(workflow) = &module.items[0] else {
+            panic!("expected a workflow");
+        };
+        assert_eq!(workflow.steps.len(), 2);
+        assert_eq!(
+            workflow.body.statements.len(),
+            2,
+            "{:?}",
+            workflow.body.statements
+        );
+        assert!(workflow.on_error.is_some());
+        assert!(workflow.steps[0].on_error.is_some());
+        assert_eq!(workflow.steps[0].body.statements.len(), 1);
+    }
+
+    #[test]
+    fn parse_mmap_matches_in_memory_parse() {
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../project/src/main.hilo");
+        let mapped = parse_mmap(&path).expect("mapped parse should succeed");
+        let src = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(mapped, parse_module(&src).unwrap());
+
+        let empty = std::env::temp_dir().join(format!("hilo-empty-{}.hilo", std::process::id()));
+        std::fs::write(&empty, "").unwrap();
+        let module = parse_mmap(&empty).expect("empty file should parse");
+        std::fs::remove_file(&empty).unwrap();
+        assert!(module.items.is_empty());
+
+        assert!(matches!(
+            parse_mmap(path.with_extension("missing")),
+            Err(HiloParseError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn parse_cache_shares_modules_for_identical_content() {
+        let cache = ParseCache::default();
+        let src = "module cached\ntask A() {\n  return 1\n}\n";
+        let copy = String::from(src);
+
+        let first = cache.get_or_parse(src).expect("parser should succeed");
+        let second = cache.get_or_parse(&copy).expect("parser should succeed");
+        assert!(std::sync::Arc::ptr_eq(&first, &second));
+        assert_eq!(cache.len(), 1);
+
+        let edited = cache
+            .get_or_parse(&src.replace("1", "2"))
+            .expect("parser should succeed");
+        assert!(!std::sync::Arc::ptr_eq(&first, &edited));
+        assert_eq!(cache.len(), 2);
+
+        let strict = ParseCache::new(ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        });
+        assert!(strict.get_or_parse("agent X {}").is_err());
+        assert!(strict.is_empty());
+
+        assert!(cache.remove(src));
+        assert!(!cache.remove(src));
+        assert_eq!(cache.len(), 1);
+        cache.clear();
+        assert!(cache.is_empty());
+
+        let bounded = ParseCache::with_capacity(ParseOptions::default(), 2);
+        let revisions: Vec<String> = (0..3)
+            .map(|n| format!("module cached\ntask A() {{\n  return {n}\n}}\n"))
+            .collect();
+        let oldest = bounded
+            .get_or_parse(&revisions[0])
+            .expect("parser should succeed");
+        for revision in &revisions[1..] {
+            bounded
+                .get_or_parse(revision)
+                .expect("parser should succeed");
+        }
+        assert_eq!(bounded.len(), 2);
+        let reparsed = bounded
+            .get_or_parse(&revisions[0])
+            .expect("parser should succeed");
+        assert!(!std::sync::Arc::ptr_eq(&oldest, &reparsed));
+        assert_eq!(bounded.len(), 2);
+    }
+
+    #[test]
+    fn leading_arithmetic_operators_continue_statements() {
+        let src = "task Total(a: Int, b: Int) -> Int {\n  let sum = a\n    + b\n    - 1\n  let scaled = sum\n    * 2\n    / 3\n    % 4\n  -sum\n  // done\n  return scaled\n}\n";
+        let module = parse_module(src).expect("parser should succeed");
+        let ast::Item::Task(task) = &module.items[0] else {
+            panic!("expected a task");
+        };
+        let statements = &task.body.statements;
+        assert_eq!(statements.len(), 4, "{statements:?}");
+        let ast::Statement::Let {
+            value: Some(sum), ..
+        } = &statements[0]
+        else {
+            panic!("expected let, got {:?}", statements[0]);
+        };
+        assert_eq!(printer::print_expression(sum), "a + b - 1");
+        assert!(matches!(
+            &statements[1],
+            ast::Statement::Let {
+                value: Some(ast::Expression::Binary { .. }),
+                ..
+            }
+        ));
+        assert!(matches!(
+            &statements[2],
+            ast::Statement::Expr(ast::Expression::Unary { .. })
+        ));
+        assert!(matches!(&statements[3], ast::Statement::Return { .. }));
+    }
+
+    #[test]
+    fn statements_continue_across_operator_lines() {
+        let src = r#"
+            task Summarize(topic: String) -> String {
+              let urls = web.search(topic,
+                                    max_results=5)
+              let notes = urls
+                |> map(fn(u) -> web.open(u))
+                |> filter(fn(p) -> p.ok)
+              let title = "Brief: " +
+                topic
+              let ready = notes != "" and
+                title != ""
+              let pages = notes
+                .first()
+              return Brief {
+                title: title,
+                body: notes
+              }
+            }
+        "#;
+
+        let module = parse_module(src).expect("parser should succeed");
+        let task = match &module.items[0] {
+            ast::Item::Task(task) => task,
+            other => panic!("expected task, got {:?}", other),
+        };
+        let names: Vec<_> = task
+            .body
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                ast::Statement::Let { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, ["urls", "notes", "title", "ready", "pages"]);
+        assert_eq!(task.body.statements.len(), 6);
+
+        match &task.body.statements[0] {
+            ast::Statement::Let {
+                value: Some(ast::Expression::Call { args, .. }),
+                ..
+            } => assert_eq!(args.len(), 2),
+            other => panic!("expected call spanning two lines, got {:?}", other),
+        }
+        match &task.body.statements[2] {
+            ast::Statement::Let {
+                value: Some(ast::Expression::Binary { op, right, .. }),
+                ..
+            } => {
+                assert_eq!(op, "+");
+                assert!(matches!(right.as_ref(), ast::Expression::Identifier(id) if id == "topic"));
+            }
+            other => panic!("expected binary expression, got {:?}", other),
+        }
+        assert!(matches!(
+            &task.body.statements[5],
+            ast::Statement::Return {
+                value: Some(ast::Expression::StructLiteral { .. })
+            }
+        ));
+
+        let researcher = include_str!("../../project/src/agents/Researcher.hilo");
+        let body_start = researcher.find("let urls").unwrap();
+        let body_end = researcher.rfind("return Ok").unwrap();
+        let statements = ast::Block {
+            raw: researcher[body_start..body_end].to_string(),
+            statements: Vec::new(),
+            span: body_start..body_end,
+            lifted: Vec::new(),
+        }
+        .parse();
+        assert_eq!(statements.len(), 3, "got {:?}", statements);
+    }
+
+    #[test]
+    fn expression_scanning_skips_strings_and_comments() {
+        let src = r#"
+            task Links(items: List[String]) -> String {
+              let joined = join(", ", items)
+              let url = base + "?a=b&c=d"
+              let label = Label { text: "x: {y}", note: "(a, b)" }
+              let total = first + // running sum
+                second
+              let name = "Zoë" + suffix
+            }
+        "#;
+
+        let module = parse_module(src).expect("parser should succeed");
+        let task = match &module.items[0] {
+            ast::Item::Task(task) => task,
+            other => panic!("expected task, got {:?}", other),
+        };
+        let values: Vec<&ast::Expression> = task
+            .body
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                ast::Statement::Let {
+                    value: Some(value), ..
+                } => Some(value),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(values.len(), 5);
+
+        match values[0] {
+            ast::Expression::Call { args, .. } => {
+                assert_eq!(args.len(), 2);
+                assert!(
+                    matches!(&args[0].value, ast::Expression::Literal(ast::Literal::Str(lit)) if lit == ", ")
+                );
+            }
+            other => panic!("expected call, got {:?}", other),
+        }
+        match values[1] {
+            ast::Expression::Binary { left, op, right } => {
+                assert_eq!(op, "+");
+                assert!(matches!(left.as_ref(), ast::Expression::Identifier(id) if id == "base"));
+                assert!(
+                    matches!(right.as_ref(), ast::Expression::Literal(ast::Literal::Str(lit)) if lit == "?a=b&c=d")
+                );
+            }
+            other => panic!("expected binary expression, got {:?}", other),
+        }
+        match values[2] {
+            ast::Expression::StructLiteral { fields, .. } => {
+                assert_eq!(fields.len(), 2);
+                assert_eq!(fields[0].0, "text");
+                assert!(
+                    matches!(&fields[0].1, ast::Expression::Literal(ast::Literal::Str(lit)) if lit == "x: {y}")
+                );
+                assert!(
+                    matches!(&fields[1].1, ast::Expression::Literal(ast::Literal::Str(lit)) if lit == "(a, b)")
+                );
+            }
+            other => panic!("expected struct literal, got {:?}", other),
+        }
+        match values[3] {
+            ast::Expression::Binary { left, right, .. } => {
+                assert!(matches!(left.as_ref(), ast::Expression::Identifier(id) if id == "first"));
+                assert!(
+                    matches!(right.as_ref(), ast::Expression::Identifier(id) if id == "second")
+                );
+            }
+            other => panic!("expected binary expression, got {:?}", other),
+        }
+        assert!(matches!(
+            values[4],
+            ast::Expression::Binary { op, .. } if op == "+"
+        ));
+    }
+
+    #[test]
+    fn parses_record_methods() {
+        let src = r#"
+            record Brief {
+              title: String
+              tags: List[String]
+              fn summary() -> String { return title }
+              fn tagged(tag: String) -> Bool {
+                return tags.contains(tag)
+              }
+              note?: String
+            }
+            record Inline { title: String  fn summary() -> String { return title } }
+        "#;
+
+        let module = parse_module(src).expect("parser should succeed");
+        let brief = match &module.items[0] {
+            ast::Item::Record(record) => record,
+            other => panic!("expected record, got {:?}", other),
+        };
+        let field_names: Vec<_> = brief.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(field_names, ["title", "tags", "note"]);
+        let method_names: Vec<_> = brief.methods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(method_names, ["summary", "tagged"]);
+        assert_eq!(brief.methods[1].params[0].name, "tag");
+        assert!(matches!(
+            brief.methods[1].body.statements.as_slice(),
+            [ast::Statement::Return { .. }]
+        ));
+        assert!(src[brief.methods[0].span.clone()].starts_with("fn summary"));
+
+        let inline = match &module.items[1] {
+            ast::Item::Record(record) => record,
+            other => panic!("expected record, got {:?}", other),
+        };
+        assert_eq!(inline.fields.len(), 1);
+        assert_eq!(inline.methods.len(), 1);
+    }
+
+    #[test]
+    fn document_symbols_outline_items_with_spans() {
+        use ide::SymbolKind;
+
+        let src = r#"
+            record Brief {
+              title: String
+              fn summary() -> String { return title }
+            }
+            task Research(topic: String, depth: Int = 2) -> Brief {
+              return Brief { title: topic }
+            }
+            workflow Daily {
+              start {
+                let brief = Research("rust")
+                publish { send(brief) }
+              }
+            }
+            test "daily runs" { Daily() }
+        "#;
+
+        let module = parse_module(src).expect("parser should succeed");
+        let symbols = ide::document_symbols(&module);
+        let outline: Vec<_> = symbols.iter().map(|s| (s.kind, s.name.as_str())).collect();
+        assert_eq!(
+            outline,
+            [
+                (SymbolKind::Record, "Brief"),
+                (SymbolKind::Task, "Research"),
+                (SymbolKind::Workflow, "Daily"),
+                (SymbolKind::Test, "daily runs"),
+            ]
+        );
+
+        let record = &symbols[0].children;
+        assert_eq!(record[0].kind, SymbolKind::Field);
+        assert_eq!(&src[record[0].span.clone()], "title: String");
+        assert_eq!(record[1].kind, SymbolKind::Method);
+        assert_eq!(record[1].name, "summary");
+
+        let params = &symbols[1].children;
+        assert_eq!(&src[params[1].span.clone()], "depth: Int = 2");
+
+        let steps = &symbols[2].children;
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].kind, SymbolKind::Step);
+        assert!(src[steps[0].span.clone()].starts_with("let brief"));
+        assert_eq!(steps[0].children[0].name, "publish");
+        assert_eq!(&src[steps[0].children[0].span.clone()], "send(brief)");
+    }
+
+    #[test]
+    fn folding_and_selection_ranges_follow_brackets_strings_and_comments() {
+        use ide::FoldingKind;
+
+        let src = "// one\n// two\ntask Run(topic: String) {\n  let text = \"a\nb\"\n  call(topic, 1)\n}\n";
+        let folds = ide::folding_ranges(src);
+        let folded: Vec<_> = folds
+            .iter()
+            .map(|fold| (fold.kind, &src[fold.span.clone()]))
+            .collect();
+        assert_eq!(
+            folded,
+            [
+                (FoldingKind::Comment, "// one\n// two"),
+                (
+                    FoldingKind::Region,
+                    "{\n  let text = \"a\nb\"\n  call(topic, 1)\n}"
+                ),
+                (FoldingKind::Literal, "\"a\nb\""),
+            ]
+        );
+
+        let offset = src.find("topic, 1").unwrap() + 2;
+        let selections: Vec<_> = ide::selection_ranges(src, offset)
+            .into_iter()
+            .map(|span| &src[span])
+            .collect();
+        assert_eq!(selections[0], "topic");
+        assert_eq!(selections[1], "topic, 1");
+        assert_eq!(selections[2], "(topic, 1)");
+        assert_eq!(selections[3], "call(topic, 1)");
+        assert!(selections[4].starts_with("let text"));
+        assert_eq!(*selections.last().unwrap(), src);
+    }
+
+    #[test]
+    fn organize_imports_sorts_merges_and_drops_unused_members() {
+        let src = r#"
+            import core.text { trim, join }
+            import core.io
+            import core.text { split, trim }
+            import core.text as T
+            import core.json { parse }
+        "#;
+
+        let mut module = parse_module(src).expect("parser should succeed");
+        transform::organize_imports(&mut module);
+        assert_eq!(
+            transform::render_imports(&module.imports),
+            "import core.io\n\
+             import core.json { parse }\n\
+             import core.text { join, split, trim }\n\
+             import core.text as T\n"
+        );
+
+        transform::organize_imports_with(&mut module, |_, member| member != "parse");
+        let paths: Vec<_> = module
+            .imports
+            .iter()
+            .map(|import| import.path.join("."))
+            .collect();
+        assert_eq!(paths, ["core.io", "core.text", "core.text"]);
+    }
+
+    #[test]
+    fn embedded_stdlib_modules_parse_strictly() {
+        let strict = ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        };
+        for name in stdlib::module_names() {
+            let source = stdlib::source(name).unwrap();
+            let module = parse_module_with(source, &strict)
+                .unwrap_or_else(|err| panic!("{name} failed to parse: {err}"));
+            assert_eq!(module.name.map(|n| n.join(".")).as_deref(), Some(name));
+        }
+
+        let text = stdlib::load("core.text").unwrap().unwrap();
+        let trim = text
+            .items
+            .iter()
+            .find_map(|item| match item {
+                ast::Item::Task(task) if task.name == "trim" => Some(task),
+                _ => None,
+            })
+            .expect("core.text declares trim");
+        assert_eq!(trim.params[0].name, "s");
+        assert!(stdlib::load("core.missing").is_none());
+    }
+
+    #[test]
+    fn tool_schemas_follow_task_signatures_and_docs() {
+        let src = r#"
+            record Source {
+              url: String
+              score?: Float
+            }
+
+            /// Research a topic.
+            /// Returns a brief.
+            task Research(topic: String, sources: List[Source], depth: Int = 2, note: String?) -> String {
+              return topic
+            }
+        "#;
+        let options = ParseOptions {
+            keep_trivia: true,
+            ..ParseOptions::default()
+        };
+        let module = parse_module_with(src, &options).expect("parser should succeed");
+
+        let schemas = schema::tool_schemas(&module);
+        assert_eq!(
+            schemas,
+            [serde_json::json!({
+                "name": "Research",
+                "description": "Research a topic.\nReturns a brief.",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "topic": { "type": "string" },
+                        "sources": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "url": { "type": "string" },
+                                    "score": { "type": "number" },
+                                },
+                                "required": ["url"],
+                            },
+                        },
+                        "depth": { "type": "integer", "default": 2 },
+                        "note": { "type": "string" },
+                    },
+                    "required": ["topic", "sources"],
+                },
+            })]
+        );
+    }
+
+    #[test]
+    fn tool_schemas_include_inherited_record_fields() {
+        let src = r#"
+            record Source {
+              url: String
+              score?: Float
+            }
+            record RankedSource extends Source {
+              rank: Int
+            }
+            task Cite(source: RankedSource) {}
+        "#;
+        let module = parse_module(src).expect("parser should succeed");
+        let schemas = schema::tool_schemas(&module);
+        assert_eq!(
+            schemas[0]["parameters"]["properties"]["source"],
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "url": { "type": "string" },
+                    "score": { "type": "number" },
+                    "rank": { "type": "integer" },
+                },
+                "required": ["url", "rank"],
+            })
+        );
+    }
+
+    #[test]
+    fn naming_lints_report_case_violations_with_fixes() {
+        let src = r#"
+            record source_page {
+              page_url: String
+              HTTPStatus: Int
+              title: String
+            }
+            task ProduceBrief(topic: String, max_results: Int) -> String {
+              return topic
+            }
+            workflow HTTPPipeline { start { ProduceBrief("x", 1) } }
+        "#;
+
+        let module = parse_module(src).expect("parser should succeed");
+        let diagnostics = lint::check(src, &module, &lint::LintConfig::default());
+        let fixes: Vec<_> = diagnostics
+            .iter()
+            .map(|diag| {
+                let fix = diag.fix.as_ref().expect("naming fixes are mechanical");
+                (diag.rule, &src[fix.span.clone()], fix.replacement.as_str())
+            })
+            .collect();
+        assert_eq!(
+            fixes,
+            [
+                ("naming::type-case", "source_page", "SourcePage"),
+                ("naming::member-case", "page_url", "pageUrl"),
+                ("naming::member-case", "HTTPStatus", "httpStatus"),
+                ("naming::member-case", "max_results", "maxResults"),
+            ]
+        );
+
+        let snake_members = lint::LintConfig {
+            type_case: None,
+            member_case: Some(lint::Case::Snake),
+        };
+        let diagnostics = lint::check(src, &module, &snake_members);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "field `HTTPStatus` should be written `http_status`"
+        );
+        assert_eq!(
+            lint::convert("HTTPPipeline", lint::Case::Snake),
+            "http_pipeline"
+        );
+        assert_eq!(
+            lint::convert("parseURL2Text", lint::Case::Pascal),
+            "ParseUrl2Text"
+        );
+    }
+
+    #[test]
+    fn naming_lints_reach_nested_modules_and_only_fix_unused_names() {
+        let src = r#"
+            module outer {
+              record inner_rec {
+                fn Resize(new_width: Int) {}
+              }
+            }
+            record Page {
+              page_url: String
+            }
+            task Show(page: Page) -> String {
+              return page.page_url
+            }
+        "#;
+        let module = parse_module(src).expect("parser should succeed");
+        let diagnostics = lint::check(src, &module, &lint::LintConfig::default());
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|diag| {
+                (
+                    &src[diag.span.clone()],
+                    diag.fix.as_ref().map(|fix| fix.replacement.as_str()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("inner_rec", Some("InnerRec")),
+                ("new_width", Some("newWidth")),
+                ("page_url", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn naming_lints_look_past_visibility_modifiers() {
+        let cases = [
+            ("pub record bad_rec {}", "bad_rec", "BadRec"),
+            ("internal task fetch_it() {}", "fetch_it", "FetchIt"),
+            ("pub async task load_all() {}", "load_all", "LoadAll"),
+            (
+                "internal workflow nightly_run {}",
+                "nightly_run",
+                "NightlyRun",
+            ),
+        ];
+        for (src, name, expected) in cases {
+            let module = parse_module(src).expect("parser should succeed");
+            let diagnostics = lint::check(src, &module, &lint::LintConfig::default());
+            let fixes: Vec<_> = diagnostics
+                .iter()
+                .filter_map(|diag| diag.fix.as_ref())
+                .map(|fix| (&src[fix.span.clone()], fix.replacement.as_str()))
+                .collect();
+            assert_eq!(fixes, [(name, expected)], "{src}");
+        }
+    }
+
+    #[test]
+    fn ast_dump_is_a_stable_indented_tree() {
+        let src = r#"
+            module org.example.dump
+            import core.text { trim } as T
+
+            record Brief {
+              title: String
+              tags?: List[String]
+            }
+
+            task Produce(topic: String, limit: Int = 3) -> Brief? {
+              let brief: Brief = Brief { title: T.trim(topic) }
+              return brief
+            }
+
+            workflow Main {
+              start { Produce("x") }
+            }
+        "#;
+
+        let module = parse_module(src).expect("parser should succeed");
+        let expected = "\
+Module org.example.dump
+  Import core.text { trim } as T
+  Record Brief
+    Field title: String
+    Field tags?: List[String]
+  Task Produce -> Brief?
+    Param topic: String
+    Param limit: Int =
+      Literal 3
+    Block
+      Let brief: Brief
+        StructLiteral Brief
+          Field title
+            Call
+              Member .trim
+                Identifier T
+              Identifier topic
+      Return
+        Identifier brief
+  Workflow Main
+    Block
+      Labeled start
+        Block
+          Call
+            Identifier Produce
+            Literal \"x\"
+";
+        assert_eq!(ast::dump(&module), expected);
+    }
+
+    #[test]
+    fn conformance_corpus_passes() {
+        let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("conformance");
+        let report = conformance::run(&corpus).expect("corpus should be readable");
+        assert_eq!(report.cases.len(), 9);
+        assert!(report.is_success(), "{report}");
+    }
+
+    #[test]
+    fn grammar_description_is_closed_and_renders() {
+        let grammar = grammar::describe();
+        let names: Vec<&str> = grammar.rules.iter().map(|rule| rule.name).collect();
+
+        fn references(expr: &grammar::Expr, out: &mut Vec<&'static str>) {
+            match expr {
+                grammar::Expr::Rule(name) => out.push(name),
+                grammar::Expr::Sequence(items) | grammar::Expr::Choice(items) => {
+                    items.iter().for_each(|item| references(item, out))
+                }
+                grammar::Expr::Optional(inner) | grammar::Expr::Repeat(inner) => {
+                    references(inner, out)
+                }
+                grammar::Expr::Terminal(_) | grammar::Expr::Token(_) => {}
+            }
+        }
+        let mut referenced = Vec::new();
+        for rule in &grammar.rules {
+            references(&rule.expr, &mut referenced);
+        }
+        for name in referenced {
+            assert!(names.contains(&name), "undefined rule {name}");
+        }
+
+        let ebnf = grammar.to_ebnf();
+        assert!(
+            ebnf.contains(
+                "TaskDecl     = Annotation* Visibility? \"async\"? \"task\" Signature WhereClause? RetryClause? ( \"timeout\" DURATION )? Block ;"
+            ),
+            "{ebnf}"
+        );
+        assert!(grammar.keywords.contains(&"workflow"));
+        let json = grammar.to_json();
+        assert_eq!(json["rules"][0]["name"], "Program");
+        assert_eq!(json["rules"][0]["expr"]["type"], "sequence");
+    }
+
+    #[test]
+    fn grammar_describes_every_item_keyword() {
+        // The keywords `parse_items` dispatches on, read from its match arms.
+        let source = include_str!("parser.rs");
+        let start = source
+            .find("pub(crate) fn parse_items(")
+            .expect("parse_items exists");
+        let body = &source[start..];
+        let body = &body[..body.find("\n}\n").expect("parse_items ends")];
+        let dispatched: Vec<&str> = body
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("Some(\""))
+            .filter_map(|arm| arm.split_once(')').map(|(words, _)| words))
+            .flat_map(|words| words.split('|'))
+            .map(|word| word.trim().trim_matches('"'))
+            .collect();
+        assert!(dispatched.contains(&"record") && dispatched.contains(&"secret"));
+
+        let grammar = grammar::describe();
+        let mut pending = vec!["Item"];
+        let mut seen = Vec::new();
+        let mut terminals = Vec::new();
+        fn walk<'a>(
+            expr: &'a grammar::Expr,
+            rules: &mut Vec<&'static str>,
+            terminals: &mut Vec<&'a str>,
+        ) {
+            match expr {
+                grammar::Expr::Rule(name) => rules.push(name),
+                grammar::Expr::Terminal(text) => terminals.push(text),
+                grammar::Expr::Sequence(items) | grammar::Expr::Choice(items) => {
+                    items.iter().for_each(|item| walk(item, rules, terminals))
+                }
+                grammar::Expr::Optional(inner) | grammar::Expr::Repeat(inner) => {
+                    walk(inner, rules, terminals)
+                }
+                grammar::Expr::Token(_) => {}
+            }
+        }
+        while let Some(name) = pending.pop() {
+            if seen.contains(&name) {
+                continue;
+            }
+            seen.push(name);
+            let rule = grammar.rules.iter().find(|rule| rule.name == name).unwrap();
+            walk(&rule.expr, &mut pending, &mut terminals);
+        }
+        for keyword in dispatched {
+            assert!(
+                terminals.contains(&keyword),
+                "`{keyword}` starts an item but no rule reachable from Item mentions it"
+            );
+        }
+    }
+
+    #[test]
+    fn literals_are_decoded_by_kind() {
+        use std::time::Duration;
+
+        let src = r#"
+            task Values() {
+              let text = "say \"hi\"\n\\done"
+              let count = 10_000
+              let negative = -3
+              let ratio = 0.25
+              let flag = false
+              let nothing = null
+              let wait = 45s
+              let quick = 250ms
+              let name = nullable
+              let escaped = "\0\u{1F600}\u{41}\'\u{110000}\u{zz}\q"
+            }
+        "#;
+
+        let module = parse_module(src).expect("parser should succeed");
+        let task = match &module.items[0] {
+            ast::Item::Task(task) => task,
+            other => panic!("expected task, got {:?}", other),
+        };
+        let values: Vec<&ast::Expression> = task
+            .body
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                ast::Statement::Let {
+                    value: Some(value), ..
+                } => Some(value),
+                _ => None,
+            })
+            .collect();
+        let literal = |expr: &ast::Expression| match expr {
+            ast::Expression::Literal(literal) => literal.clone(),
+            other => panic!("expected literal, got {:?}", other),
+        };
+
+        assert_eq!(
+            literal(values[0]),
+            ast::Literal::Str("say \"hi\"\n\\done".to_string())
+        );
+        assert_eq!(literal(values[1]), ast::Literal::Int(10_000));
+        assert_eq!(literal(values[2]), ast::Literal::Int(-3));
+        assert_eq!(literal(values[3]), ast::Literal::Float(0.25));
+        assert_eq!(literal(values[4]), ast::Literal::Bool(false));
+        assert_eq!(literal(values[5]), ast::Literal::Null);
+        assert_eq!(
+            literal(values[6]),
+            ast::Literal::Duration(Duration::from_secs(45))
+        );
+        assert_eq!(
+            literal(values[7]),
+            ast::Literal::Duration(Duration::from_millis(250))
+        );
+        assert!(matches!(values[8], ast::Expression::Identifier(name) if name == "nullable"));
+        assert_eq!(
+            literal(values[9]),
+            ast::Literal::Str("\0\u{1F600}A'\\u{110000}\\u{zz}\\q".to_string())
+        );
+        assert_eq!(
+            printer::print_expression(values[9]),
+            r#""\0😀A'\\u{110000}\\u{zz}\\q""#
+        );
+    }
+
+    #[test]
+    fn generated_modules_print_and_parse_back() {
+        let options = ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        };
+        for seed in 0..64 {
+            let config = generate::GenConfig::with_seed(seed);
+            let module = generate::module(&config);
+            let src = printer::print_module(&module);
+            assert_eq!(
+                src,
+                generate::source(&config),
+                "generation is deterministic"
+            );
+
+            let parsed = parse_module_with(&src, &options)
+                .unwrap_or_else(|err| panic!("seed {seed} should parse: {err}\n{src}"));
+            assert_eq!(
+                ast::dump(&parsed),
+                ast::dump(&module),
+                "seed {seed}:\n{src}"
+            );
+        }
+    }
+
+    #[test]
+    fn codemods_rewrite_only_the_edited_ranges() {
+        let rename = codemod::RenameMember {
+            module: vec!["core".into(), "text".into()],
+            from: "trim".into(),
+            to: "strip".into(),
+        };
+        let sources = [
+            r#"import core.text { trim, lower }
+import other { trim }
+
+// trim the topic first
+task Clean(topic: String) -> String {
+  let t = trim(topic)   // keep "trim" in strings
+  return other.trim(lower(t))
+}
+"#,
+            r#"import core.text as T
+
+task Clean(topic: String) -> String {
+  return T.trim(topic).trim
+}
+"#,
+            "task Broken(",
+        ];
+
+        let options = ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        };
+        let results = codemod::rewrite_many(&sources, &rename, &options);
+        assert_eq!(
+            results[0].as_ref().expect("first source rewrites"),
+            r#"import core.text { strip, lower }
+import other { trim }
+
+// trim the topic first
+task Clean(topic: String) -> String {
+  let t = strip(topic)   // keep "trim" in strings
+  return other.trim(lower(t))
+}
+"#
+        );
+        assert_eq!(
+            results[1].as_ref().expect("second source rewrites"),
+            r#"import core.text as T
+
+task Clean(topic: String) -> String {
+  return T.strip(topic).trim
+}
+"#
+        );
+        assert!(results[2].is_err());
+
+        let shadowed = r#"import core.text { trim }
+
+task Clean(topic: String) -> String {
+  let cleaned = trim(topic)
+  {
+    let trim = "  "
+    log(trim)
+  }
+  for trim in ["a"] { log(trim) }
+  return trim(cleaned)
+}
+
+task Pad<T>(text: T, trim: Bool) -> String {
+  return pad(text, trim)
+}
+
+record Line {
+  fn strip(trim: Int) { log(trim) }
+}
+"#;
+        let rewritten = codemod::rewrite(shadowed, &rename, &options).expect("rewrites");
+        assert_eq!(
+            rewritten,
+            shadowed
+                .replacen("{ trim }", "{ strip }", 1)
+                .replacen("trim(topic)", "strip(topic)", 1)
+                .replacen("trim(cleaned)", "strip(cleaned)", 1)
+        );
+        let declared =
+            "import core.text { trim }\n\ntask trim(s: String) {}\n\ntask Run() { trim(\"x\") }\n";
+        assert_eq!(
+            codemod::rewrite(declared, &rename, &options).expect("rewrites"),
+            declared.replacen("{ trim }", "{ strip }", 1)
+        );
+
+        let overlapping = [
+            TextEdit {
+                range: 0..4,
+                text: "a".into(),
+            },
+            TextEdit {
+                range: 2..6,
+                text: "b".into(),
+            },
+        ];
+        assert!(matches!(
+            codemod::apply_edits("abcdefgh", &overlapping),
+            Err(HiloParseError::InvalidEdit(_))
+        ));
+    }
+
+    #[test]
+    fn compat_diff_classifies_api_changes() {
+        let old = parse_module(
+            r#"
+            record Brief {
+              title: String
+              tags?: List[String]
+            }
+
+            task Produce(topic: String, limit: Int) -> Brief {
+              return Brief { title: topic }
+            }
+
+            task Legacy() {}
+
+            workflow Main {
+              start { Produce("x", 3) }
+            }
+            "#,
+        )
+        .expect("old version parses");
+        let new = parse_module(
+            r#"
+            record Brief {
+              title: String
+              summary?: String
+              score: Float
+            }
+
+            task Produce(topic: String, limit: Float, tone: String = "neutral") -> Brief {
+              let brief = Brief { title: topic, score: 0.0 }
+              return brief
+            }
+
+            workflow Main {
+              start { Produce("x", 3.0) }
+            }
+
+            task Publish(brief: Brief) {}
+            "#,
+        )
+        .expect("new version parses");
+
+        let report = compat::diff(&old, &new);
+        let summary: Vec<(compat::ChangeKind, &str, &str)> = report
+            .changes
+            .iter()
+            .map(|change| (change.kind, change.path.as_str(), change.message.as_str()))
+            .collect();
+        use compat::ChangeKind::*;
+        assert_eq!(
+            summary,
+            vec![
+                (Breaking, "Brief.tags", "field removed"),
+                (Additive, "Brief.summary", "optional field added"),
+                (Breaking, "Brief.score", "required field added"),
+                (Breaking, "Produce(limit)", "type changed from Int to Float"),
+                (Additive, "Produce(tone)", "parameter with default added"),
+                (Internal, "Produce", "body changed"),
+                (Breaking, "Legacy", "task removed"),
+                (Internal, "Main", "body changed"),
+                (Additive, "Publish", "task added"),
+            ]
+        );
+        assert_eq!(report.required_bump(), compat::Bump::Major);
+        assert_eq!(
+            compat::diff(&new, &new).required_bump(),
+            compat::Bump::Patch
+        );
+    }
+
+    #[test]
+    fn compat_treats_moving_fields_into_a_new_parent_as_internal() {
+        let old = parse_module("record Page {\n  url: String\n  title: String\n}\n").unwrap();
+        let moved = parse_module(
+            "record Doc {\n  url: String\n}\n\nrecord Page extends Doc {\n  title: String\n}\n",
+        )
+        .unwrap();
+        let widened = parse_module(
+            "record Doc {\n  url: String\n  lang: String\n}\n\nrecord Page extends Doc {\n  title: String\n}\n",
+        )
+        .unwrap();
+        let summary = |new: &ast::Module| {
+            compat::diff(&old, new)
+                .changes
+                .iter()
+                .map(|change| (change.kind, change.path.clone(), change.message.clone()))
+                .collect::<Vec<_>>()
+        };
+        use compat::ChangeKind::*;
+        let page = |kind, message: &str| (kind, "Page".to_string(), message.to_string());
+
+        let changes = summary(&moved);
+        assert!(
+            changes.contains(&page(
+                Internal,
+                "now extends Doc, whose fields it already had"
+            )),
+            "{changes:?}"
+        );
+        assert!(
+            changes.iter().all(|change| change.0 != Breaking),
+            "{changes:?}"
+        );
+
+        let changes = summary(&widened);
+        assert!(
+            changes.contains(&page(Breaking, "parent changed from nothing to Doc")),
+            "{changes:?}"
+        );
+    }
+
+    #[test]
+    fn bundles_imported_modules_into_one() {
+        let parse = |src: &str| parse_module(src).expect("module parses");
+        let app = parse(
+            r#"module app
+import lib.text { clean }
+import lib.util as U
+import core.io
+
+record Brief {
+  title: String
+}
+
+task Main(topic: String) -> Brief {
+  U.log(topic)
+  return Brief { title: clean(topic) }
+}
+"#,
+        );
+        let modules = [
+            parse(
+                r#"module lib.text
+
+record Brief {
+  body: String
+}
+
+task clean(text: String) -> String {
+  let brief = Brief { body: text }
+  return brief.body
+}
+
+task unused() {}
+
+test "clean trims" {
+  clean(" x ")
+}
+"#,
+            ),
+            parse(
+                r#"module lib.util
+import lib.text
+
+task log(message: String) {
+  text.clean(message)
+}
+"#,
+            ),
+        ];
+
+        let bundled = bundle::bundle(
+            &app,
+            &modules,
+            &bundle::BundleOptions { strip_unused: true },
+        )
+        .expect("imports resolve");
+        assert_eq!(
+            printer::print_module(&bundled),
+            r#"module app
+
+import core.io
+
+record lib_text_Brief {
+  body: String
+}
+
+task clean(text: String) -> String {
+  let brief = lib_text_Brief { body: text }
+  return brief.body
+}
+
+task log(message: String) {
+  clean(message)
+}
+
+record Brief {
+  title: String
+}
+
+task Main(topic: String) -> Brief {
+  log(topic)
+  return Brief { title: clean(topic) }
+}
+"#
+        );
+
+        let missing = bundle::bundle(&app, &modules[..1], &bundle::BundleOptions::default());
+        assert!(matches!(
+            missing,
+            Err(HiloParseError::UnresolvedImport(path)) if path == "lib.util"
+        ));
+    }
+
+    #[test]
+    fn bundles_rename_colliding_triggers_and_hooks() {
+        let parse = |src: &str| parse_module(src).expect("module parses");
+        let app = parse(
+            "module app\nimport jobs\n\nworkflow Main {}\n\ntrigger Daily { cron: \"0 9 * * *\", run: Main }\n\nhook Audit before task * {}\n",
+        );
+        let jobs = parse(
+            "module jobs\n\nworkflow Cleanup {}\n\ntrigger Daily { cron: \"0 2 * * *\", run: Cleanup }\n\nhook Audit after task * {}\n",
+        );
+
+        for strip_unused in [false, true] {
+            let bundled = bundle::bundle(
+                &app,
+                std::slice::from_ref(&jobs),
+                &bundle::BundleOptions { strip_unused },
+            )
+            .expect("imports resolve");
+            let printed = printer::print_module(&bundled);
+            assert_eq!(
+                printed,
+                "module app\n\nworkflow Cleanup {}\n\ntrigger jobs_Daily {\n  cron: \"0 2 * * *\"\n  run: Cleanup\n}\n\nhook jobs_Audit after task * {}\n\nworkflow Main {}\n\ntrigger Daily {\n  cron: \"0 9 * * *\"\n  run: Main\n}\n\nhook Audit before task * {}\n",
+                "strip_unused: {strip_unused}"
+            );
+        }
+    }
+
+    #[test]
+    fn bundles_a_project_from_disk() {
+        let root = std::env::temp_dir().join(format!("hilo-bundle-{}", std::process::id()));
+        let write = |path: &str, src: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, src).unwrap();
+        };
+        write(
+            "hilo.toml",
+            "[project]\nname = \"demo\"\nentry = \"src/app.hilo\"\n",
+        );
+        write(
+            "src/app.hilo",
+            "module app\nimport lib.text { clean }\n\ntask Main(topic: String) -> String {\n  return clean(topic)\n}\n",
+        );
+        write(
+            "src/lib/text.hilo",
+            "module lib.text\n\ntask clean(text: String) -> String {\n  return text\n}\n",
+        );
+        write("target/stale.hilo", "this is not hilo");
+
+        let bundled = bundle::bundle_project(&root, &bundle::BundleOptions::default());
+        write("src/broken.hilo", "module broken\nwidget Foo {}\n");
+        let broken = bundle::bundle_project(&root, &bundle::BundleOptions::default());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            bundled.expect("project bundles"),
+            "module app\n\ntask clean(text: String) -> String {\n  return text\n}\n\ntask Main(topic: String) -> String {\n  return clean(topic)\n}\n"
+        );
+        assert!(matches!(
+            broken,
+            Err(HiloParseError::Parse(message)) if message.contains("broken.hilo")
+        ));
+    }
+
+    #[test]
+    fn scaffold_templates_parse_strictly() {
+        let hilo = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../bin/hilo");
+        let root = std::env::temp_dir().join(format!("hilo-templates-{}", std::process::id()));
+        let strict = ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        };
+        for template in ["minimal", "agent-pipeline", "api-service"] {
+            let dir = root.join(template);
+            let status = std::process::Command::new(&hilo)
+                .args(["new".as_ref(), dir.as_os_str(), "--template".as_ref()])
+                .arg(template)
+                .stdout(std::process::Stdio::null())
+                .status()
+                .expect("bin/hilo runs");
+            assert!(status.success(), "hilo new --template {template} failed");
+            let src = std::fs::read_to_string(dir.join("src/main.hilo")).unwrap();
+            let module = parse_module_with(&src, &strict)
+                .unwrap_or_else(|err| panic!("{template} template: {err}"));
+            let dump = ast::dump(&module);
+            assert!(!dump.contains("Raw"), "{template} template:\n{dump}");
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn records_extend_parents_and_flatten_fields() {
+        let src = r#"
+            import shared { Base }
+
+            record Brief {
+              title: String
+              tags?: List[String]
+            }
+
+            record DetailedBrief extends Brief {
+              citations: List[String]
+              tags?: List[String]
+            }
+
+            record Conflicting extends DetailedBrief {
+              title: Int
+            }
+
+            record Remote extends Base {}
+            record Orphan extends Missing {}
+            record Loop extends Loop {}
+        "#;
+        let module = parse_module(src).expect("parser should succeed");
+        let detailed = semantic::record(&module, "DetailedBrief").expect("record exists");
+        assert_eq!(detailed.extends, Some(vec!["Brief".to_string()]));
+
+        let fields: Vec<&str> = semantic::record_fields(&module, "Conflicting")
+            .expect("record exists")
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect();
+        assert_eq!(fields, ["title", "tags", "citations"]);
+
+        let diagnostics = semantic::check_records(&module);
+        let rules: Vec<&str> = diagnostics.iter().map(|diag| diag.rule).collect();
+        assert_eq!(
+            rules,
+            [
+                "records::field-conflict",
+                "records::unknown-parent",
+                "records::inheritance-cycle"
+            ]
+        );
+        assert_eq!(&src[diagnostics[0].span.clone()], "title: Int");
+        assert!(
+            ast::dump(&module).contains("Record DetailedBrief extends Brief\n"),
+            "dump shows the parent"
+        );
+    }
+
+    #[test]
+    fn parses_task_hooks() {
+        let src = r#"
+            hook LogAll around task * {
+              log("start")
+            }
+
+            hook AuditFetches before task Fetch* {}
+        "#;
+        let module = parse_module(src).expect("parser should succeed");
+        let hooks: Vec<&ast::HookDecl> = module
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ast::Item::Hook(decl) => Some(decl),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(hooks.len(), 2);
+        assert_eq!(hooks[0].name, "LogAll");
+        assert_eq!(hooks[0].position, ast::HookPosition::Around);
+        assert_eq!(hooks[0].body.statements.len(), 1);
+        assert_eq!(hooks[1].position, ast::HookPosition::Before);
+        assert_eq!(hooks[1].pattern, "Fetch*");
+        assert!(hooks[0].applies_to("Anything"));
+        assert!(hooks[1].applies_to("FetchPage"));
+        assert!(!hooks[1].applies_to("Prefetch"));
+        assert!(
+            src[hooks[1].span.clone()].starts_with("hook AuditFetches"),
+            "span covers the declaration"
+        );
+    }
+
+    #[test]
+    fn parses_map_type_shorthands() {
+        let src = r#"
+record Index {
+  counts: Map[String -> Int]
+  nested: Map[String -> Map[String, Int]]
+  short: { String: List[Int] }
+  point: { x: Int }
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Record(record) = &module.items[0] else {
+            panic!("expected record");
+        };
+        let types: Vec<String> = record
+            .fields
+            .iter()
+            .map(|field| printer::print_type(&field.ty))
+            .collect();
+        assert_eq!(
+            types,
+            [
+                "Map[String, Int]",
+                "Map[String, Map[String, Int]]",
+                "Map[String, List[Int]]",
+                "{ x: Int }",
+            ]
+        );
+        assert!(matches!(record.fields[3].ty, ast::TypeExpr::Struct(_)));
+    }
+
+    #[test]
+    fn parses_parallel_for_loops() {
+        let src = r#"
+workflow Research {
+  for source in Sources(topic, "a (b)") parallel(max: limit + 1) {
+    let page = Fetch(source)
+  }
+  for item in items parallel {
+    Log(item)
+  }
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Workflow(workflow) = &module.items[0] else {
+            panic!("expected workflow");
+        };
+        match &workflow.body.statements[..] {
+            [
+                ast::Statement::ParallelFor {
+                    binding,
+                    iterable: ast::Expression::Call { args, .. },
+                    limit: Some(ast::Expression::Binary { .. }),
+                    body,
+                },
+                ast::Statement::ParallelFor { limit: None, .. },
+            ] => {
+                assert_eq!(binding, "source");
+                assert_eq!(args.len(), 2);
+                assert!(matches!(
+                    &body.statements[..],
+                    [ast::Statement::Let { name, .. }] if name == "page"
+                ));
+                assert_eq!(&src[body.span.clone()].trim(), &"let page = Fetch(source)");
+            }
+            other => panic!("unexpected statements: {other:?}"),
+        }
+
+        let printed = printer::print_module(&module);
+        assert!(printed.contains("for item in items parallel {"));
+        assert_eq!(
+            ast::dump(&parse_module(&printed).unwrap()),
+            ast::dump(&module)
+        );
+    }
+
+    #[test]
+    fn parses_for_loops() {
+        let src = r#"
+task Summarize(data: Any) {
+  for item in data["items {"] {
+    // a stray } in a comment
+    let title = item.title
+    if_ready {
+      Log(title)
+    }
+  }
+  Done()
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Task(task) = &module.items[0] else {
+            panic!("expected task");
+        };
+        match &task.body.statements[..] {
+            [
+                ast::Statement::For {
+                    binding,
+                    iterable: ast::Expression::Index { .. },
+                    body,
+                },
+                ast::Statement::Expr(ast::Expression::Call { .. }),
+            ] => {
+                assert_eq!(binding, "item");
+                assert!(matches!(
+                    &body.statements[..],
+                    [
+                        ast::Statement::Let { .. },
+                        ast::Statement::Block { label: Some(_), .. }
+                    ]
+                ));
+                assert!(src[body.span.clone()].trim_end().ends_with('}'));
+            }
+            other => panic!("unexpected statements: {other:?}"),
+        }
+        assert!(printer::print_module(&module).contains("for item in data[\"items {\"] {"));
+    }
+
+    #[test]
+    fn parses_while_loops() {
+        let src = r#"
+task Drain(queue: Any) {
+  while queue.pending > 0 {
+    /* { unbalanced in a comment */
+    let job = queue.next()
+    while job.retrying {
+      job.step()
+    }
+  }
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Task(task) = &module.items[0] else {
+            panic!("expected task");
+        };
+        let [ast::Statement::While { condition, body }] = &task.body.statements[..] else {
+            panic!("unexpected statements: {:?}", task.body.statements);
+        };
+        assert!(matches!(condition, ast::Expression::Binary { .. }));
+        assert!(matches!(
+            &body.statements[..],
+            [ast::Statement::Let { .. }, ast::Statement::While { .. }]
+        ));
+        assert!(printer::print_module(&module).contains("  while queue.pending > 0 {\n"));
+    }
+
+    #[test]
+    fn parses_match_expressions() {
+        let src = r#"
+task Label(result: Any) -> String {
+  let label = match result.status {
+    Ok(page) if page.size > 0 => "ready",
+    Err(Timeout) => "slow"
+    Point { x, y: 0 } => "axis"
+    (first, _) => first
+    404 => "missing"
+    _ => {
+      Log(result)
+      return "unknown"
+    }
+  }
+  return label
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Task(task) = &module.items[0] else {
+            panic!("expected task");
+        };
+        let Some(ast::Statement::Let {
+            value: Some(ast::Expression::Match { subject, arms }),
+            ..
+        }) = task.body.statements.first()
+        else {
+            panic!("unexpected statements: {:?}", task.body.statements);
+        };
+        assert!(matches!(subject.as_ref(), ast::Expression::Member { .. }));
+        let patterns: Vec<String> = arms
+            .iter()
+            .map(|arm| match &arm.pattern {
+                ast::Pattern::Wildcard => "_".to_string(),
+                ast::Pattern::Binding(name) => name.clone(),
+                ast::Pattern::Literal(_) => "literal".to_string(),
+                ast::Pattern::Variant { path, fields } => {
+                    format!("{}/{}", path.join("."), fields.len())
+                }
+                ast::Pattern::Record { name, fields } => {
+                    format!("{}{{{}}}", name.join("."), fields.len())
+                }
+                ast::Pattern::Tuple(items) => format!("({})", items.len()),
+                ast::Pattern::Raw(text) => format!("raw {text}"),
+            })
+            .collect();
+        assert_eq!(
+            patterns,
+            ["Ok/1", "Err/1", "Point{2}", "(2)", "literal", "_"]
+        );
+        assert!(matches!(
+            arms[0].guard,
+            Some(ast::Expression::Binary { .. })
+        ));
+        assert_eq!(
+            arms[1].pattern,
+            ast::Pattern::Variant {
+                path: vec!["Err".into()],
+                fields: vec![ast::Pattern::Variant {
+                    path: vec!["Timeout".into()],
+                    fields: Vec::new(),
+                }],
+            }
+        );
+        let ast::ArmBody::Block(fallback) = &arms[5].body else {
+            panic!("expected block arm");
+        };
+        assert_eq!(fallback.statements.len(), 2);
+        assert!(src[fallback.span.clone()].starts_with("Log(result)"));
+
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("    Ok(page) if page.size > 0 => \"ready\"\n"),
+            "{printed}"
+        );
+        assert_eq!(
+            ast::dump(&parse_module(&printed).unwrap()),
+            ast::dump(&module)
+        );
+    }
+
+    #[test]
+    fn triple_quoted_strings_are_dedented() {
+        let src = "task Prompt(topic: String) {\n  let prompt = \"\"\"\n    Summarize \"{topic}\" for {audience}.\n\n      - cite sources\\tinline\n    \"\"\"\n  let inline = \"\"\"one \"quoted\" line\"\"\"\n  Ask(prompt)\n}\n";
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Task(task) = &module.items[0] else {
+            panic!("expected task");
+        };
+        let values: Vec<&ast::Literal> = task
+            .body
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                ast::Statement::Let {
+                    value: Some(ast::Expression::Literal(literal)),
+                    ..
+                } => Some(literal),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            values,
+            [
+                &ast::Literal::Str(
+                    "Summarize \"{topic}\" for {audience}.\n\n  - cite sources\tinline".into()
+                ),
+                &ast::Literal::Str("one \"quoted\" line".into()),
+            ]
+        );
+        assert_eq!(task.body.statements.len(), 3);
+    }
+
+    #[test]
+    fn parses_unary_operators() {
+        let src = r#"
+task Flags(done: Bool, offset: Int) {
+  let pending = !done
+  let back = -offset
+  let idle = not busy.active
+  let delta = total * -offset
+  let either = -offset + 1
+  let negative = -3
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Task(task) = &module.items[0] else {
+            panic!("expected task");
+        };
+        let dumped = ast::dump(&module);
+        for expected in [
+            "Let pending\n        Unary !\n          Identifier done\n",
+            "Let back\n        Unary -\n          Identifier offset\n",
+            "Let idle\n        Unary not\n          Member .active\n",
+            "Let delta\n        Binary *\n          Identifier total\n          Unary -\n",
+            "Let either\n        Binary +\n          Unary -\n",
+            "Let negative\n        Literal -3\n",
+        ] {
+            assert!(
+                dumped.contains(expected),
+                "missing {expected:?} in\n{dumped}"
+            );
+        }
+        assert_eq!(task.body.statements.len(), 6);
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("let idle = not busy.active\n"),
+            "{printed}"
+        );
+        assert!(
+            printed.contains("let delta = total * -offset\n"),
+            "{printed}"
+        );
+    }
+    #[test]
+    fn binary_operators_follow_precedence() {
+        let src = r#"
+task Score(a: Int, b: Int, c: Int) {
+  let sum = a + b * c
+  let mixed = a * 2 + b == c - 1
+  let chained = a - b - c
+  let grouped = (a + b) * c
+  let ready = !ready(job) == false
+  let logic = a < b && b < c || done
+  let words = a > 0 and not done or late
+  let called = Writer.run(brief, audience="Engineer").title
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let dumped = ast::dump(&module);
+        for expected in [
+            "Let sum\n        Binary +\n          Identifier a\n          Binary *\n",
+            "Let mixed\n        Binary ==\n          Binary +\n            Binary *\n",
+            "Let chained\n        Binary -\n          Binary -\n            Identifier a\n            Identifier b\n          Identifier c\n",
+            "Let grouped\n        Binary *\n          Binary +\n",
+            "Let ready\n        Binary ==\n          Unary !\n            Call\n              Identifier ready\n              Identifier job\n          Literal false\n",
+            "Let logic\n        Binary ||\n          Binary &&\n",
+            "Let words\n        Binary or\n          Binary and\n            Binary >\n",
+            "Member .title\n          Call\n            Member .run\n              Identifier Writer\n            Identifier brief\n            Arg audience\n              Literal \"Engineer\"\n",
+        ] {
+            assert!(
+                dumped.contains(expected),
+                "missing {expected:?} in\n{dumped}"
+            );
+        }
+        let printed = printer::print_module(&module);
+        for expected in [
+            "let sum = a + b * c\n",
+            "let chained = a - b - c\n",
+            "let grouped = (a + b) * c\n",
+            "let ready = !ready(job) == false\n",
+        ] {
+            assert!(
+                printed.contains(expected),
+                "missing {expected:?} in\n{printed}"
+            );
+        }
+    }
+    #[test]
+    fn parses_lambda_expressions() {
+        let src = r#"
+task Titles(briefs: List[Brief]) -> List[String] {
+  let titles = List.map(briefs, (b) => b.title)
+  let long = List.filter(titles, t => t.length > 10)
+  let add = (a: Int, b: Int) => a + b
+  let tidy = List.map(titles, (title) => {
+    let trimmed = T.trim(title)
+    return trimmed
+  })
+  return titles
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Task(task) = &module.items[0] else {
+            panic!("expected task");
+        };
+        assert_eq!(task.body.statements.len(), 5);
+        let dumped = ast::dump(&module);
+        for expected in [
+            "Identifier briefs\n          Lambda (b)\n            Member .title\n",
+            "Lambda (t)\n            Binary >\n",
+            "Let add\n        Lambda (a: Int, b: Int)\n          Binary +\n",
+            "Lambda (title)\n            Block\n              Let trimmed\n",
+        ] {
+            assert!(
+                dumped.contains(expected),
+                "missing {expected:?} in\n{dumped}"
+            );
+        }
+        let ast::Statement::Let {
+            value: Some(ast::Expression::Call { args, .. }),
+            ..
+        } = &task.body.statements[3]
+        else {
+            panic!("expected call");
+        };
+        let ast::Expression::Lambda { body, .. } = &args[1].value else {
+            panic!("expected lambda argument");
+        };
+        let ast::ArmBody::Block(block) = body.as_ref() else {
+            panic!("expected block body");
+        };
+        assert_eq!(&src[block.span.clone()], block.raw);
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("let add = (a: Int, b: Int) => a + b\n"),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+    }
+    #[test]
+    fn parses_async_tasks_and_await() {
+        let src = r#"
+record Client {
+  base: String
+
+  async fn get(path: String) -> String {
+    return await http.get(base + path)
+  }
+}
+
+async task Fetch(url: String) -> String {
+  let page = await http.get(url, null, 10s)
+  let size = (await Measure(page)).bytes + 1
+  return page
+}
+
+task Plain() {
+  return 1
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Record(client) = &module.items[0] else {
+            panic!("expected record");
+        };
+        assert_eq!(client.fields.len(), 1);
+        assert!(client.methods[0].is_async);
+        let ast::Item::Task(fetch) = &module.items[1] else {
+            panic!("expected task");
+        };
+        assert!(fetch.is_async);
+        assert_eq!(&src[fetch.span.clone()][..11], "async task ");
+        let ast::Item::Task(plain) = &module.items[2] else {
+            panic!("expected task");
+        };
+        assert!(!plain.is_async);
+        let dumped = ast::dump(&module);
+        for expected in [
+            "Method async get -> String\n",
+            "Task async Fetch -> String\n",
+            "Let page\n        Await\n          Call\n",
+            "Let size\n        Binary +\n          Member .bytes\n            Await\n",
+        ] {
+            assert!(
+                dumped.contains(expected),
+                "missing {expected:?} in\n{dumped}"
+            );
+        }
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("async task Fetch(url: String)"),
+            "{printed}"
+        );
+        assert!(
+            printed.contains("let size = (await Measure(page)).bytes + 1\n"),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+    }
+    #[test]
+    fn parses_interface_declarations() {
+        let src = r#"
+/// Anything that can condense text.
+interface Summarizer {
+  /// One paragraph, at most `words` long.
+  task summarize(text: String, words: Int = 120) -> String
+  async task review(draft: Draft) -> List[String]; fn reset()
+}
+
+trait Fetcher { task fetch(url: String) -> String }
+
+async task crawl_site(url: String) {}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Interface(summarizer) = &module.items[0] else {
+            panic!("expected interface");
+        };
+        assert_eq!(summarizer.name, "Summarizer");
+        let names: Vec<_> = summarizer.methods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["summarize", "review", "reset"]);
+        assert!(summarizer.methods[1].is_async);
+        assert_eq!(
+            summarizer.methods[0].params[1].default,
+            Some(ast::Expression::Literal(ast::Literal::Int(120)))
+        );
+        assert_eq!(&src[summarizer.methods[2].span.clone()], "fn reset()");
+        assert!(matches!(&module.items[1], ast::Item::Interface(decl) if decl.name == "Fetcher"));
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains(
+                "Interface Summarizer\n    Signature summarize -> String\n      Param text: String\n"
+            ),
+            "{dumped}"
+        );
+        assert!(
+            dumped.contains("Signature async review -> List[String]\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("interface Fetcher {\n  task fetch(url: String) -> String\n}\n"),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+
+        let symbols = ide::document_symbols(&module);
+        assert_eq!(symbols[0].kind, ide::SymbolKind::Interface);
+        assert_eq!(symbols[0].children.len(), 3);
+
+        let diagnostics = lint::check(src, &module, &lint::LintConfig::default());
+        let renames: Vec<_> = diagnostics
+            .iter()
+            .map(|diag| &src[diag.span.clone()])
+            .collect();
+        assert_eq!(renames, ["crawl_site"]);
+
+        let narrowed = parse_module(
+            "interface Summarizer {\n  task summarize(text: String) -> String\n  task outline(text: String)\n}\ntrait Fetcher { task fetch(url: String) -> String }",
+        )
+        .expect("new version parses");
+        let report = compat::diff(&module, &narrowed);
+        let summary: Vec<_> = report
+            .breaking()
+            .map(|change| (change.path.as_str(), change.message.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Summarizer.summarize(words)", "parameter removed"),
+                ("Summarizer.review", "method removed"),
+                ("Summarizer.reset", "method removed"),
+                (
+                    "Summarizer.outline",
+                    "method added; implementations must provide it"
+                ),
+                ("crawl_site", "task removed"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_prompt_declarations() {
+        let src = r#"
+prompt ResearchPrompt {
+  system: """
+    You are a research assistant for {team}.
+    Reply as JSON: {{"sources": []}}
+    """,
+  user: "Summarize {topic} for {brief.title}; stay on {topic}."
+  style: "plain"
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Prompt(prompt) = &module.items[0] else {
+            panic!("expected prompt");
+        };
+        assert_eq!(prompt.name, "ResearchPrompt");
+        let system = &prompt.sections[0];
+        assert_eq!(
+            system.text,
+            "You are a research assistant for {team}.\nReply as JSON: {{\"sources\": []}}"
+        );
+        assert_eq!(system.placeholders, ["team"]);
+        assert_eq!(prompt.sections[1].placeholders, ["topic", "brief.title"]);
+        assert!(prompt.sections[2].placeholders.is_empty());
+        assert!(src[prompt.sections[2].span.clone()].starts_with("style:"));
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains("    Section user \"Summarize {topic} for {brief.title}; stay on {topic}.\"\n      Placeholder topic\n      Placeholder brief.title\n"),
+            "{dumped}"
+        );
+        let reparsed =
+            parse_module(&printer::print_module(&module)).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+
+        let symbols = ide::document_symbols(&module);
+        assert_eq!(symbols[0].kind, ide::SymbolKind::Prompt);
+        assert_eq!(symbols[0].children.len(), 3);
+
+        let reworded = parse_module(
+            "prompt ResearchPrompt {\n  system: \"You research for {team} in {locale}.\"\n  user: \"Summarize {topic} for {brief.title}.\"\n}",
+        )
+        .expect("module parses");
+        let report = compat::diff(&module, &reworded);
+        let breaking: Vec<_> = report
+            .breaking()
+            .map(|change| (change.path.as_str(), change.message.as_str()))
+            .collect();
+        assert_eq!(
+            breaking,
+            [
+                (
+                    "ResearchPrompt.system",
+                    "placeholder `{locale}` added; callers must supply it"
+                ),
+                ("ResearchPrompt.style", "section removed"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_tool_declarations() {
+        let src = "record SearchResult { title: String, url: String }\n\n/// Query the web.\ntool web.search(query: String, limit: Int = 10) -> List[SearchResult] // cached\ntool audit.log(message: String); tool clock.now() -> Int\n\ntask find(topic: String) -> List[SearchResult] {\n  return web.search(topic)\n}\n";
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Tool(search) = &module.items[1] else {
+            panic!("expected tool");
+        };
+        assert_eq!(search.name, ["web", "search"]);
+        assert_eq!(search.params.len(), 2);
+        assert_eq!(
+            search
+                .return_type
+                .as_ref()
+                .map(printer::print_type)
+                .as_deref(),
+            Some("List[SearchResult]")
+        );
+        assert!(src[search.span.clone()].ends_with("-> List[SearchResult]"));
+        let names: Vec<_> = module
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ast::Item::Tool(decl) => Some(decl.name.join(".")),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, ["web.search", "audit.log", "clock.now"]);
+        assert!(matches!(module.items.last(), Some(ast::Item::Task(_))));
+        assert_eq!(
+            semantic::tool(&module, "audit.log").map(|decl| decl.return_type.is_none()),
+            Some(true)
+        );
+        assert!(semantic::tool(&module, "web").is_none());
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains("Tool web.search -> List[SearchResult]\n    Param query: String\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("tool audit.log(message: String)\n"),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+
+        let symbols = ide::document_symbols(&module);
+        assert_eq!(symbols[1].kind, ide::SymbolKind::Tool);
+        assert_eq!(symbols[1].name, "web.search");
+    }
+
+    #[test]
+    fn annotated_records_keep_the_items_after_them() {
+        let src =
+            "@deprecated(\"use Page\")\npub record R {\n  url: String\n}\n\ntask Load() -> R {}\n";
+        let module = parse_module(src).expect("annotated record parses");
+        assert_eq!(module.items.len(), 2, "{:?}", module.items);
+        let ast::Item::Record(record) = &module.items[0] else {
+            panic!("expected a record, got {:?}", module.items[0]);
+        };
+        assert_eq!(record.annotations[0].name, "deprecated");
+        assert_eq!(record.visibility, ast::Visibility::Public);
+        assert!(src[record.span.clone()].starts_with("pub record R"));
+        assert!(matches!(&module.items[1], ast::Item::Task(task) if task.name == "Load"));
+
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.starts_with("@deprecated(\"use Page\")\npub record R {"),
+            "{printed}"
+        );
+        assert_eq!(
+            ast::dump(&parse_module(&printed).unwrap()),
+            ast::dump(&module)
+        );
+
+        let err = parse_module("@deprecated\ntest \"old\" {}\n\ntask Load() {}\n")
+            .expect_err("tests take no annotations");
+        assert_eq!(
+            err.to_string(),
+            "parse error: `@deprecated` cannot annotate a `test` declaration"
+        );
+    }
+
+    #[test]
+    fn parses_annotations_on_declarations() {
+        let src = r#"
+record Source {
+  @range(min: 0, max: 1)
+  score: Float
+  @deprecated url: String
+  @memo
+  fn label() -> String { return url }
+}
+
+/// Fetch with retries.
+@retry(max: 3, backoff: "exp")
+@timeout(30s)
+async task fetch(url: String) -> String {
+  return url
+}
+
+@pure workflow Digest {
+  start { fetch("https://example.com") }
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Record(record) = &module.items[0] else {
+            panic!("expected record");
+        };
+        assert_eq!(record.fields.len(), 2);
+        assert_eq!(record.fields[0].annotations[0].name, "range");
+        assert_eq!(record.fields[1].annotations[0].name, "deprecated");
+        assert!(record.fields[1].annotations[0].args.is_empty());
+        assert_eq!(record.methods[0].annotations[0].name, "memo");
+
+        let ast::Item::Task(task) = &module.items[1] else {
+            panic!("expected task");
+        };
+        let names: Vec<_> = task.annotations.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["retry", "timeout"]);
+        let retry = &task.annotations[0];
+        assert_eq!(&src[retry.span.clone()], "@retry(max: 3, backoff: \"exp\")");
+        assert_eq!(retry.args[0].name.as_deref(), Some("max"));
+        assert_eq!(
+            retry.args[1].value,
+            ast::Expression::Literal(ast::Literal::Str("exp".into()))
+        );
+        assert_eq!(task.annotations[1].args[0].name, None);
+        assert!(src[task.span.clone()].starts_with("async task fetch"));
+        assert!(
+            matches!(&module.items[2], ast::Item::Workflow(decl) if decl.annotations[0].name == "pure")
+        );
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains("    Annotation retry\n      Arg max\n        Literal 3\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("@timeout(30s)\nasync task fetch(url: String) -> String {"),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+
+        let retuned = parse_module(&src.replace("max: 3", "max: 5")).expect("module parses");
+        let report = compat::diff(&module, &retuned);
+        let changes: Vec<_> = report
+            .changes
+            .iter()
+            .map(|change| (change.kind, change.path.as_str(), change.message.as_str()))
+            .collect();
+        assert_eq!(
+            changes,
+            [(compat::ChangeKind::Internal, "fetch", "annotations changed")]
+        );
+    }
+
+    #[test]
+    fn parses_decorated_statements() {
+        let src = r#"
+workflow Digest {
+  @parallel
+  gather {
+    @timeout(10s) fetch("https://example.com")
+  }
+  @retry(max: 2) @label("final")
+  publish { return 1 }
+  @orphan
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Workflow(workflow) = &module.items[0] else {
+            panic!("expected workflow");
+        };
+        let statements = &workflow.body.statements;
+        assert_eq!(statements.len(), 3);
+        let ast::Statement::Annotated {
+            annotations,
+            statement,
+        } = &statements[0]
+        else {
+            panic!("expected decorated step");
+        };
+        assert_eq!(annotations[0].name, "parallel");
+        assert_eq!(&src[annotations[0].span.clone()], "@parallel");
+        let ast::Statement::Block { label, body } = statement.as_ref() else {
+            panic!("expected block");
+        };
+        assert_eq!(label.as_deref(), Some("gather"));
+        assert!(matches!(
+            &body.statements[0],
+            ast::Statement::Annotated { statement, .. }
+                if matches!(statement.as_ref(), ast::Statement::Expr(ast::Expression::Call { .. }))
+        ));
+        assert!(matches!(
+            &statements[1],
+            ast::Statement::Annotated { annotations, .. } if annotations.len() == 2
+        ));
+        assert_eq!(
+            statements[2],
+            ast::Statement::Expr(ast::Expression::Raw("@orphan".into()))
+        );
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped
+                .contains("      Annotated\n        Annotation parallel\n        Labeled gather\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("  @retry(max: 2)\n  @label(\"final\")\n  publish {"),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+
+        let steps: Vec<_> = ide::document_symbols(&module)[0]
+            .children
+            .iter()
+            .map(|step| step.name.clone())
+            .collect();
+        assert_eq!(steps, ["gather", "publish"]);
+    }
+
+    #[test]
+    fn parses_nested_function_declarations() {
+        let src = r#"
+task publish(title: String) -> String {
+  @memo
+  fn slugify(s: String) -> String {
+    return s.lower()
+  }
+  async fn stamp(s: String) -> String { return s }
+  let slug = slugify(title)
+  return slug
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Task(task) = &module.items[0] else {
+            panic!("expected task");
+        };
+        assert_eq!(module.items.len(), 1);
+        let ast::Statement::FnDecl(slugify) = &task.body.statements[0] else {
+            panic!("expected nested fn");
+        };
+        assert_eq!(slugify.name, "slugify");
+        assert_eq!(slugify.annotations[0].name, "memo");
+        assert_eq!(&src[slugify.params[0].span.clone()], "s: String");
+        assert!(src[slugify.span.clone()].starts_with("fn slugify(s: String)"));
+        assert!(src[slugify.body.span.clone()].starts_with("return s.lower()"));
+        assert!(matches!(
+            &task.body.statements[1],
+            ast::Statement::FnDecl(decl) if decl.is_async && decl.name == "stamp"
+        ));
+        assert!(matches!(
+            &task.body.statements[2],
+            ast::Statement::Let { .. }
+        ));
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains(
+                "      Fn slugify -> String\n        Param s: String\n        Annotation memo\n"
+            ),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains(
+                "  @memo\n  fn slugify(s: String) -> String {\n    return s.lower()\n  }\n"
+            ),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+    }
+
+    #[test]
+    fn parses_spread_arguments_and_list_literals() {
+        let src = r#"
+task compose(base: Config, overrides: List[Config]) -> List[String] {
+  let merged = merge(base, ...overrides)
+  let tags = [...defaults, "extra", ...List.reverse(more)]
+  let bad = ...xs
+  return []
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Task(task) = &module.items[0] else {
+            panic!("expected task");
+        };
+        let values: Vec<&ast::Expression> = task
+            .body
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                ast::Statement::Let { value, .. } | ast::Statement::Return { value } => {
+                    value.as_ref()
+                }
+                _ => None,
+            })
+            .collect();
+        let ast::Expression::Call { args, .. } = values[0] else {
+            panic!("expected call, got {:?}", values[0]);
+        };
+        assert_eq!(
+            args[1].value,
+            ast::Expression::Spread(Box::new(ast::Expression::Identifier("overrides".into())))
+        );
+        let ast::Expression::List(elements) = values[1] else {
+            panic!("expected list, got {:?}", values[1]);
+        };
+        assert_eq!(elements.len(), 3);
+        assert!(matches!(
+            &elements[2],
+            ast::Expression::Spread(inner) if matches!(inner.as_ref(), ast::Expression::Call { .. })
+        ));
+        assert!(
+            matches!(values[2], ast::Expression::Raw(_)),
+            "{:?}",
+            values[2]
+        );
+        assert_eq!(values[3], &ast::Expression::List(Vec::new()));
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains("List\n          Spread\n            Identifier defaults\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("let tags = [...defaults, \"extra\", ...List.reverse(more)]"),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+    }
+
+    #[test]
+    fn parses_named_call_arguments() {
+        let src = r#"
+workflow Publish {
+  let brief = Writer.run(topic, style: "brief", limit = 3, ...extras)
+  let same = check(a == b, f = x => x)
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Workflow(workflow) = &module.items[0] else {
+            panic!("expected workflow");
+        };
+        let calls: Vec<&Vec<ast::CallArg>> = workflow
+            .body
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                ast::Statement::Let {
+                    value: Some(ast::Expression::Call { args, .. }),
+                    ..
+                } => Some(args),
+                _ => None,
+            })
+            .collect();
+        let names: Vec<Option<&str>> = calls[0].iter().map(|arg| arg.name.as_deref()).collect();
+        assert_eq!(names, [None, Some("style"), Some("limit"), None]);
+        assert_eq!(
+            calls[0][1].value,
+            ast::Expression::Literal(ast::Literal::Str("brief".into()))
+        );
+        assert!(matches!(calls[0][3].value, ast::Expression::Spread(_)));
+        assert!(calls[1][0].name.is_none());
+        assert!(matches!(
+            &calls[1][0].value,
+            ast::Expression::Binary { op, .. } if op == "=="
+        ));
+        assert_eq!(calls[1][1].name.as_deref(), Some("f"));
+        assert!(matches!(calls[1][1].value, ast::Expression::Lambda { .. }));
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains("          Arg style\n            Literal \"brief\"\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("Writer.run(topic, style: \"brief\", limit: 3, ...extras)"),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+    }
+
+    #[test]
+    fn parses_parameter_defaults_as_expressions() {
+        let src = r#"
+task Search(query: String, limit: Int = -1, sep: String = "a=b", wait: Duration = Time.seconds(30, jitter: true)) {}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Task(task) = &module.items[0] else {
+            panic!("expected task");
+        };
+        let defaults: Vec<Option<&ast::Expression>> = task
+            .params
+            .iter()
+            .map(|param| param.default.as_ref())
+            .collect();
+        assert_eq!(defaults[0], None);
+        assert_eq!(
+            defaults[1],
+            Some(&ast::Expression::Literal(ast::Literal::Int(-1)))
+        );
+        assert_eq!(
+            defaults[2],
+            Some(&ast::Expression::Literal(ast::Literal::Str("a=b".into())))
+        );
+        assert!(matches!(
+            defaults[3],
+            Some(ast::Expression::Call { args, .. }) if args.len() == 2
+        ));
+        assert_eq!(
+            &src[task.params[3].span.clone()],
+            "wait: Duration = Time.seconds(30, jitter: true)"
+        );
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains("    Param limit: Int =\n      Literal -1\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("wait: Duration = Time.seconds(30, jitter: true))"),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+    }
+
+    #[test]
+    fn parses_null_coalescing_operator() {
+        let src = r#"
+task Rank(hit: Hit?, fallback: Int) -> Bool {
+  let score = hit?.score ?? fallback + 1
+  let chained = hit?.title ?? cached?.title ?? "untitled"
+  return hit?.score ?? 0 > 5
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let dumped = ast::dump(&module);
+        for expected in [
+            "Let score\n        Binary ??\n          OptionalChain ?.score\n            Identifier hit\n          Binary +\n",
+            "Let chained\n        Binary ??\n          Binary ??\n",
+            "Return\n        Binary >\n          Binary ??\n",
+        ] {
+            assert!(
+                dumped.contains(expected),
+                "missing {expected:?} in\n{dumped}"
+            );
+        }
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("let score = hit?.score ?? fallback + 1\n"),
+            "{printed}"
+        );
+        assert_eq!(
+            printer::print_expression(&ast::Expression::Binary {
+                left: Box::new(ast::Expression::Identifier("a".into())),
+                op: "??".into(),
+                right: Box::new(ast::Expression::Binary {
+                    left: Box::new(ast::Expression::Identifier("b".into())),
+                    op: "||".into(),
+                    right: Box::new(ast::Expression::Identifier("c".into())),
+                }),
+            }),
+            "a ?? (b || c)"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+    }
+
+    #[test]
+    fn parses_try_catch_statements() {
+        let src = r#"
+task Fetch(url: String) -> String {
+  try {
+    let page = web.get(url)
+    return page.body
+  } catch (e: ToolError) {
+    log(e.message)
+  }
+  catch (e) { log(e) }
+  finally {
+    close(url)
+  }
+  try { risky() } catch { retry() }
+  try { risky() } finally { done() }
+  try { risky() }
+  return ""
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Task(task) = &module.items[0] else {
+            panic!("expected task");
+        };
+        let ast::Statement::Try {
+            body,
+            catches,
+            finally,
+        } = &task.body.statements[0]
+        else {
+            panic!("expected try, got {:?}", task.body.statements[0]);
+        };
+        assert_eq!(body.statements.len(), 2);
+        assert_eq!(catches.len(), 2);
+        assert_eq!(catches[0].binding.as_deref(), Some("e"));
+        assert_eq!(
+            catches[0].ty,
+            Some(ast::TypeExpr::Simple(vec!["ToolError".into()]))
+        );
+        assert_eq!(&src[catches[0].body.span.clone()], "log(e.message)");
+        assert_eq!(catches[1].ty, None);
+        assert_eq!(&src[finally.as_ref().unwrap().span.clone()], "close(url)");
+        assert!(matches!(
+            &task.body.statements[1],
+            ast::Statement::Try { catches, finally: None, .. }
+                if catches.len() == 1 && catches[0].binding.is_none()
+        ));
+        assert!(matches!(
+            &task.body.statements[2],
+            ast::Statement::Try { catches, finally: Some(_), .. } if catches.is_empty()
+        ));
+        assert!(matches!(
+            &task.body.statements[3],
+            ast::Statement::Expr(ast::Expression::Raw(_))
+        ));
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains("        Catch e: ToolError\n          Block\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("  } catch (e: ToolError) {\n    log(e.message)\n  } catch (e) {\n"),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+    }
+
+    #[test]
+    fn parses_assert_statements() {
+        let src = r#"
+test "sum works" {
+  assert add(2, 3) == 5
+  assert
+  let assertion = true
+  assert(assertion)
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Test(test) = &module.items[0] else {
+            panic!("expected test");
+        };
+        let statements = &test.body.statements;
+        let ast::Statement::Assert(ast::Expression::Binary { op, .. }) = &statements[0] else {
+            panic!("expected assert, got {:?}", statements[0]);
+        };
+        assert_eq!(op, "==");
+        assert!(matches!(
+            &statements[1],
+            ast::Statement::Expr(ast::Expression::Identifier(name)) if name == "assert"
+        ));
+        assert!(matches!(&statements[2], ast::Statement::Let { name, .. } if name == "assertion"));
+        assert!(matches!(&statements[3], ast::Statement::Assert(_)));
+
+        let printed = printer::print_module(&module);
+        assert!(printed.contains("  assert add(2, 3) == 5\n"), "{printed}");
+        assert_eq!(
+            ast::dump(&parse_module(&printed).unwrap()),
+            ast::dump(&module)
+        );
+    }
+
+    #[test]
+    fn parses_throw_statements() {
+        let src = r#"
+task Validate(topic: String) -> String {
+  throw ValidationError("missing topic", field: "topic")
+  try { check(topic) } catch (e) { throw e }
+  throw
+  return topic
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Task(task) = &module.items[0] else {
+            panic!("expected task");
+        };
+        let ast::Statement::Throw(ast::Expression::Call { target, args }) =
+            &task.body.statements[0]
+        else {
+            panic!("expected throw, got {:?}", task.body.statements[0]);
+        };
+        assert_eq!(
+            **target,
+            ast::Expression::Identifier("ValidationError".into())
+        );
+        assert_eq!(args[1].name.as_deref(), Some("field"));
+        let ast::Statement::Try { catches, .. } = &task.body.statements[1] else {
+            panic!("expected try");
+        };
+        assert_eq!(
+            catches[0].body.statements,
+            [ast::Statement::Throw(ast::Expression::Identifier(
+                "e".into()
+            ))]
+        );
+        assert!(matches!(
+            &task.body.statements[2],
+            ast::Statement::Expr(ast::Expression::Raw(text)) if text == "throw"
+        ));
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains("      Throw\n        Call\n          Identifier ValidationError\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("  throw ValidationError(\"missing topic\", field: \"topic\")\n"),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+    }
+
+    #[test]
+    fn parses_guard_statements() {
+        let src = r#"
+task Brief(topic: String, hit: Hit?) -> String? {
+  guard topic != "" else { return None }
+  guard hit?.score ?? 0 > 5 else {
+    log("weak hit")
+    throw WeakHit(topic)
+  }
+  guard ready else
+  return topic
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Task(task) = &module.items[0] else {
+            panic!("expected task");
+        };
+        let ast::Statement::Guard {
+            condition,
+            else_body,
+        } = &task.body.statements[0]
+        else {
+            panic!("expected guard, got {:?}", task.body.statements[0]);
+        };
+        assert!(matches!(condition, ast::Expression::Binary { op, .. } if op == "!="));
+        assert_eq!(&src[else_body.span.clone()], "return None");
+        assert!(matches!(
+            &task.body.statements[1],
+            ast::Statement::Guard { else_body, .. } if else_body.statements.len() == 2
+        ));
+        assert!(!matches!(
+            &task.body.statements[2],
+            ast::Statement::Guard { .. }
+        ));
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains("      Guard\n        Binary !=\n          Identifier topic\n          Literal \"\"\n        Else\n          Block\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("  guard topic != \"\" else {\n    return None\n  }\n"),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+    }
+
+    #[test]
+    fn parses_tuple_types_and_literals() {
+        let src = r#"
+task Best(hits: List[(String, Int)], pair: Tuple[String, Float?]) -> (String, Int) {
+  let grouped: (Int) = (1 + 2)
+  let nested = ((a, b), [c], f(x, y))
+  return (top.title, top.score)
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let ast::Item::Task(task) = &module.items[0] else {
+            panic!("expected task");
+        };
+        let simple = |name: &str| ast::TypeExpr::Simple(vec![name.to_string()]);
+        assert_eq!(
+            task.return_type,
+            Some(ast::TypeExpr::Tuple(vec![simple("String"), simple("Int")]))
+        );
+        assert_eq!(
+            task.params[0].ty,
+            ast::TypeExpr::List(Box::new(ast::TypeExpr::Tuple(vec![
+                simple("String"),
+                simple("Int")
+            ])))
+        );
+        assert_eq!(
+            task.params[1].ty,
+            ast::TypeExpr::Tuple(vec![
+                simple("String"),
+                ast::TypeExpr::Optional(Box::new(simple("Float")))
+            ])
+        );
+        let ast::Statement::Let { ty, value, .. } = &task.body.statements[0] else {
+            panic!("expected let");
+        };
+        assert_eq!(ty, &Some(simple("Int")));
+        assert!(matches!(value, Some(ast::Expression::Binary { .. })));
+        let ast::Statement::Let {
+            value: Some(ast::Expression::Tuple(elements)),
+            ..
+        } = &task.body.statements[1]
+        else {
+            panic!("expected tuple, got {:?}", task.body.statements[1]);
+        };
+        assert!(matches!(&elements[0], ast::Expression::Tuple(inner) if inner.len() == 2));
+        assert!(matches!(&elements[2], ast::Expression::Call { .. }));
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains("  Task Best -> (String, Int)\n    Param hits: List[(String, Int)]\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains("pair: (String, Float?)) -> (String, Int) {\n"),
+            "{printed}"
+        );
+        assert!(
+            printed.contains("  return (top.title, top.score)\n"),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+    }
+
+    #[test]
+    fn parses_generic_type_parameters_with_bounds() {
+        let src = r#"
+record Page<T: Serializable, K: Comparable[K] + Hashable> {
+  items: List[T]
+}
+
+interface Store {
+  task load<T: Serializable>(key: String) -> T?
+}
+
+task First<T>(items: List[T]) -> T? {
+  return items[0]
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let simple = |name: &str| ast::TypeExpr::Simple(vec![name.to_string()]);
+        let ast::Item::Record(record) = &module.items[0] else {
+            panic!("expected record");
+        };
+        assert_eq!(
+            record.type_params,
+            vec![
+                ast::TypeParam {
+                    name: "T".to_string(),
+                    bounds: vec![simple("Serializable")],
+                },
+                ast::TypeParam {
+                    name: "K".to_string(),
+                    bounds: vec![
+                        ast::TypeExpr::Generic {
+                            base: vec!["Comparable".to_string()],
+                            arguments: vec![simple("K")],
+                        },
+                        simple("Hashable"),
+                    ],
+                },
+            ]
+        );
+        let ast::Item::Interface(store) = &module.items[1] else {
+            panic!("expected interface");
+        };
+        assert_eq!(
+            store.methods[0].type_params[0].bounds,
+            vec![simple("Serializable")]
+        );
+        let ast::Item::Task(task) = &module.items[2] else {
+            panic!("expected task");
+        };
+        assert_eq!(task.name, "First");
+        assert_eq!(
+            task.type_params,
+            vec![ast::TypeParam {
+                name: "T".to_string(),
+                bounds: Vec::new(),
+            }]
+        );
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains(
+                "  Record Page<T: Serializable, K: Comparable[K] + Hashable>
+"
+            ),
+            "{dumped}"
+        );
+        assert!(
+            dumped.contains(
+                "  Task First<T> -> T?
+"
+            ),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains(
+                "  task load<T: Serializable>(key: String) -> T?
+"
+            ),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+    }
+
+    #[test]
+    fn parses_where_clauses() {
+        let src = r#"
+record Cache<K, V> extends Store where K: Hashable, V: Serializable + Clone {
+  entries: Map[K, V]
+}
+
+task Merge<A, B>(left: A, right: B) -> A where A: Mergeable[B] {
+  return left.merge(right)
+}
+
+task Keep<T>(value: T)
+  where T: Serializable
+{
+  return value
+}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let simple = |name: &str| ast::TypeExpr::Simple(vec![name.to_string()]);
+        let ast::Item::Record(record) = &module.items[0] else {
+            panic!("expected record");
+        };
+        assert_eq!(record.extends, Some(vec!["Store".to_string()]));
+        assert_eq!(record.constraints.len(), 2);
+        assert_eq!(record.constraints[1].name, "V");
+        assert_eq!(
+            record.constraints[1].bounds,
+            vec![simple("Serializable"), simple("Clone")]
+        );
+        let ast::Item::Task(merge) = &module.items[1] else {
+            panic!("expected task");
+        };
+        assert_eq!(merge.return_type, Some(simple("A")));
+        assert_eq!(
+            merge.constraints,
+            vec![ast::TypeParam {
+                name: "A".to_string(),
+                bounds: vec![ast::TypeExpr::Generic {
+                    base: vec!["Mergeable".to_string()],
+                    arguments: vec![simple("B")],
+                }],
+            }]
+        );
+        let ast::Item::Task(keep) = &module.items[2] else {
+            panic!("expected task");
+        };
+        assert_eq!(keep.return_type, None);
+        assert_eq!(keep.constraints[0].bounds, vec![simple("Serializable")]);
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains("  Task Merge<A, B> -> A\n    Param left: A\n    Param right: B\n    Where A: Mergeable[B]\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.contains(
+                "record Cache<K, V> extends Store where K: Hashable, V: Serializable + Clone {\n"
+            ),
+            "{printed}"
+        );
+        assert!(
+            printed.contains("task Keep<T>(value: T) where T: Serializable {\n"),
+            "{printed}"
+        );
+        let reparsed = parse_module(&printed).expect("printed module parses");
+        assert_eq!(ast::dump(&reparsed), dumped);
+    }
+
+    #[test]
+    fn visibility_modifiers_hide_internal_items_from_importers() {
+        let parse = |src: &str| parse_module(src).expect("module parses");
+        let lib = parse(
+            r#"module lib.text
+
+pub record Page {
+  body: String
+}
+
+internal task normalize(text: String) -> String {
+  return text
+}
+
+@retry(max: 2)
+pub async task clean(text: String) -> String {
+  return normalize(text)
+}
+
+internal workflow Warmup {
+  clean("")
+}
+"#,
+        );
+        let visibilities: Vec<ast::Visibility> =
+            lib.items.iter().map(ast::Item::visibility).collect();
+        assert_eq!(
+            visibilities,
+            vec![
+                ast::Visibility::Public,
+                ast::Visibility::Internal,
+                ast::Visibility::Public,
+                ast::Visibility::Internal,
+            ]
+        );
+        let ast::Item::Task(clean) = &lib.items[2] else {
+            panic!("expected task");
+        };
+        assert!(clean.is_async);
+        assert_eq!(clean.annotations[0].name, "retry");
+
+        let dumped = ast::dump(&lib);
+        assert!(dumped.contains("  Record pub Page\n"), "{dumped}");
+        assert!(
+            dumped.contains("  Task pub async clean -> String\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&lib);
+        assert!(
+            printed.contains("@retry(max: 2)\npub async task clean(text: String) -> String {\n"),
+            "{printed}"
+        );
+        assert!(
+            printed.contains("internal workflow Warmup {\n"),
+            "{printed}"
+        );
+        assert_eq!(ast::dump(&parse(&printed)), dumped);
+
+        let public =
+            parse("module app\nimport lib.text { clean }\n\ntask Main() {\n  clean(\"x\")\n}\n");
+        let bundled = bundle::bundle(
+            &public,
+            std::slice::from_ref(&lib),
+            &bundle::BundleOptions::default(),
+        )
+        .expect("public items import");
+        assert_eq!(bundled.items.len(), 5);
+
+        let private = parse(
+            "module app\nimport lib.text { normalize }\n\ntask Main() {\n  normalize(\"x\")\n}\n",
+        );
+        let result = bundle::bundle(
+            &private,
+            std::slice::from_ref(&lib),
+            &bundle::BundleOptions::default(),
+        );
+        assert!(matches!(
+            result,
+            Err(HiloParseError::InternalImport { module, name })
+                if module == "lib.text" && name == "normalize"
+        ));
+
+        let exported: Vec<String> = compat::diff(&lib, &parse("module lib.text\n"))
+            .changes
+            .into_iter()
+            .map(|change| change.path)
+            .collect();
+        assert_eq!(exported, vec!["Page", "clean"]);
+    }
+
+    #[test]
+    fn export_lists_control_the_public_surface() {
+        let parse = |src: &str| parse_module(src).expect("module parses");
+        let lib = parse(
+            r#"module lib.brief
+
+record Brief {
+  title: String
+}
+
+task ProduceBrief(topic: String) -> Brief {
+  return Brief { title: draft(topic) }
+}
+
+task draft(topic: String) -> String {
+  return topic
+}
+
+pub task Version() -> String {
+  return "1"
+}
+
+export { ProduceBrief, Brief }
+"#,
+        );
+        let ast::Item::Export(export) = &lib.items[4] else {
+            panic!("expected export list, got {:?}", lib.items[4]);
+        };
+        assert_eq!(export.names, vec!["ProduceBrief", "Brief"]);
+        let dumped = ast::dump(&lib);
+        assert!(
+            dumped.contains("  Export ProduceBrief, Brief\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&lib);
+        assert!(
+            printed.contains("export { ProduceBrief, Brief }\n"),
+            "{printed}"
+        );
+        assert_eq!(ast::dump(&parse(&printed)), dumped);
+
+        let surface: Vec<String> = compat::diff(&lib, &parse("module lib.brief\n"))
+            .changes
+            .into_iter()
+            .map(|change| change.path)
+            .collect();
+        assert_eq!(surface, vec!["Brief", "ProduceBrief", "Version"]);
+
+        let app =
+            parse("module app\nimport lib.brief { draft }\n\ntask Main() {\n  draft(\"x\")\n}\n");
+        let result = bundle::bundle(
+            &app,
+            std::slice::from_ref(&lib),
+            &bundle::BundleOptions::default(),
+        );
+        assert!(matches!(
+            result,
+            Err(HiloParseError::InternalImport { name, .. }) if name == "draft"
+        ));
+
+        let checked =
+            parse("module lib\n\ninternal task hidden() {}\n\nexport { hidden, missing }\n");
+        let rules: Vec<&str> = semantic::check_exports(&checked)
+            .iter()
+            .map(|diagnostic| diagnostic.rule)
+            .collect();
+        assert_eq!(
+            rules,
+            vec!["exports::internal-item", "exports::unknown-item"]
+        );
+    }
+
+    #[test]
+    fn reexports_forward_symbols_through_library_modules() {
+        let parse = |src: &str| parse_module(src).expect("module parses");
+        let prelude = parse(
+            r#"module lib.prelude
+
+export lib.text { clean }
+export * from lib.brief
+export core.text { trim }
+"#,
+        );
+        assert_eq!(
+            prelude.items[0],
+            ast::Item::ReExport(ast::ReExportDecl {
+                path: vec!["lib".to_string(), "text".to_string()],
+                members: Some(vec!["clean".to_string()]),
+                span: 20..45,
+            })
+        );
+        assert!(matches!(
+            &prelude.items[1],
+            ast::Item::ReExport(decl) if decl.members.is_none() && decl.path.join(".") == "lib.brief"
+        ));
+        let dumped = ast::dump(&prelude);
+        assert!(
+            dumped.contains("  ReExport lib.text { clean }\n  ReExport * from lib.brief\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&prelude);
+        assert!(printed.contains("export * from lib.brief\n"), "{printed}");
+        assert_eq!(ast::dump(&parse(&printed)), dumped);
+
+        let modules = [
+            prelude,
+            parse("module lib.text\n\ntask clean(text: String) -> String {\n  return text\n}\n"),
+            parse(
+                "module lib.brief\n\nrecord Brief {\n  title: String\n}\n\ninternal task draft() {}\n",
+            ),
+        ];
+        let app = parse(
+            r#"module app
+import lib.prelude { clean, Brief, trim }
+
+task Main(topic: String) -> Brief {
+  return Brief { title: trim(clean(topic)) }
+}
+"#,
+        );
+        let bundled = bundle::bundle(&app, &modules, &bundle::BundleOptions::default())
+            .expect("re-exported names resolve");
+        assert_eq!(bundled.imports.len(), 1);
+        assert_eq!(bundled.imports[0].path.join("."), "core.text");
+        assert_eq!(bundled.imports[0].members, Some(vec!["trim".to_string()]));
+        let names: Vec<&str> = bundled
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ast::Item::Record(decl) => Some(decl.name.as_str()),
+                ast::Item::Task(decl) => Some(decl.name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, vec!["clean", "Brief", "draft", "Main"]);
+
+        let mut forwarding_internal = modules.clone();
+        forwarding_internal[0] = parse("module lib.prelude\nexport lib.brief { draft }\n");
+        assert!(matches!(
+            bundle::bundle(&app, &forwarding_internal, &bundle::BundleOptions::default()),
+            Err(HiloParseError::InternalImport { module, name })
+                if module == "lib.brief" && name == "draft"
+        ));
+
+        let report = compat::diff(
+            &modules[0],
+            &parse("module lib.prelude\n\nexport lib.text { clean }\n"),
+        );
+        let changes: Vec<(String, String)> = report
+            .changes
+            .into_iter()
+            .map(|change| (change.path, change.message))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("lib.brief.*".to_string(), "re-export removed".to_string()),
+                (
+                    "core.text.trim".to_string(),
+                    "re-export removed".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn relative_imports_keep_their_leading_segments() {
+        let src = r#"module app.main
+import ./util.slugify
+import ../../shared.types { Brief } as T
+import core.text
+
+task Main() {}
+"#;
+        let module = parse_module(src).expect("module parses");
+        let paths: Vec<&[String]> = module
+            .imports
+            .iter()
+            .map(|import| import.path.as_slice())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                &[".", "util", "slugify"][..],
+                &["..", "..", "shared", "types"][..],
+                &["core", "text"][..],
+            ]
+        );
+        assert!(module.imports[1].is_relative());
+        assert!(!module.imports[2].is_relative());
+        assert_eq!(module.imports[1].path_text(), "../../shared.types");
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains("  Import ../../shared.types { Brief } as T\n"),
+            "{dumped}"
+        );
+        let printed = printer::print_module(&module);
+        assert!(printed.contains("import ./util.slugify\n"), "{printed}");
+        assert_eq!(ast::dump(&parse_module(&printed).unwrap()), dumped);
+
+        let bundled = bundle::bundle(&module, &[], &bundle::BundleOptions::default());
+        assert!(matches!(
+            bundled,
+            Err(HiloParseError::UnresolvedImport(path)) if path == "./util.slugify"
+        ));
+    }
+
+    #[test]
+    fn parses_versioned_imports() {
+        let src = "module demo\nimport vendor.summarizer @ \"1.2\" { summarize } as S\nimport core.text\n";
+        let module = parse_module(src).expect("versioned import parses");
+        assert_eq!(module.imports[0].version.as_deref(), Some("1.2"));
+        assert_eq!(
+            module.imports[0].members,
+            Some(vec!["summarize".to_string()])
+        );
+        assert_eq!(module.imports[0].alias.as_deref(), Some("S"));
+        assert_eq!(module.imports[1].version, None);
+
+        let mut module = module;
+        transform::organize_imports(&mut module);
+        let rendered = transform::render_imports(&module.imports);
+        assert!(rendered.contains("import vendor.summarizer @ \"1.2\" { summarize } as S\n"));
+        assert_eq!(parse_module(&rendered).unwrap().imports, module.imports);
+    }
+
+    #[test]
+    fn module_doc_comments_are_captured() {
+        let src = "// licence header\n/// Research helpers.\n///\n/// Shared by every agent.\nmodule agents.research\n\ntask Run() {}\n";
+        let module = parse_module(src).expect("documented module parses");
+        assert_eq!(
+            module.docs.as_deref(),
+            Some("Research helpers.\n\nShared by every agent.")
+        );
+        assert!(
+            ast::dump(&module)
+                .contains("  Docs \"Research helpers.\\n\\nShared by every agent.\"\n")
+        );
+
+        let printed = printer::print_module(&module);
+        assert!(
+            printed.starts_with("/// Research helpers.\n///\n/// Shared by every agent.\nmodule")
+        );
+        assert_eq!(parse_module(&printed).unwrap().docs, module.docs);
+
+        let undeclared = parse_module("/// Just a comment.\ntask Run() {}\n").unwrap();
+        assert_eq!(undeclared.docs, None);
+    }
+
+    #[test]
+    fn doc_comments_attach_to_declarations() {
+        let src = r#"
+module demo
+
+/// A fetched page.
+record Page {
+  /// Where the page lives.
+  url: String
+  // not documentation
+  title?: String
+
+  /// Short form of the page.
+  fn summary() -> String { return url }
+}
+
+/// Research a topic.
+/// Returns a brief.
+task Research(
+  /// What to look into, in a few words.
+  topic: String,
+  depth: Int = 2,
+) -> String {
+  return topic
+}
+
+/// Nightly refresh.
+workflow Nightly {}
+"#;
+        let module = parse_module(src).expect("documented items parse");
+        let ast::Item::Record(page) = &module.items[0] else {
+            panic!("expected a record, got {:?}", module.items[0]);
+        };
+        assert_eq!(page.docs.as_deref(), Some("A fetched page."));
+        assert_eq!(
+            page.fields[0].docs.as_deref(),
+            Some("Where the page lives.")
+        );
+        assert_eq!(page.fields[1].docs, None);
+        assert_eq!(
+            page.methods[0].docs.as_deref(),
+            Some("Short form of the page.")
+        );
+        let ast::Item::Task(research) = &module.items[1] else {
+            panic!("expected a task, got {:?}", module.items[1]);
+        };
+        assert_eq!(
+            research.docs.as_deref(),
+            Some("Research a topic.\nReturns a brief.")
+        );
+        assert_eq!(
+            research.params[0].docs.as_deref(),
+            Some("What to look into, in a few words.")
+        );
+        assert_eq!(research.params[1].docs, None);
+        assert_eq!(&src[research.span.clone()][..13], "task Research");
+        let ast::Item::Workflow(nightly) = &module.items[2] else {
+            panic!("expected a workflow, got {:?}", module.items[2]);
+        };
+        assert_eq!(nightly.docs.as_deref(), Some("Nightly refresh."));
+
+        let schema = schema::tool_schema(&module, research);
+        assert_eq!(schema["description"], "Research a topic.\nReturns a brief.");
+        assert_eq!(
+            schema["parameters"]["properties"]["topic"]["description"],
+            "What to look into, in a few words."
+        );
+
+        let printed = printer::print_module(&module);
+        assert_eq!(
+            ast::dump(&parse_module(&printed).unwrap()),
+            ast::dump(&module)
+        );
+
+        let parsed = ParsedModule::parse(src, &ParseOptions::default()).unwrap();
+        let at = src.find("Nightly refresh").unwrap();
+        let edit = TextEdit {
+            range: at..at + "Nightly".len(),
+            text: "Weekly".to_string(),
+        };
+        let next = reparse(&parsed, &edit).expect("reparse");
+        assert!(ast::dump(&next.module).contains("Docs \"Weekly refresh.\""));
+    }
+
+    #[test]
+    fn nested_modules_scope_their_items() {
+        let src = r#"
+module app
+
+/// String helpers.
+module helpers {
+  task Slugify(title: String) -> String {
+    return title
+  }
+
+  module inner {
+    record Slug {
+      text: String
+    }
+  }
+}
+
+task Publish(title: String) -> String {
+  return helpers.Slugify(title)
+}
+"#;
+        let module = parse_module(src).expect("nested modules parse");
+        assert_eq!(module.name, Some(vec!["app".to_string()]));
+        let ast::Item::Module(helpers) = &module.items[0] else {
+            panic!("expected a nested module, got {:?}", module.items[0]);
+        };
+        assert_eq!(helpers.name, Some(vec!["helpers".to_string()]));
+        assert_eq!(helpers.docs.as_deref(), Some("String helpers."));
+        assert!(src[helpers.span.clone()].starts_with("module helpers {"));
+        assert!(matches!(&helpers.items[0], ast::Item::Task(task) if task.name == "Slugify"));
+        assert!(matches!(&helpers.items[1], ast::Item::Module(inner) if inner.items.len() == 1));
+        assert!(matches!(&module.items[1], ast::Item::Task(task) if task.name == "Publish"));
+
+        let dumped = ast::dump(&module);
+        assert!(
+            dumped.contains("  Module helpers\n    Docs \"String helpers.\"\n    Task Slugify")
+        );
+        assert!(dumped.contains("    Module inner\n      Record Slug\n"));
+
+        let symbols = ide::document_symbols(&module);
+        assert_eq!(symbols[0].kind, ide::SymbolKind::Module);
+        assert_eq!(symbols[0].children[1].children[0].name, "Slug");
+
+        let printed = printer::print_module(&module);
+        assert!(printed.contains("module helpers {\n  task Slugify(title: String) -> String {\n"));
+        assert_eq!(ast::dump(&parse_module(&printed).unwrap()), dumped);
+
+        let headless = parse_module("module util {\n  task Trim(s: String) {}\n}\n").unwrap();
+        assert_eq!(headless.name, None);
+        assert!(matches!(&headless.items[0], ast::Item::Module(util) if util.items.len() == 1));
+    }
+
+    #[test]
+    fn workflow_steps_are_structured() {
+        let src = r#"
+workflow Research {
+  let topic = "rust"
+  step fetch(topic) {
+    return topic
+  }
+  step summarize(fetch, topic) {
+    return fetch
+  }
+  step publish {}
+}
+"#;
+        let module = parse_module(src).expect("workflow steps parse");
+        let ast::Item::Workflow(workflow) = &module.items[0] else {
+            panic!("expected a workflow, got {:?}", module.items[0]);
+        };
+        let steps: Vec<_> = workflow
+            .steps
+            .iter()
+            .map(|step| (step.name.as_str(), step.inputs.join(",")))
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                ("fetch", "topic".to_string()),
+                ("summarize", "fetch,topic".to_string()),
+                ("publish", String::new())
+            ]
+        );
+        assert_eq!(workflow.body.statements.len(), 1);
+        assert_eq!(&src[workflow.steps[2].span.clone()], "step publish {}");
+        assert!(
+            ast::dump(&module)
+                .contains("    Step summarize(fetch, topic)\n      Block\n        Return\n")
+        );
+
+        let symbols = ide::document_symbols(&module);
+        let names: Vec<_> = symbols[0]
+            .children
+            .iter()
+            .map(|step| step.name.as_str())
+            .collect();
+        assert_eq!(names, ["fetch", "summarize", "publish"]);
+
+        let printed = printer::print_module(&module);
+        assert_eq!(
+            ast::dump(&parse_module(&printed).unwrap()),
+            ast::dump(&module)
+        );
+
+        let lazy = ParseOptions {
+            lazy_bodies: true,
+            ..ParseOptions::default()
+        };
+        let module = parse_module_with(src, &lazy).unwrap();
+        assert!(
+            matches!(&module.items[0], ast::Item::Workflow(workflow) if workflow.steps.len() == 3)
+        );
+    }
+
+    #[test]
+    fn parallel_blocks_split_into_branches() {
+        let src = r#"
+workflow Survey {
+  parallel {
+    search("rust")
+    @retry(max: 2)
+    search("zig")
+    {
+      let pages = fetch("https://example.com")
+      summarize(pages)
+    }
+  }
+}
+"#;
+        let module = parse_module(src).expect("parallel block parses");
+        let ast::Item::Workflow(workflow) = &module.items[0] else {
+            panic!("expected a workflow, got {:?}", module.items[0]);
+        };
+        let [ast::Statement::Parallel(branches)] = workflow.body.statements.as_slice() else {
+            panic!(
+                "expected a parallel block, got {:?}",
+                workflow.body.statements
+            );
+        };
+        let sizes: Vec<_> = branches
+            .iter()
+            .map(|branch| branch.statements.len())
+            .collect();
+        assert_eq!(sizes, [1, 1, 2]);
+        assert!(matches!(
+            &branches[1].statements[0],
+            ast::Statement::Annotated { .. }
+        ));
+        assert_eq!(branches[0].raw, "search(\"rust\")");
+        assert_eq!(&src[branches[2].span.clone()], branches[2].raw);
+
+        let dumped = ast::dump(&module);
+        assert!(dumped.contains("      Parallel\n        Block\n          Call\n"));
+        let printed = printer::print_module(&module);
+        assert_eq!(ast::dump(&parse_module(&printed).unwrap()), dumped);
+    }
+
+    #[test]
+    fn branch_statements_route_between_steps() {
+        let src = r#"
+workflow Review {
+  step check {
+    return "ok"
+  }
+  branch on result.status {
+    "ok" => publish,
+    Err(reason) => escalate
+    else => retry
+  }
+  step publish {}
+  step retry {}
+}
+"#;
+        let module = parse_module(src).expect("branch parses");
+        let ast::Item::Workflow(workflow) = &module.items[0] else {
+            panic!("expected a workflow, got {:?}", module.items[0]);
+        };
+        let [
+            ast::Statement::Branch {
+                subject,
+                arms,
+                otherwise,
+            },
+        ] = workflow.body.statements.as_slice()
+        else {
+            panic!("expected a branch, got {:?}", workflow.body.statements);
+        };
+        assert!(matches!(subject, ast::Expression::Member { .. }));
+        assert_eq!(
+            arms[0].pattern,
+            ast::Pattern::Literal(ast::Literal::Str("ok".to_string()))
+        );
+        assert_eq!(arms[1].target, "escalate");
+        assert_eq!(otherwise.as_deref(), Some("retry"));
+
+        let printed = printer::print_module(&module);
+        assert_eq!(
+            ast::dump(&parse_module(&printed).unwrap()),
+            ast::dump(&module)
+        );
+
+        let diagnostics = semantic::check_workflows(&module);
+        let messages: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.rule, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            [(
+                "workflows::unknown-step",
+                "workflow `Review` has no step `escalate`"
+            )]
+        );
+    }
+
+    #[test]
+    fn on_error_clauses_attach_to_workflows_and_steps() {
+        let src = "module demo\n\nworkflow Publish {\n  step fetch {\n    let page = load()\n    on_error(err) {\n      log(err)\n    }\n  }\n  step send(fetch) {}\n  on_error {\n    rollback()\n  }\n}\n";
+        let module = parse_module(src).expect("on_error clauses parse");
+        let ast::Item::Workflow(workflow) = &module.items[0] else {
+            panic!("expected a workflow");
+        };
+        let handler = workflow.on_error.as_ref().expect("workflow handler");
+        assert_eq!(handler.binding, None);
+        assert_eq!(
+            &src[handler.span.clone()],
+            "on_error {\n    rollback()\n  }"
+        );
+        assert!(workflow.body.statements.is_empty());
+
+        let fetch = &workflow.steps[0];
+        let handler = fetch.on_error.as_ref().expect("step handler");
+        assert_eq!(handler.binding.as_deref(), Some("err"));
+        assert_eq!(handler.body.statements.len(), 1);
+        assert_eq!(fetch.body.statements.len(), 1);
+        assert!(workflow.steps[1].on_error.is_none());
+
+        let printed = printer::print_module(&module);
+        assert_eq!(
+            ast::dump(&parse_module(&printed).unwrap()),
+            ast::dump(&module)
+        );
+    }
+
+    #[test]
+    fn retry_clauses_parse_into_policies() {
+        use std::time::Duration;
+
+        let src = "module demo\n\ntask Fetch<T>(url: String) -> Page where T: Hashable retry(max: 3, backoff: exponential(2s)) {\n  return load(url)\n}\n\ntask Ping() retry(backoff: fixed(500ms)) {}\n\nworkflow Publish {\n  step send retry(max: 2) {}\n}\n";
+        let module = parse_module(src).expect("retry clauses parse");
+        let ast::Item::Task(fetch) = &module.items[0] else {
+            panic!("expected a task, got {:?}", module.items[0]);
+        };
+        let retry = fetch.retry.as_ref().expect("task retry");
+        assert_eq!(retry.max, Some(3));
+        assert_eq!(
+            retry.backoff,
+            Some(ast::Backoff::Exponential(Duration::from_secs(2)))
+        );
+        assert_eq!(
+            &src[retry.span.clone()],
+            "retry(max: 3, backoff: exponential(2s))"
+        );
+        assert_eq!(fetch.constraints.len(), 1);
+        assert!(
+            matches!(&fetch.return_type, Some(ast::TypeExpr::Simple(name)) if name == &["Page"])
+        );
+
+        let ast::Item::Task(ping) = &module.items[1] else {
+            panic!("expected a task");
+        };
+        let retry = ping.retry.as_ref().expect("task retry");
+        assert_eq!(retry.max, None);
+        assert_eq!(
+            retry.backoff,
+            Some(ast::Backoff::Fixed(Duration::from_millis(500)))
+        );
+
+        let ast::Item::Workflow(workflow) = &module.items[2] else {
+            panic!("expected a workflow");
+        };
+        assert_eq!(
+            workflow.steps[0].retry.as_ref().and_then(|retry| retry.max),
+            Some(2)
+        );
+
+        let printed = printer::print_module(&module);
+        assert!(printed.contains("task Ping() retry(backoff: fixed(500ms)) {}"));
+        assert_eq!(
+            ast::dump(&parse_module(&printed).unwrap()),
+            ast::dump(&module)
+        );
+
+        let retuned = parse_module(&src.replace("max: 3", "max: 5")).expect("module parses");
+        let changes: Vec<_> = compat::diff(&module, &retuned)
+            .changes
+            .into_iter()
+            .map(|change| (change.kind, change.path, change.message))
+            .collect();
+        assert_eq!(
+            changes,
+            [(
+                compat::ChangeKind::Internal,
+                "Fetch".to_string(),
+                "retry policy changed".to_string()
+            )]
+        );
+
+        let unknown = "module demo\n\ntask Ping() retry(tries: 3) {}\n";
+        assert!(!matches!(
+            parse_module(unknown).map(|module| module.items),
+            Ok(items) if matches!(items.as_slice(), [ast::Item::Task(_)])
+        ));
+    }
+
+    #[test]
+    fn timeout_clauses_take_duration_literals() {
+        use std::time::Duration;
+
+        let src = "module demo\n\ntask Fetch(url: String) -> Page retry(max: 2) timeout 45s {\n  return load(url)\n}\n\ntask Ping() timeout 5m {}\n\nworkflow Publish {\n  step send(Fetch) timeout 2h {}\n}\n";
+        let module = parse_module(src).expect("timeout clauses parse");
+        let timeouts: Vec<_> = module
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ast::Item::Task(decl) => Some(decl.timeout),
+                ast::Item::Workflow(decl) => Some(decl.steps[0].timeout),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            timeouts,
+            [
+                Some(Duration::from_secs(45)),
+                Some(Duration::from_secs(300)),
+                Some(Duration::from_secs(7_200)),
+            ]
+        );
+        let ast::Item::Task(fetch) = &module.items[0] else {
+            panic!("expected a task");
+        };
+        assert!(fetch.retry.is_some());
+        assert!(
+            matches!(&fetch.return_type, Some(ast::TypeExpr::Simple(name)) if name == &["Page"])
+        );
+
+        let untimed = parse_module(&src.replace(" timeout 5m", "")).expect("module parses");
+        let changes: Vec<_> = compat::diff(&module, &untimed)
+            .changes
+            .into_iter()
+            .map(|change| (change.kind, change.path, change.message))
+            .collect();
+        assert_eq!(
+            changes,
+            [(
+                compat::ChangeKind::Internal,
+                "Ping".to_string(),
+                "timeout changed from 5m to none".to_string()
+            )]
+        );
+
+        let printed = printer::print_module(&module);
+        assert!(printed.contains("task Ping() timeout 5m {}"));
+        assert_eq!(
+            ast::dump(&parse_module(&printed).unwrap()),
+            ast::dump(&module)
+        );
+    }
+
+    #[test]
+    fn trigger_declarations_schedule_workflows() {
+        let src = "module demo\n\nimport jobs { Cleanup }\n\nworkflow Main {}\n\ntrigger Daily { cron: \"0 9 * * *\", run: Main }\n\ntrigger Nightly {\n  cron: \"0 2 * *\"\n  run: Cleanup\n}\n\ntrigger Broken { cron: \"* * * * *\", run: Missing }\n";
+        let module = parse_module(src).expect("triggers parse");
+        let ast::Item::Trigger(daily) = &module.items[1] else {
+            panic!("expected a trigger, got {:?}", module.items[1]);
+        };
+        assert_eq!(daily.name, "Daily");
+        assert_eq!(daily.cron, "0 9 * * *");
+        assert_eq!(daily.run, ["Main"]);
+        assert_eq!(
+            &src[daily.span.clone()],
+            "trigger Daily { cron: \"0 9 * * *\", run: Main }"
+        );
+
+        let diagnostics = semantic::check_triggers(&module);
+        let rules: Vec<_> = diagnostics.iter().map(|d| d.rule).collect();
+        assert_eq!(
+            rules,
+            ["triggers::invalid-cron", "triggers::unknown-target"]
+        );
+        assert!(diagnostics[1].message.contains("`Missing`"));
+
+        let printed = printer::print_module(&module);
+        assert_eq!(
+            ast::dump(&parse_module(&printed).unwrap()),
+            ast::dump(&module)
+        );
+        let missing_run = "module demo\n\ntrigger Daily { cron: \"0 9 * * *\" }\n";
+        assert!(matches!(
+            parse_module(missing_run).unwrap().items.as_slice(),
+            [ast::Item::Other(_)]
+        ));
+    }
+
+    #[test]
+    fn event_handlers_capture_pattern_binding_and_body() {
+        let src = "module demo\n\non event \"document.uploaded\" (doc) {\n  Index(doc)\n}\n\non event \"billing.*\" {}\n\ntask Index(doc: Document) {}\n";
+        let module = parse_module(src).expect("event handlers parse");
+        let ast::Item::EventHandler(uploaded) = &module.items[0] else {
+            panic!("expected an event handler, got {:?}", module.items[0]);
+        };
+        assert_eq!(uploaded.pattern, "document.uploaded");
+        assert_eq!(uploaded.binding.as_deref(), Some("doc"));
+        assert_eq!(uploaded.body.statements.len(), 1);
+        assert!(src[uploaded.span.clone()].ends_with("Index(doc)\n}"));
+
+        let ast::Item::EventHandler(billing) = &module.items[1] else {
+            panic!("expected an event handler");
+        };
+        assert_eq!(billing.pattern, "billing.*");
+        assert_eq!(billing.binding, None);
+        assert!(matches!(module.items[2], ast::Item::Task(_)));
+
+        let symbols = ide::document_symbols(&module);
+        assert_eq!(symbols[0].kind, ide::SymbolKind::EventHandler);
+        assert_eq!(symbols[0].name, "document.uploaded");
+
+        let printed = printer::print_module(&module);
+        assert!(printed.contains("on event \"billing.*\" {}"));
+        assert_eq!(
+            ast::dump(&parse_module(&printed).unwrap()),
+            ast::dump(&module)
+        );
+    }
+
+    #[test]
+    fn config_blocks_hold_key_expression_pairs() {
+        let src = "module demo\n\nconfig {\n  default_model: \"gpt-4o\", region: env(\"HILO_REGION\")\n  retries: 3\n}\n\ntask Run() {}\n";
+        let module = parse_module(src).expect("config block parses");
+        let ast::Item::Config(config) = &module.items[0] else {
+            panic!("expected a config block, got {:?}", module.items[0]);
+        };
+        let keys: Vec<_> = config
+            .entries
+            .iter()
+            .map(|entry| entry.key.as_str())
+            .collect();
+        assert_eq!(keys, ["default_model", "region", "retries"]);
+        assert_eq!(
+            config.entries[0].value,
+            ast::Expression::Literal(ast::Literal::Str("gpt-4o".to_string()))
+        );
+        assert!(matches!(
+            &config.entries[1].value,
+            ast::Expression::Call { .. }
+        ));
+        assert_eq!(
+            &src[config.entries[1].span.clone()],
+            "region: env(\"HILO_REGION\")"
+        );
+        assert!(matches!(module.items[1], ast::Item::Task(_)));
+
+        let printed = printer::print_module(&module);
+        assert_eq!(
+            ast::dump(&parse_module(&printed).unwrap()),
+            ast::dump(&module)
+        );
+    }
+
+    #[test]
+    fn secret_declarations_are_explicit_items() {
+        let src = "module demo\n\nsecret OPENAI_API_KEY\nsecret db_url from vault(\"prod/db\");\n\nmodule store {\n  secret token from env(\"TOKEN\")\n}\n\ntask Run() {}\n";
+        let module = parse_module(src).expect("secrets parse");
+        let ast::Item::Secret(key) = &module.items[0] else {
+            panic!("expected a secret, got {:?}", module.items[0]);
+        };
+        assert_eq!(key.name, "OPENAI_API_KEY");
+        assert_eq!(key.source, None);
+        assert_eq!(&src[key.span.clone()], "secret OPENAI_API_KEY");
+        let ast::Item::Secret(db) = &module.items[1] else {
+            panic!("expected a secret");
+        };
+        assert!(matches!(&db.source, Some(ast::Expression::Call { .. })));
+        assert_eq!(
+            &src[db.span.clone()],
+            "secret db_url from vault(\"prod/db\")"
+        );
+        assert!(matches!(module.items[3], ast::Item::Task(_)));
+
+        let names: Vec<_> = semantic::secrets(&module)
+            .iter()
+            .map(|secret| secret.name.as_str())
+            .collect();
+        assert_eq!(names, ["OPENAI_API_KEY", "db_url", "token"]);
+
+        let printed = printer::print_module(&module);
+        assert!(printed.contains("secret db_url from vault(\"prod/db\")\n"));
+        assert_eq!(
+            ast::dump(&parse_module(&printed).unwrap()),
+            ast::dump(&module)
+        );
+    }
+
+    #[test]
+    fn float_literals_compare_by_bits() {
+        let nan = ast::Literal::Float(f64::NAN);
+        assert_eq!(nan, nan.clone());
+        assert_eq!(ast::Literal::Float(2.5), ast::Literal::Float(2.5));
+        assert_ne!(ast::Literal::Float(0.0), ast::Literal::Float(-0.0));
+        assert_ne!(ast::Literal::Float(1.0), ast::Literal::Int(1));
+    }
 }
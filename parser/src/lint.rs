@@ -0,0 +1,256 @@
+//! Style lints over parsed modules.
+//!
+//! Every diagnostic carries a span into the linted source and, where the fix
+//! is mechanical and safe, a replacement for that span.
+
+use crate::{ast, codemod};
+
+/// A style violation found by [`check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Identifier of the rule that fired, e.g. `naming::type-case`.
+    pub rule: &'static str,
+    pub message: String,
+    pub span: ast::Span,
+    pub fix: Option<Fix>,
+}
+
+/// Replace `span` with `replacement` to resolve a diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    pub span: ast::Span,
+    pub replacement: String,
+}
+
+/// Identifier casing conventions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Case {
+    /// `ProduceBrief`
+    Pascal,
+    /// `maxResults`
+    Camel,
+    /// `max_results`
+    Snake,
+    /// `MAX_RESULTS`
+    ScreamingSnake,
+}
+
+/// Which naming rules run and the casing each one expects; `None` disables a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintConfig {
+    /// Records, interfaces, tasks, workflows, hooks, and prompts.
+    pub type_case: Option<Case>,
+    /// Record fields and task parameters.
+    pub member_case: Option<Case>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            type_case: Some(Case::Pascal),
+            member_case: Some(Case::Camel),
+        }
+    }
+}
+
+/// Run the built-in naming rules over `module`, which was parsed from `source`.
+///
+/// Items of nested modules are checked too. A fix is offered only when the
+/// name occurs nowhere else in `source`, since renaming just the declaration
+/// would strand its uses; uses in other modules are not visible here.
+pub fn check(source: &str, module: &ast::Module, config: &LintConfig) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    check_items(source, &module.items, config, &mut diagnostics);
+    let tokens = codemod::tokens(source);
+    for diagnostic in &mut diagnostics {
+        let name = &source[diagnostic.span.clone()];
+        if tokens
+            .iter()
+            .any(|(span, text)| *text == name && *span != diagnostic.span)
+        {
+            diagnostic.fix = None;
+        }
+    }
+    diagnostics
+}
+
+fn check_items(
+    source: &str,
+    items: &[ast::Item],
+    config: &LintConfig,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for item in items {
+        let (kind, name, span) = match item {
+            ast::Item::Record(decl) => ("record", &decl.name, &decl.span),
+            ast::Item::Interface(decl) => ("interface", &decl.name, &decl.span),
+            ast::Item::Task(decl) => ("task", &decl.name, &decl.span),
+            ast::Item::Workflow(decl) => ("workflow", &decl.name, &decl.span),
+            ast::Item::Hook(decl) => ("hook", &decl.name, &decl.span),
+            ast::Item::Prompt(decl) => ("prompt", &decl.name, &decl.span),
+            ast::Item::Trigger(decl) => ("trigger", &decl.name, &decl.span),
+            ast::Item::Module(module) => {
+                check_items(source, &module.items, config, diagnostics);
+                continue;
+            }
+            _ => continue,
+        };
+        if let Some(case) = config.type_case
+            && let Some(name_span) = name_after_keyword(source, span, name)
+        {
+            check_name(
+                diagnostics,
+                "naming::type-case",
+                kind,
+                name,
+                name_span,
+                case,
+            );
+        }
+
+        let Some(case) = config.member_case else {
+            continue;
+        };
+        let members: Vec<(&str, &ast::Ident, &ast::Span)> = match item {
+            ast::Item::Record(decl) => decl
+                .fields
+                .iter()
+                .map(|field| ("field", &field.name, &field.span))
+                .chain(
+                    decl.methods
+                        .iter()
+                        .flat_map(|method| &method.params)
+                        .map(|param| ("parameter", &param.name, &param.span)),
+                )
+                .collect(),
+            ast::Item::Task(decl) => decl
+                .params
+                .iter()
+                .map(|param| ("parameter", &param.name, &param.span))
+                .collect(),
+            _ => Vec::new(),
+        };
+        for (kind, name, span) in members {
+            let name_span = span.start..span.start + name.len();
+            check_name(
+                diagnostics,
+                "naming::member-case",
+                kind,
+                name,
+                name_span,
+                case,
+            );
+        }
+    }
+}
+
+fn check_name(
+    diagnostics: &mut Vec<Diagnostic>,
+    rule: &'static str,
+    kind: &str,
+    name: &str,
+    span: ast::Span,
+    case: Case,
+) {
+    if is_case(name, case) {
+        return;
+    }
+    let expected = convert(name, case);
+    diagnostics.push(Diagnostic {
+        rule,
+        message: format!("{kind} `{name}` should be written `{expected}`"),
+        fix: (!expected.is_empty()).then(|| Fix {
+            span: span.clone(),
+            replacement: expected,
+        }),
+        span,
+    });
+}
+
+/// Locates `name` following the declaration keyword, and any `pub`,
+/// `internal`, or `async` modifiers before it, at the start of an item's span.
+fn name_after_keyword(source: &str, span: &ast::Span, name: &str) -> Option<ast::Span> {
+    let text = source.get(span.clone())?;
+    let mut header = text;
+    while let Some(rest) = ["pub", "internal", "async"].iter().find_map(|modifier| {
+        header
+            .strip_prefix(modifier)
+            .filter(|rest| rest.starts_with(char::is_whitespace))
+    }) {
+        header = rest.trim_start();
+    }
+    let keyword_end = header.find(char::is_whitespace)?;
+    let rest = header[keyword_end..].trim_start();
+    let offset = span.start + text.len() - rest.len();
+    source[offset..]
+        .starts_with(name)
+        .then(|| offset..offset + name.len())
+}
+
+/// Whether `name` already follows `case`; acronyms such as `HTTPClient` are accepted.
+pub fn is_case(name: &str, case: Case) -> bool {
+    let first = name.chars().next();
+    match case {
+        Case::Pascal => first.is_some_and(char::is_uppercase) && !name.contains('_'),
+        Case::Camel => first.is_some_and(char::is_lowercase) && !name.contains('_'),
+        Case::Snake => !name.chars().any(char::is_uppercase),
+        Case::ScreamingSnake => !name.chars().any(char::is_lowercase),
+    }
+}
+
+/// Rewrites `name` in `case`, splitting words at `_`, lower-to-upper
+/// transitions, and the end of an acronym (`HTTPStatus` is `HTTP`, `Status`).
+pub fn convert(name: &str, case: Case) -> String {
+    let words = words(name);
+    let mut out = String::with_capacity(name.len());
+    for (idx, word) in words.iter().enumerate() {
+        match case {
+            Case::Pascal => push_capitalized(&mut out, word),
+            Case::Camel if idx == 0 => out.push_str(&word.to_lowercase()),
+            Case::Camel => push_capitalized(&mut out, word),
+            Case::Snake | Case::ScreamingSnake => {
+                if idx > 0 {
+                    out.push('_');
+                }
+                if case == Case::Snake {
+                    out.push_str(&word.to_lowercase());
+                } else {
+                    out.push_str(&word.to_uppercase());
+                }
+            }
+        }
+    }
+    out
+}
+
+fn words(name: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    for part in name.split('_').filter(|part| !part.is_empty()) {
+        let mut start = 0;
+        let mut prev: Option<char> = None;
+        let mut chars = part.char_indices().peekable();
+        while let Some((idx, ch)) = chars.next() {
+            let next = chars.peek().map(|&(_, next)| next);
+            if let Some(prev) = prev
+                && ch.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_ascii_digit()
+                    || (prev.is_uppercase() && next.is_some_and(char::is_lowercase)))
+            {
+                words.push(&part[start..idx]);
+                start = idx;
+            }
+            prev = Some(ch);
+        }
+        words.push(&part[start..]);
+    }
+    words
+}
+
+fn push_capitalized(out: &mut String, word: &str) {
+    let mut chars = word.chars();
+    if let Some(first) = chars.next() {
+        out.extend(first.to_uppercase());
+        out.push_str(&chars.as_str().to_lowercase());
+    }
+}
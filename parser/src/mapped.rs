@@ -0,0 +1,30 @@
+//! Parsing straight from memory-mapped files.
+
+use std::fs::File;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::{ast, error::HiloParseError, options::ParseOptions, parser};
+
+/// Maps `path` read-only and parses it in place, avoiding a heap copy of the
+/// source. The AST still owns its strings.
+///
+/// The file must not be modified while it is being parsed; the mapping is only
+/// held for the duration of this call.
+pub(crate) fn parse_mmap(
+    path: &Path,
+    options: &ParseOptions,
+) -> Result<ast::Module, HiloParseError> {
+    let file = File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return parser::parse_module("", options);
+    }
+    // SAFETY: the map is read-only and dropped before returning; callers are
+    // documented not to truncate or rewrite the file while it is parsed.
+    let map = unsafe { Mmap::map(&file)? };
+    let source = std::str::from_utf8(&map).map_err(|err| {
+        HiloParseError::Lex(format!("{} is not valid UTF-8: {err}", path.display()))
+    })?;
+    parser::parse_module(source, options)
+}
@@ -0,0 +1,82 @@
+//! Configuration accepted by [`crate::parse_module_with`].
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::ast::ExtensionPayload;
+
+/// Knobs controlling how a HILO source file is parsed.
+///
+/// `ParseOptions::default()` matches the behaviour of [`crate::parse_module`]:
+/// lenient item recovery, no comment retention, and no nesting limit.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Reject top-level content the parser does not recognise instead of
+    /// preserving it as `Item::Other`.
+    pub strict: bool,
+    /// Collect every comment in the file into `Module::comments`.
+    pub keep_trivia: bool,
+    /// Parse item headers only, leaving `Block::statements` empty; bodies can
+    /// be parsed later with `Block::parse`.
+    pub lazy_bodies: bool,
+    /// Maximum bracket nesting depth accepted before parsing is refused.
+    pub max_depth: Option<usize>,
+    /// Language revision the source is written against.
+    pub language_version: LanguageVersion,
+    /// Custom top-level item keywords recognised alongside the built-in ones.
+    pub extensions: ItemExtensions,
+}
+
+/// Published revisions of the HILO language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[non_exhaustive]
+pub enum LanguageVersion {
+    /// Draft v1.0 (`hilo/spec/LANGUAGE_SPEC.md`).
+    #[default]
+    V1_0,
+}
+
+/// Callback building the payload of a custom item from its keyword, header, and body.
+pub type ItemExtensionFn =
+    dyn Fn(&str, &str, &str) -> Result<ExtensionPayload, String> + Send + Sync;
+
+/// Registry of embedder-defined top-level item keywords.
+///
+/// A registered keyword is matched wherever a declaration may start, after the
+/// built-in items have been tried. Everything up to the first `{` is passed to
+/// the callback as the header, and the balanced brace contents as the body; the
+/// result is stored as `Item::Extension`. Returning `Err` fails the parse.
+#[derive(Clone, Default)]
+pub struct ItemExtensions {
+    handlers: BTreeMap<String, Arc<ItemExtensionFn>>,
+}
+
+impl ItemExtensions {
+    /// Register `handler` for `keyword`, replacing any previous registration.
+    pub fn register<F>(&mut self, keyword: impl Into<String>, handler: F) -> &mut Self
+    where
+        F: Fn(&str, &str, &str) -> Result<ExtensionPayload, String> + Send + Sync + 'static,
+    {
+        self.handlers.insert(keyword.into(), Arc::new(handler));
+        self
+    }
+
+    pub fn keywords(&self) -> impl Iterator<Item = &str> {
+        self.handlers.keys().map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    pub(crate) fn get(&self, keyword: &str) -> Option<&ItemExtensionFn> {
+        self.handlers.get(keyword).map(Arc::as_ref)
+    }
+}
+
+impl fmt::Debug for ItemExtensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.keywords()).finish()
+    }
+}
@@ -1,44 +1,128 @@
 //! Top-level parser entry points.
 
+use std::borrow::Cow;
+
 use chumsky::prelude::*;
-use chumsky::{Parser, error::Simple};
+use chumsky::{Parser, Stream, error::Simple};
 
-use crate::{ast, error::HiloParseError};
+use crate::{ast, error::HiloParseError, options::ParseOptions};
 
-pub fn parse_module(source: &str) -> Result<ast::Module, HiloParseError> {
-    module_parser().parse(source).map_err(|errs| {
-        let msg = errs
-            .into_iter()
-            .map(|e| e.to_string())
-            .collect::<Vec<_>>()
-            .join("\n");
-        HiloParseError::Parse(msg)
+mod expression;
+
+pub(crate) use expression::{BINARY_OPERATORS, PREFIX_LEVEL, UNARY_OPERATORS, binary_precedence};
+
+pub fn parse_module(source: &str, options: &ParseOptions) -> Result<ast::Module, HiloParseError> {
+    check_depth(source, options)?;
+    let (docs, name, imports, body_start) = parse_header(source)?;
+    let items = parse_items(source, body_start, options)?;
+    finish_module(source, docs, name, imports, items, options)
+}
+
+pub(crate) fn check_depth(source: &str, options: &ParseOptions) -> Result<(), HiloParseError> {
+    if let Some(limit) = options.max_depth {
+        let depth = max_nesting_depth(source);
+        if depth > limit {
+            return Err(HiloParseError::DepthLimit { depth, limit });
+        }
+    }
+    Ok(())
+}
+
+/// Parses the module declaration and imports, returning the offset where items begin.
+///
+/// Only the header is pulled through chumsky; spans are byte offsets so the
+/// item scanner can continue directly on `source` without copying the tail.
+pub(crate) fn parse_header(source: &str) -> Result<ModuleHeader, HiloParseError> {
+    let eoi = source.len()..source.len();
+    let tokens = source
+        .char_indices()
+        .map(|(idx, ch)| (ch, idx..idx + ch.len_utf8()));
+    module_parser()
+        .parse(Stream::from_iter(eoi, tokens))
+        .map_err(|errs| {
+            let msg = errs
+                .into_iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            HiloParseError::Parse(msg)
+        })
+}
+
+pub(crate) fn finish_module(
+    source: &str,
+    docs: Option<String>,
+    name: Option<ast::QualifiedName>,
+    imports: Vec<ast::Import>,
+    items: Vec<ast::Item>,
+    options: &ParseOptions,
+) -> Result<ast::Module, HiloParseError> {
+    if options.strict
+        && let Some(ast::Item::Other(rest)) = items.last()
+    {
+        let preview = rest.lines().next().unwrap_or_default();
+        return Err(HiloParseError::Parse(format!(
+            "unrecognized top-level content: `{preview}`"
+        )));
+    }
+    let comments = if options.keep_trivia {
+        collect_comments(source)
+    } else {
+        Vec::new()
+    };
+    Ok(ast::Module {
+        docs,
+        name,
+        imports,
+        items,
+        comments,
+        span: 0..source.len(),
     })
 }
 
-fn module_parser() -> impl Parser<char, ast::Module, Error = Simple<char>> {
-    ws().ignore_then(
-        module_decl()
-            .then(import_parser().repeated())
-            .then(remainder())
-            .map(|((name, imports), body)| {
-                let items = parse_items_from_remainder(&body);
-                ast::Module {
-                    name,
-                    imports,
-                    items,
-                }
-            }),
-    )
-    .then_ignore(ws())
-    .then_ignore(end())
+type ModuleHeader = (
+    Option<String>,
+    Option<ast::QualifiedName>,
+    Vec<ast::Import>,
+    usize,
+);
+
+fn module_parser() -> impl Parser<char, ModuleHeader, Error = Simple<char>> {
+    leading_docs()
+        .then(module_decl())
+        .then(import_parser().repeated())
+        .then_ignore(ws())
+        .then(empty().map_with_span(|_, span: ast::Span| span.start))
+        .map(|(((docs, name), imports), body_start)| {
+            // Doc comments only describe the module when it is declared.
+            let docs = name.as_ref().and(docs);
+            (docs, name, imports, body_start)
+        })
+}
+
+/// Like [`ws`], but keeps the text of `///` lines, joined by newlines.
+fn leading_docs() -> impl Parser<char, Option<String>, Error = Simple<char>> {
+    let doc_comment = just("///")
+        .ignore_then(filter(|c: &char| *c != '\n').repeated().collect::<String>())
+        .then_ignore(just('\n').ignored().or(end()))
+        .map(|text| Some(doc_line(&text)));
+
+    choice((doc_comment, trivia().to(None)))
+        .repeated()
+        .map(|lines| {
+            let lines: Vec<String> = lines.into_iter().flatten().collect();
+            (!lines.is_empty()).then(|| lines.join("\n"))
+        })
 }
 
+/// `module a.b` at the top of a file; `module name {` opens a nested module
+/// instead and is left to the item scanner.
 fn module_decl() -> impl Parser<char, Option<ast::QualifiedName>, Error = Simple<char>> {
     text::keyword("module")
         .then_ignore(ws())
         .ignore_then(qualified_name())
         .then_ignore(ws())
+        .then_ignore(just('{').not().rewind().ignored().or(end()))
         .map(Some)
         .or_not()
         .map(|opt| opt.flatten())
@@ -47,16 +131,31 @@ fn module_decl() -> impl Parser<char, Option<ast::QualifiedName>, Error = Simple
 fn import_parser() -> impl Parser<char, ast::Import, Error = Simple<char>> {
     ws().ignore_then(text::keyword("import"))
         .then_ignore(ws())
-        .ignore_then(qualified_name())
+        .ignore_then(import_path())
         .then_ignore(ws())
+        .then(import_version().or_not())
         .then(import_tail())
-        .map(|(path, (alias, members))| ast::Import {
+        .map(|((path, version), (alias, members))| ast::Import {
             path,
+            version,
             members,
             alias,
         })
 }
 
+/// `@ "1.2"` after an import path; the constraint text is kept verbatim.
+fn import_version() -> impl Parser<char, String, Error = Simple<char>> {
+    just('@')
+        .then_ignore(ws())
+        .ignore_then(
+            filter(|c: &char| *c != '"' && *c != '\n')
+                .repeated()
+                .collect::<String>()
+                .delimited_by(just('"'), just('"')),
+        )
+        .then_ignore(ws())
+}
+
 fn import_tail() -> impl Parser<char, (Option<String>, Option<Vec<String>>), Error = Simple<char>> {
     let alias_then_members = alias_parser()
         .map(Some)
@@ -74,8 +173,22 @@ fn import_tail() -> impl Parser<char, (Option<String>, Option<Vec<String>>), Err
         .map(|opt| opt.unwrap_or((None, None)))
 }
 
-fn remainder() -> impl Parser<char, String, Error = Simple<char>> {
-    any().repeated().collect::<String>()
+/// A qualified name, optionally preceded by `./` or one or more `../`, which
+/// stay in the path as `.` and `..` segments.
+fn import_path() -> impl Parser<char, ast::QualifiedName, Error = Simple<char>> {
+    let current = just("./").to(vec![".".to_string()]);
+    let parents = just("../").to("..".to_string()).repeated().at_least(1);
+    current
+        .or(parents)
+        .or_not()
+        .then(qualified_name())
+        .map(|(relative, name)| {
+            relative
+                .unwrap_or_default()
+                .into_iter()
+                .chain(name)
+                .collect()
+        })
 }
 
 fn qualified_name() -> impl Parser<char, ast::QualifiedName, Error = Simple<char>> {
@@ -113,6 +226,11 @@ fn member_list_parser() -> impl Parser<char, Vec<String>, Error = Simple<char>>
 }
 
 fn ws() -> impl Parser<char, (), Error = Simple<char>> {
+    trivia().repeated().ignored()
+}
+
+/// One run of whitespace or a single comment.
+fn trivia() -> impl Parser<char, (), Error = Simple<char>> {
     let spaces = filter(|c: &char| c.is_whitespace())
         .repeated()
         .at_least(1)
@@ -134,47 +252,89 @@ fn ws() -> impl Parser<char, (), Error = Simple<char>> {
         .ignored();
 
     choice((spaces, doc_comment, line_comment, block_comment))
-        .repeated()
-        .ignored()
 }
 
-fn parse_items_from_remainder(src: &str) -> Vec<ast::Item> {
+/// Item keywords whose declarations take no annotations; `@x` before one is
+/// an error rather than unrecognized content that swallows the rest of the file.
+const UNANNOTATED_ITEM_KEYWORDS: &[&str] = &[
+    "interface",
+    "trait",
+    "test",
+    "hook",
+    "prompt",
+    "tool",
+    "trigger",
+    "on",
+    "config",
+    "secret",
+    "export",
+    "module",
+];
+
+/// Scans declarations in `src[start..]`; spans are offsets into `src`.
+pub(crate) fn parse_items(
+    src: &str,
+    start: usize,
+    options: &ParseOptions,
+) -> Result<Vec<ast::Item>, HiloParseError> {
     let mut items = Vec::new();
-    let mut offset = skip_ws(src, 0);
+    let mut offset = rewind_doc_comments(src, skip_ws(src, start));
     while offset < src.len() {
-        if let Some((item, next)) = parse_record_decl(src, offset) {
-            items.push(item);
-            offset = skip_ws(src, next);
-            continue;
-        }
-        if let Some((item, next)) = parse_task_decl(src, offset) {
-            items.push(item);
-            offset = skip_ws(src, next);
-            continue;
-        }
-        if let Some((item, next)) = parse_workflow_decl(src, offset) {
-            items.push(item);
-            offset = skip_ws(src, next);
-            continue;
+        let (annotations, after_annotations) =
+            parse_annotations(src, skip_doc_comments(src, offset), 0);
+        let (_, keyword_at) = parse_visibility(src, skip_ws(src, after_annotations));
+        if let Some(annotation) = annotations.first()
+            && let Some(keyword) = ident_at(src, keyword_at)
+            && UNANNOTATED_ITEM_KEYWORDS.contains(&keyword)
+        {
+            return Err(HiloParseError::Parse(format!(
+                "`{}` cannot annotate a `{keyword}` declaration",
+                &src[annotation.span.clone()]
+            )));
         }
-        if let Some((item, next)) = parse_test_decl(src, offset) {
+        let parsed = match ident_at(src, keyword_at) {
+            Some("record") => parse_record_decl(src, offset, options),
+            Some("interface" | "trait") => parse_interface_decl(src, offset),
+            Some("task" | "async") => parse_task_decl(src, offset, options),
+            Some("workflow") => parse_workflow_decl(src, offset, options),
+            Some("test") => parse_test_decl(src, offset, options),
+            Some("hook") => parse_hook_decl(src, offset, options),
+            Some("prompt") => parse_prompt_decl(src, offset),
+            Some("tool") => parse_tool_decl(src, offset),
+            Some("trigger") => parse_trigger_decl(src, offset),
+            Some("on") => parse_event_handler(src, offset, options),
+            Some("config") => parse_config_decl(src, offset),
+            Some("secret") => parse_secret_decl(src, offset),
+            Some("export") => parse_export_decl(src, offset),
+            Some("module") => parse_nested_module(src, offset, options)?,
+            Some(_) => parse_extension_item(src, offset, options)?,
+            None => None,
+        };
+        if let Some((item, next)) = parsed {
             items.push(item);
-            offset = skip_ws(src, next);
+            offset = rewind_doc_comments(src, skip_ws(src, next));
             continue;
         }
 
-        let remainder = src[offset..].trim();
+        let remainder = src[skip_ws(src, offset)..].trim();
         if remainder.is_empty() {
             break;
         }
         items.push(ast::Item::Other(remainder.to_string()));
         break;
     }
-    items
+    Ok(items)
 }
 
-fn parse_record_decl(src: &str, start: usize) -> Option<(ast::Item, usize)> {
-    let mut idx = skip_doc_comments(src, start);
+fn parse_record_decl(
+    src: &str,
+    start: usize,
+    options: &ParseOptions,
+) -> Option<(ast::Item, usize)> {
+    let (docs, next) = take_doc_comments(src, start);
+    let (annotations, next) = parse_annotations(src, next, 0);
+    let item_start = skip_ws(src, next);
+    let (visibility, mut idx) = parse_visibility(src, item_start);
     if !starts_with_keyword(src, idx, "record") {
         return None;
     }
@@ -183,93 +343,533 @@ fn parse_record_decl(src: &str, start: usize) -> Option<(ast::Item, usize)> {
     let (name, mut idx) = take_ident(src, idx)?;
     idx = skip_ws(src, idx);
 
-    let mut type_params = Vec::new();
-    if src[idx..].starts_with('<') {
-        let (params_src, consumed) = extract_balanced(src, idx, '<', '>')?;
-        idx = consumed;
-        type_params = params_src
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
+    let (type_params, mut idx) = parse_type_params(src, idx)?;
+
+    let mut extends = None;
+    if starts_with_keyword(src, idx, "extends") {
+        idx = skip_ws(src, idx + "extends".len());
+        let mut parent = Vec::new();
+        loop {
+            let (part, next) = take_ident(src, idx)?;
+            parent.push(part);
+            idx = next;
+            if !src[idx..].starts_with('.') {
+                break;
+            }
+            idx += 1;
+        }
+        extends = Some(parent);
         idx = skip_ws(src, idx);
     }
+    let (constraints, mut idx) = parse_where_clause(src, idx)?;
 
     if !src[idx..].starts_with('{') {
         return None;
     }
-    let (fields_src, consumed) = extract_balanced(src, idx, '{', '}')?;
-    idx = consumed;
-    let fields = parse_record_fields(&fields_src);
-    idx = skip_ws(src, idx);
+    let (_, consumed) = extract_balanced(src, idx, '{', '}')?;
+    let (segments, methods) = split_record_members(src, idx + 1..consumed - 1, options);
+    let fields = parse_record_fields(src, &segments);
+    idx = skip_ws(src, consumed);
 
     Some((
         ast::Item::Record(ast::RecordDecl {
+            docs,
+            annotations,
+            visibility,
             name,
             type_params,
+            extends,
+            constraints,
             fields,
+            methods,
+            span: item_start..consumed,
         }),
         idx,
     ))
 }
 
-fn parse_task_decl(src: &str, start: usize) -> Option<(ast::Item, usize)> {
+/// Separates method declarations (`fn` or `func`) from the field text of a
+/// record body, returned as the ranges of `src` between methods.
+fn split_record_members(
+    src: &str,
+    body: ast::Span,
+    options: &ParseOptions,
+) -> (Vec<ast::Span>, Vec<ast::TaskDecl>) {
+    let mut fields = Vec::new();
+    let mut methods = Vec::new();
+    let mut copied = body.start;
+    let mut depth = 0usize;
+    let mut idx = body.start;
+    while idx < body.end {
+        if let Some(next) = skip_comment_or_string(src, idx) {
+            idx = next;
+            continue;
+        }
+        let ch = peek_char(src, idx).unwrap_or('\0');
+        match ch {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && (ch == '@' || is_ident_start(ch)) => {
+                let (_, after_annotations) = parse_annotations(&src[..body.end], idx, 0);
+                // Doc comments above a method were scanned as field text.
+                let method_start = rewind_doc_comments(src, idx).max(copied);
+                if let Some(keyword) = method_keyword(src, skip_ws(src, after_annotations))
+                    && let Some((method, next)) =
+                        parse_callable(&src[..body.end], method_start, 0, keyword, options)
+                {
+                    fields.push(copied..method_start);
+                    methods.push(method);
+                    copied = next;
+                    idx = next;
+                    continue;
+                }
+                idx += ident_at(src, idx).map_or(ch.len_utf8(), str::len);
+                continue;
+            }
+            _ => {}
+        }
+        idx += ch.len_utf8();
+    }
+    fields.push(copied..body.end);
+    (fields, methods)
+}
+
+/// `fn` or `func` when a method declaration, possibly `pub` or `async`, starts
+/// at `idx`.
+fn method_keyword(src: &str, idx: usize) -> Option<&str> {
+    let (_, idx) = parse_visibility(src, idx);
+    let keyword = match ident_at(src, idx)? {
+        "async" => ident_at(src, skip_ws(src, idx + "async".len()))?,
+        keyword => keyword,
+    };
+    matches!(keyword, "fn" | "func").then_some(keyword)
+}
+
+/// Parses `interface Name { ... }` (or `trait Name { ... }`), whose members are
+/// bodiless signatures ending at a newline or `;`.
+fn parse_interface_decl(src: &str, start: usize) -> Option<(ast::Item, usize)> {
+    let mut idx = skip_doc_comments(src, start);
+    let item_start = idx;
+    let keyword = ident_at(src, idx).filter(|word| matches!(*word, "interface" | "trait"))?;
+    idx = skip_ws(src, idx + keyword.len());
+    let (name, next) = take_ident(src, idx)?;
+    idx = skip_ws(src, next);
+    if !src[idx..].starts_with('{') {
+        return None;
+    }
+    let (_, consumed) = extract_balanced(src, idx, '{', '}')?;
+    let body = &src[idx + 1..consumed - 1];
+    let methods = split_statements(body)
+        .into_iter()
+        .map(|member| parse_method_sig(member, idx + 1 + offset_in(body, member)))
+        .collect::<Option<Vec<_>>>()?;
+    Some((
+        ast::Item::Interface(ast::InterfaceDecl {
+            name,
+            methods,
+            span: item_start..consumed,
+        }),
+        skip_ws(src, consumed),
+    ))
+}
+
+/// `async? (task | fn | func) name(params) (-> Type)?`, starting at byte `base`.
+fn parse_method_sig(member: &str, base: usize) -> Option<ast::MethodSig> {
+    let is_async = starts_with_keyword(member, 0, "async");
+    let mut idx = if is_async {
+        skip_ws(member, "async".len())
+    } else {
+        0
+    };
+    let keyword = ident_at(member, idx).filter(|word| matches!(*word, "task" | "fn" | "func"))?;
+    idx = skip_ws(member, idx + keyword.len());
+    let (name, next) = take_ident(member, idx)?;
+    let (type_params, idx) = parse_type_params(member, skip_ws(member, next))?;
+    let (params_src, consumed) = extract_balanced(member, idx, '(', ')')?;
+    let params = parse_params(&params_src, base + idx + 1);
+    let rest = member[consumed..].trim();
+    let return_type = match rest.strip_prefix("->").map(str::trim) {
+        Some(ty) if !ty.is_empty() => Some(parse_type_expr(ty)),
+        None if rest.is_empty() => None,
+        _ => return None,
+    };
+    Some(ast::MethodSig {
+        name,
+        is_async,
+        type_params,
+        params,
+        return_type,
+        span: base..base + member.len(),
+    })
+}
+
+/// Parses `prompt Name { system: """...""", user: "..." }`. Each section is a
+/// `name: "string"` entry separated by commas or newlines.
+fn parse_prompt_decl(src: &str, start: usize) -> Option<(ast::Item, usize)> {
     let mut idx = skip_doc_comments(src, start);
-    if !starts_with_keyword(src, idx, "task") {
+    let item_start = idx;
+    if !starts_with_keyword(src, idx, "prompt") {
+        return None;
+    }
+    idx = skip_ws(src, idx + "prompt".len());
+    let (name, next) = take_ident(src, idx)?;
+    idx = skip_ws(src, next);
+    if !src[idx..].starts_with('{') {
+        return None;
+    }
+    let (_, consumed) = extract_balanced(src, idx, '{', '}')?;
+    let body = &src[idx + 1..consumed - 1];
+    let sections = split_statements(body)
+        .into_iter()
+        .flat_map(split_args)
+        .map(|entry| parse_prompt_section(entry, idx + 1 + offset_in(body, entry)))
+        .collect::<Option<Vec<_>>>()?;
+    Some((
+        ast::Item::Prompt(ast::PromptDecl {
+            name,
+            sections,
+            span: item_start..consumed,
+        }),
+        skip_ws(src, consumed),
+    ))
+}
+
+/// `name: "text"`, starting at byte `base`.
+fn parse_prompt_section(entry: &str, base: usize) -> Option<ast::PromptSection> {
+    let colon = find_top_level(entry, ":")?;
+    let name = entry[..colon].trim();
+    if ident_at(name, 0) != Some(name) {
+        return None;
+    }
+    let ast::Literal::Str(text) = parse_literal(entry[colon + 1..].trim())? else {
+        return None;
+    };
+    Some(ast::PromptSection {
+        name: name.to_string(),
+        placeholders: prompt_placeholders(&text),
+        text,
+        span: base..base + entry.len(),
+    })
+}
+
+/// Dotted names inside `{...}` in prompt text, in order of first use. `{{` and
+/// `}}` are literal braces, and braces around anything other than a dotted
+/// name are left as text.
+fn prompt_placeholders(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        rest = &rest[open + 1..];
+        if let Some(escaped) = rest.strip_prefix('{') {
+            rest = escaped;
+            continue;
+        }
+        let Some(close) = rest.find('}') else {
+            break;
+        };
+        let name = rest[..close].trim();
+        let is_path =
+            !name.is_empty() && name.split('.').all(|part| ident_at(part, 0) == Some(part));
+        if is_path && !names.iter().any(|seen| seen == name) {
+            names.push(name.to_string());
+        }
+        rest = &rest[close + 1..];
+    }
+    names
+}
+
+/// Parses `tool web.search(query: String) -> List[SearchResult]`, which ends at
+/// the first newline or `;` outside brackets.
+/// Parses `export { Name, Other }` and the re-exports `export lib.text { trim }`
+/// and `export * from lib.text`.
+fn parse_export_decl(src: &str, start: usize) -> Option<(ast::Item, usize)> {
+    let item_start = skip_doc_comments(src, start);
+    if !starts_with_keyword(src, item_start, "export") {
+        return None;
+    }
+    let idx = skip_ws(src, item_start + "export".len());
+    if src[idx..].starts_with('*') {
+        let idx = skip_ws(src, idx + 1);
+        if !starts_with_keyword(src, idx, "from") {
+            return None;
+        }
+        let (path, end) = take_qualified_name(src, skip_ws(src, idx + "from".len()))?;
+        let item = ast::Item::ReExport(ast::ReExportDecl {
+            path,
+            members: None,
+            span: item_start..end,
+        });
+        return Some((item, skip_ws(src, end)));
+    }
+    let (path, idx) = match take_qualified_name(src, idx) {
+        Some((path, next)) => (Some(path), skip_ws(src, next)),
+        None => (None, idx),
+    };
+    if !src[idx..].starts_with('{') {
+        return None;
+    }
+    let (names_src, consumed) = extract_balanced(src, idx, '{', '}')?;
+    let names = split_args(&names_src)
+        .into_iter()
+        .map(|name| (ident_at(name, 0) == Some(name)).then(|| name.to_string()))
+        .collect::<Option<_>>()?;
+    let span = item_start..consumed;
+    let item = match path {
+        Some(path) => ast::Item::ReExport(ast::ReExportDecl {
+            path,
+            members: Some(names),
+            span,
+        }),
+        None => ast::Item::Export(ast::ExportDecl { names, span }),
+    };
+    Some((item, skip_ws(src, consumed)))
+}
+
+/// A dotted name such as `lib.text` at `idx`, and the offset just past it.
+fn take_qualified_name(src: &str, idx: usize) -> Option<(ast::QualifiedName, usize)> {
+    let (first, mut idx) = take_ident(src, idx)?;
+    let mut path = vec![first];
+    while src[idx..].starts_with('.') {
+        let (part, next) = take_ident(src, idx + 1)?;
+        path.push(part);
+        idx = next;
+    }
+    Some((path, idx))
+}
+
+fn parse_tool_decl(src: &str, start: usize) -> Option<(ast::Item, usize)> {
+    let mut idx = skip_doc_comments(src, start);
+    let item_start = idx;
+    if !starts_with_keyword(src, idx, "tool") {
+        return None;
+    }
+    idx = skip_ws(src, idx + "tool".len());
+    let mut name = Vec::new();
+    loop {
+        let (part, next) = take_ident(src, idx)?;
+        name.push(part);
+        idx = next;
+        if !src[idx..].starts_with('.') {
+            break;
+        }
+        idx += 1;
+    }
+    idx = skip_ws(src, idx);
+    let (params_src, consumed) = extract_balanced(src, idx, '(', ')')?;
+    let params = parse_params(&params_src, idx + 1);
+    let end = declaration_end(src, consumed);
+    let rest = src[consumed..end].trim();
+    let return_type = match rest.strip_prefix("->").map(str::trim) {
+        Some(ty) if !ty.is_empty() => Some(parse_type_expr(ty)),
+        None if rest.is_empty() => None,
+        _ => return None,
+    };
+    let span_end = consumed + src[consumed..end].trim_end().len();
+    let next = if src[end..].starts_with(';') {
+        end + 1
+    } else {
+        end
+    };
+    Some((
+        ast::Item::Tool(ast::ToolDecl {
+            name,
+            params,
+            return_type,
+            span: item_start..span_end,
+        }),
+        skip_ws(src, next),
+    ))
+}
+
+/// Offset of the first newline, `;`, or comment after `start` that is outside
+/// brackets and strings, or the end of `src`.
+fn declaration_end(src: &str, start: usize) -> usize {
+    let mut depth = 0usize;
+    let mut idx = start;
+    while let Some(ch) = peek_char(src, idx) {
+        let rest = &src[idx..];
+        if depth == 0 && (rest.starts_with("//") || rest.starts_with("/*")) {
+            return idx;
+        }
+        if let Some(next) = skip_comment_or_string(src, idx) {
+            idx = next;
+            continue;
+        }
+        match ch {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            '\n' | ';' if depth == 0 => return idx,
+            _ => {}
+        }
+        idx += ch.len_utf8();
+    }
+    src.len()
+}
+
+fn parse_task_decl(src: &str, start: usize, options: &ParseOptions) -> Option<(ast::Item, usize)> {
+    let (decl, next) = parse_callable(src, start, 0, "task", options)?;
+    Some((ast::Item::Task(decl), next))
+}
+
+/// Parses `async? keyword name(params) -> Type { body }`, shared by tasks,
+/// record methods, and functions nested in bodies. Spans are offset by `base`;
+/// the returned offset is not.
+fn parse_callable(
+    src: &str,
+    start: usize,
+    base: usize,
+    keyword: &str,
+    options: &ParseOptions,
+) -> Option<(ast::TaskDecl, usize)> {
+    let (docs, next) = take_doc_comments(src, start);
+    let (annotations, next) = parse_annotations(src, next, base);
+    let item_start = skip_ws(src, next);
+    let (visibility, mut idx) = parse_visibility(src, item_start);
+    let is_async = starts_with_keyword(src, idx, "async");
+    if is_async {
+        idx = skip_ws(src, idx + "async".len());
+    }
+    if !starts_with_keyword(src, idx, keyword) {
         return None;
     }
-    idx += "task".len();
+    idx += keyword.len();
     idx = skip_ws(src, idx);
     let (name, mut idx) = take_ident(src, idx)?;
     idx = skip_ws(src, idx);
+    let (type_params, idx) = parse_type_params(src, idx)?;
 
     if !src[idx..].starts_with('(') {
         return None;
     }
     let (params_src, consumed) = extract_balanced(src, idx, '(', ')')?;
-    idx = consumed;
-    let params = parse_params(&params_src);
-    idx = skip_ws(src, idx);
+    let params = parse_params(&params_src, base + idx + 1);
+    let mut idx = skip_ws(src, consumed);
 
     let mut return_type = None;
     if idx < src.len() && src[idx..].starts_with("->") {
         idx += 2;
         idx = skip_ws(src, idx);
         let type_start = idx;
-        while idx < src.len() && !src[idx..].starts_with('{') {
-            if let Some(ch) = peek_char(src, idx) {
-                idx += ch.len_utf8();
-            } else {
-                break;
+        // The body is the first `{` outside the type's own brackets; a
+        // leading `{` opens a struct return type. `where`, `retry`, and
+        // `timeout` clauses may sit between the two.
+        let mut depth = 0usize;
+        while let Some(ch) = peek_char(src, idx) {
+            let started = !src[type_start..idx].trim().is_empty();
+            match ch {
+                '{' if depth == 0 && started => break,
+                _ if depth == 0
+                    && started
+                    && (starts_policy_clause(src, idx)
+                        || src[..idx].ends_with(char::is_whitespace)
+                            && starts_with_keyword(src, idx, "where")) =>
+                {
+                    break;
+                }
+                '(' | '[' | '<' | '{' => depth += 1,
+                ')' | ']' | '>' | '}' => depth = depth.saturating_sub(1),
+                _ => {}
             }
+            idx += ch.len_utf8();
         }
         let ty_str = src[type_start..idx].trim();
         if !ty_str.is_empty() {
             return_type = Some(parse_type_expr(ty_str));
         }
     }
-    idx = skip_ws(src, idx);
+    let (constraints, idx) = parse_where_clause(src, skip_ws(src, idx))?;
+    let (retry, idx) = parse_retry_clause(src, skip_ws(src, idx), base)?;
+    let (timeout, mut idx) = parse_timeout_clause(src, idx)?;
 
     if !src[idx..].starts_with('{') {
         return None;
     }
     let (body_src, consumed) = extract_balanced(src, idx, '{', '}')?;
-    idx = consumed;
-    idx = skip_ws(src, idx);
+    let body = build_block(&body_src, base + idx + 1, options);
+    idx = skip_ws(src, consumed);
 
     Some((
-        ast::Item::Task(ast::TaskDecl {
+        ast::TaskDecl {
+            docs,
+            annotations,
+            visibility,
             name,
+            is_async,
+            type_params,
             params,
             return_type,
-            body: build_block(&body_src),
-        }),
+            constraints,
+            retry,
+            timeout,
+            body,
+            span: base + item_start..base + consumed,
+        },
         idx,
     ))
 }
 
-fn parse_workflow_decl(src: &str, start: usize) -> Option<(ast::Item, usize)> {
-    let mut idx = skip_doc_comments(src, start);
+/// Parses the `@name` or `@name(args)` annotations from `idx` on, returning
+/// them with the offset just past the last one. Spans are offset by `base`.
+fn parse_annotations(src: &str, mut idx: usize, base: usize) -> (Vec<ast::Annotation>, usize) {
+    let mut annotations = Vec::new();
+    loop {
+        let at = skip_ws(src, idx);
+        if !src[at..].starts_with('@') {
+            break;
+        }
+        let Some((name, mut next)) = take_ident(src, at + 1) else {
+            break;
+        };
+        let mut args = Vec::new();
+        if src[next..].starts_with('(') {
+            let Some((_, consumed)) = extract_balanced(src, next, '(', ')') else {
+                break;
+            };
+            let inner = &src[next + 1..consumed - 1];
+            let inner_base = base + next + 1;
+            args = split_args(inner)
+                .into_iter()
+                .map(|arg| parse_annotation_arg(arg, inner_base + offset_in(inner, arg)))
+                .collect();
+            next = consumed;
+        }
+        annotations.push(ast::Annotation {
+            name,
+            args,
+            span: base + at..base + next,
+        });
+        idx = next;
+    }
+    (annotations, idx)
+}
+
+/// `name: value` or a bare `value`, starting at byte `base`.
+fn parse_annotation_arg(arg: &str, base: usize) -> ast::AnnotationArg {
+    if let Some(colon) = find_top_level(arg, ":") {
+        let name = arg[..colon].trim();
+        if ident_at(name, 0) == Some(name) {
+            let value = &arg[colon + 1..];
+            return ast::AnnotationArg {
+                name: Some(name.to_string()),
+                value: parse_expression(value, base + colon + 1),
+            };
+        }
+    }
+    ast::AnnotationArg {
+        name: None,
+        value: parse_expression(arg, base),
+    }
+}
+
+fn parse_workflow_decl(
+    src: &str,
+    start: usize,
+    options: &ParseOptions,
+) -> Option<(ast::Item, usize)> {
+    let (docs, next) = take_doc_comments(src, start);
+    let (annotations, next) = parse_annotations(src, next, 0);
+    let item_start = skip_ws(src, next);
+    let (visibility, mut idx) = parse_visibility(src, item_start);
     if !starts_with_keyword(src, idx, "workflow") {
         return None;
     }
@@ -281,22 +881,159 @@ fn parse_workflow_decl(src: &str, start: usize) -> Option<(ast::Item, usize)> {
         return None;
     }
     let (body_src, consumed) = extract_balanced(src, idx, '{', '}')?;
-    idx = consumed;
-    idx = skip_ws(src, idx);
+    let mut body = unparsed_block(&body_src, idx + 1);
+    let on_error = take_error_handler(&mut body, options);
+    let mut steps = Vec::new();
+    for stmt in split_statements(&body.raw) {
+        let base = body.span.start + offset_in(&body.raw, stmt);
+        if let Some(step) = parse_step_decl(stmt, base, options) {
+            steps.push(step);
+            body.lifted.push(base..base + stmt.len());
+        }
+    }
+    parse_unless_lazy(&mut body, options);
+    idx = skip_ws(src, consumed);
     Some((
         ast::Item::Workflow(ast::WorkflowDecl {
+            docs,
+            annotations,
+            visibility,
             name,
-            body: build_block(&body_src),
+            body,
+            steps,
+            on_error,
+            span: item_start..consumed,
         }),
         idx,
     ))
 }
 
-fn parse_test_decl(src: &str, start: usize) -> Option<(ast::Item, usize)> {
+/// `module name { items }`; the body is scanned like a file without a header.
+fn parse_nested_module(
+    src: &str,
+    start: usize,
+    options: &ParseOptions,
+) -> Result<Option<(ast::Item, usize)>, HiloParseError> {
+    let (docs, item_start) = take_doc_comments(src, start);
+    if !starts_with_keyword(src, item_start, "module") {
+        return Ok(None);
+    }
+    let idx = skip_ws(src, item_start + "module".len());
+    let Some((name, idx)) = take_ident(src, idx) else {
+        return Ok(None);
+    };
+    let idx = skip_ws(src, idx);
+    if !src[idx..].starts_with('{') {
+        return Ok(None);
+    }
+    let Some((_, consumed)) = extract_balanced(src, idx, '{', '}') else {
+        return Ok(None);
+    };
+    let items = parse_items(&src[..consumed - 1], idx + 1, options)?;
+    let module = ast::Module {
+        docs,
+        name: Some(vec![name]),
+        imports: Vec::new(),
+        items,
+        comments: Vec::new(),
+        span: item_start..consumed,
+    };
+    Ok(Some((ast::Item::Module(module), skip_ws(src, consumed))))
+}
+
+/// `step name { ... }` or `step name(a, b) { ... }`, one statement of a
+/// workflow body starting at byte `base`.
+fn parse_step_decl(stmt: &str, base: usize, options: &ParseOptions) -> Option<ast::StepDecl> {
+    if !starts_with_keyword(stmt, 0, "step") {
+        return None;
+    }
+    let idx = skip_ws(stmt, "step".len());
+    let (name, idx) = take_ident(stmt, idx)?;
+    let mut idx = skip_ws(stmt, idx);
+    let mut inputs = Vec::new();
+    if stmt[idx..].starts_with('(') {
+        let (inner, consumed) = extract_balanced(stmt, idx, '(', ')')?;
+        for input in split_args(&inner) {
+            inputs.push(
+                take_ident(input, 0)
+                    .filter(|(_, end)| *end == input.len())?
+                    .0,
+            );
+        }
+        idx = skip_ws(stmt, consumed);
+    }
+    let (retry, idx) = parse_retry_clause(stmt, idx, base)?;
+    let (timeout, idx) = parse_timeout_clause(stmt, idx)?;
+    if !stmt[idx..].starts_with('{') {
+        return None;
+    }
+    let (body_src, consumed) = extract_balanced(stmt, idx, '{', '}')?;
+    if !stmt[consumed..].trim().is_empty() {
+        return None;
+    }
+    let mut body = unparsed_block(&body_src, base + idx + 1);
+    let on_error = take_error_handler(&mut body, options);
+    parse_unless_lazy(&mut body, options);
+    Some(ast::StepDecl {
+        name,
+        inputs,
+        retry,
+        timeout,
+        body,
+        on_error,
+        span: base..base + consumed,
+    })
+}
+
+/// Lifts the first top-level `on_error` clause out of the still unparsed
+/// `body`. Any later clauses stay behind as ordinary statements.
+fn take_error_handler(body: &mut ast::Block, options: &ParseOptions) -> Option<ast::ErrorHandler> {
+    let (handler, lifted) = split_statements(&body.raw).into_iter().find_map(|stmt| {
+        let base = body.span.start + offset_in(&body.raw, stmt);
+        parse_error_handler(stmt, base, options).map(|handler| (handler, base..base + stmt.len()))
+    })?;
+    body.lifted.push(lifted);
+    Some(handler)
+}
+
+/// `on_error { ... }` or `on_error(err) { ... }`, one statement of a workflow
+/// or step body starting at byte `base`.
+fn parse_error_handler(
+    stmt: &str,
+    base: usize,
+    options: &ParseOptions,
+) -> Option<ast::ErrorHandler> {
+    if !starts_with_keyword(stmt, 0, "on_error") {
+        return None;
+    }
+    let mut idx = skip_ws(stmt, "on_error".len());
+    let mut binding = None;
+    if stmt[idx..].starts_with('(') {
+        let (inner, consumed) = extract_balanced(stmt, idx, '(', ')')?;
+        let name = inner.trim();
+        binding = Some(take_ident(name, 0).filter(|(_, end)| *end == name.len())?.0);
+        idx = skip_ws(stmt, consumed);
+    }
+    if !stmt[idx..].starts_with('{') {
+        return None;
+    }
+    let (body_src, consumed) = extract_balanced(stmt, idx, '{', '}')?;
+    if !stmt[consumed..].trim().is_empty() {
+        return None;
+    }
+    Some(ast::ErrorHandler {
+        binding,
+        body: build_block(&body_src, base + idx + 1, options),
+        span: base..base + consumed,
+    })
+}
+
+fn parse_test_decl(src: &str, start: usize, options: &ParseOptions) -> Option<(ast::Item, usize)> {
     let mut idx = skip_doc_comments(src, start);
     if !starts_with_keyword(src, idx, "test") {
         return None;
     }
+    let item_start = idx;
     idx += "test".len();
     idx = skip_ws(src, idx);
     let (name, idx_after_name) = if src[idx..].starts_with('"') {
@@ -309,134 +1046,1034 @@ fn parse_test_decl(src: &str, start: usize) -> Option<(ast::Item, usize)> {
         return None;
     }
     let (body_src, consumed) = extract_balanced(src, idx, '{', '}')?;
-    idx = consumed;
-    idx = skip_ws(src, idx);
+    let body = build_block(&body_src, idx + 1, options);
+    idx = skip_ws(src, consumed);
     Some((
         ast::Item::Test(ast::TestDecl {
             name,
-            body: build_block(&body_src),
+            body,
+            span: item_start..consumed,
         }),
         idx,
     ))
 }
 
-fn build_block(body_src: &str) -> ast::Block {
-    let raw = body_src.trim().to_string();
-    let mut statements = Vec::new();
-    let mut buffer = String::new();
-    let mut brace_balance: i32 = 0;
+fn parse_hook_decl(src: &str, start: usize, options: &ParseOptions) -> Option<(ast::Item, usize)> {
+    let mut idx = skip_doc_comments(src, start);
+    if !starts_with_keyword(src, idx, "hook") {
+        return None;
+    }
+    let item_start = idx;
+    idx = skip_ws(src, idx + "hook".len());
+    let (name, idx) = take_ident(src, idx)?;
+    let idx = skip_ws(src, idx);
+    let (position, idx) = take_ident(src, idx)?;
+    let position = match position.as_str() {
+        "before" => ast::HookPosition::Before,
+        "after" => ast::HookPosition::After,
+        "around" => ast::HookPosition::Around,
+        _ => return None,
+    };
+    let mut idx = skip_ws(src, idx);
+    if !starts_with_keyword(src, idx, "task") {
+        return None;
+    }
+    idx = skip_ws(src, idx + "task".len());
+    let pattern_len = src[idx..]
+        .find(|ch: char| !(ch == '*' || is_ident_continue(Some(ch))))
+        .unwrap_or(src.len() - idx);
+    if pattern_len == 0 {
+        return None;
+    }
+    let pattern = src[idx..idx + pattern_len].to_string();
+    idx = skip_ws(src, idx + pattern_len);
+    if !src[idx..].starts_with('{') {
+        return None;
+    }
+    let (body_src, consumed) = extract_balanced(src, idx, '{', '}')?;
+    let body = build_block(&body_src, idx + 1, options);
+    Some((
+        ast::Item::Hook(ast::HookDecl {
+            name,
+            position,
+            pattern,
+            body,
+            span: item_start..consumed,
+        }),
+        skip_ws(src, consumed),
+    ))
+}
 
-    for raw_line in body_src.lines() {
-        let trimmed = raw_line.trim();
-        if trimmed.is_empty() {
-            continue;
+/// `trigger Name { cron: "...", run: Target }`; entries are separated by
+/// newlines or commas, and each must appear exactly once.
+fn parse_trigger_decl(src: &str, start: usize) -> Option<(ast::Item, usize)> {
+    let mut idx = skip_doc_comments(src, start);
+    let item_start = idx;
+    if !starts_with_keyword(src, idx, "trigger") {
+        return None;
+    }
+    idx = skip_ws(src, idx + "trigger".len());
+    let (name, next) = take_ident(src, idx)?;
+    idx = skip_ws(src, next);
+    if !src[idx..].starts_with('{') {
+        return None;
+    }
+    let (body, consumed) = extract_balanced(src, idx, '{', '}')?;
+    let (mut cron, mut run) = (None, None);
+    for entry in split_statements(&body).into_iter().flat_map(split_args) {
+        let colon = find_top_level(entry, ":")?;
+        let value = entry[colon + 1..].trim();
+        match entry[..colon].trim() {
+            "cron" if cron.is_none() => {
+                let ast::Literal::Str(text) = parse_literal(value)? else {
+                    return None;
+                };
+                cron = Some(text);
+            }
+            "run" if run.is_none() => {
+                let path: Vec<ast::Ident> = value
+                    .split('.')
+                    .map(|segment| {
+                        let segment = segment.trim();
+                        (ident_at(segment, 0) == Some(segment)).then(|| segment.to_string())
+                    })
+                    .collect::<Option<_>>()?;
+                run = Some(path);
+            }
+            _ => return None,
         }
+    }
+    Some((
+        ast::Item::Trigger(ast::TriggerDecl {
+            name,
+            cron: cron?,
+            run: run?,
+            span: item_start..consumed,
+        }),
+        skip_ws(src, consumed),
+    ))
+}
 
-        if buffer.is_empty() {
-            if trimmed.starts_with("return") {
-                let (brace_delta, _, _) = nesting_deltas(trimmed);
-                if brace_delta > 0 && !trimmed.contains('}') {
-                    buffer.push_str(trimmed);
-                    brace_balance = brace_delta;
-                    continue;
-                }
-                statements.push(parse_statement(trimmed));
-                continue;
-            }
+/// `on event "pattern" (binding) { ... }`; the binding is optional.
+fn parse_event_handler(
+    src: &str,
+    start: usize,
+    options: &ParseOptions,
+) -> Option<(ast::Item, usize)> {
+    let item_start = skip_doc_comments(src, start);
+    if !starts_with_keyword(src, item_start, "on") {
+        return None;
+    }
+    let idx = skip_ws(src, item_start + "on".len());
+    if !starts_with_keyword(src, idx, "event") {
+        return None;
+    }
+    let (pattern, idx) = take_string_literal(src, skip_ws(src, idx + "event".len()))?;
+    let mut idx = skip_ws(src, idx);
+    let mut binding = None;
+    if src[idx..].starts_with('(') {
+        let (inner, consumed) = extract_balanced(src, idx, '(', ')')?;
+        let name = inner.trim();
+        binding = Some(take_ident(name, 0).filter(|(_, end)| *end == name.len())?.0);
+        idx = skip_ws(src, consumed);
+    }
+    if !src[idx..].starts_with('{') {
+        return None;
+    }
+    let (body_src, consumed) = extract_balanced(src, idx, '{', '}')?;
+    Some((
+        ast::Item::EventHandler(ast::EventHandlerDecl {
+            pattern,
+            binding,
+            body: build_block(&body_src, idx + 1, options),
+            span: item_start..consumed,
+        }),
+        skip_ws(src, consumed),
+    ))
+}
 
-            if trimmed.starts_with("let ") {
-                let (brace_delta, _, _) = nesting_deltas(trimmed);
-                if brace_delta > 0 && !trimmed.contains('}') {
-                    buffer.push_str(trimmed);
-                    brace_balance = brace_delta;
-                    continue;
-                }
-                statements.push(parse_statement(trimmed));
-                continue;
+/// `config { key: value }`; entries are separated by newlines or commas.
+fn parse_config_decl(src: &str, start: usize) -> Option<(ast::Item, usize)> {
+    let item_start = skip_doc_comments(src, start);
+    if !starts_with_keyword(src, item_start, "config") {
+        return None;
+    }
+    let idx = skip_ws(src, item_start + "config".len());
+    if !src[idx..].starts_with('{') {
+        return None;
+    }
+    let (_, consumed) = extract_balanced(src, idx, '{', '}')?;
+    let body = &src[idx + 1..consumed - 1];
+    let entries = split_statements(body)
+        .into_iter()
+        .flat_map(split_args)
+        .map(|entry| {
+            let base = idx + 1 + offset_in(body, entry);
+            let colon = find_top_level(entry, ":")?;
+            let key = entry[..colon].trim();
+            if ident_at(key, 0) != Some(key) {
+                return None;
             }
+            let value = entry[colon + 1..].trim();
+            Some(ast::ConfigEntry {
+                key: key.to_string(),
+                value: parse_expression(value, base + offset_in(entry, value)),
+                span: base..base + entry.len(),
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some((
+        ast::Item::Config(ast::ConfigDecl {
+            entries,
+            span: item_start..consumed,
+        }),
+        skip_ws(src, consumed),
+    ))
+}
 
-            if trimmed == "{" || trimmed == "}" {
-                continue;
-            }
+/// `secret NAME` or `secret NAME from source`, ending at the end of the line
+/// or at `;`.
+fn parse_secret_decl(src: &str, start: usize) -> Option<(ast::Item, usize)> {
+    let item_start = skip_doc_comments(src, start);
+    if !starts_with_keyword(src, item_start, "secret") {
+        return None;
+    }
+    let idx = skip_ws(src, item_start + "secret".len());
+    let (name, idx) = take_ident(src, idx)?;
+    let end = declaration_end(src, idx);
+    let rest = src[idx..end].trim();
+    let source = match strip_keyword(rest, "from").map(str::trim) {
+        Some(source) if !source.is_empty() => Some(parse_expression(
+            source,
+            idx + offset_in(&src[idx..], source),
+        )),
+        None if rest.is_empty() => None,
+        _ => return None,
+    };
+    let span_end = idx + src[idx..end].trim_end().len();
+    let next = if src[end..].starts_with(';') {
+        end + 1
+    } else {
+        end
+    };
+    Some((
+        ast::Item::Secret(ast::SecretDecl {
+            name,
+            source,
+            span: item_start..span_end,
+        }),
+        skip_ws(src, next),
+    ))
+}
+
+fn parse_extension_item(
+    src: &str,
+    start: usize,
+    options: &ParseOptions,
+) -> Result<Option<(ast::Item, usize)>, HiloParseError> {
+    if options.extensions.is_empty() {
+        return Ok(None);
+    }
+    let item_start = skip_doc_comments(src, start);
+    let Some((keyword, idx)) = take_ident(src, item_start) else {
+        return Ok(None);
+    };
+    let Some(handler) = options.extensions.get(&keyword) else {
+        return Ok(None);
+    };
+    let Some(open) = src[idx..].find('{').map(|pos| idx + pos) else {
+        return Ok(None);
+    };
+    let Some((body, consumed)) = extract_balanced(src, open, '{', '}') else {
+        return Ok(None);
+    };
+    let header = src[idx..open].trim();
+    let payload = handler(&keyword, header, &body)
+        .map_err(|msg| HiloParseError::Parse(format!("`{keyword}` item: {msg}")))?;
+    Ok(Some((
+        ast::Item::Extension(ast::ExtensionItem {
+            keyword,
+            header: header.to_string(),
+            body,
+            payload,
+            span: item_start..consumed,
+        }),
+        skip_ws(src, consumed),
+    )))
+}
 
-            statements.push(parse_statement(trimmed));
+/// Builds the block for `body_src`, which starts at byte `base` of the source.
+fn build_block(body_src: &str, base: usize, options: &ParseOptions) -> ast::Block {
+    let mut block = unparsed_block(body_src, base);
+    parse_unless_lazy(&mut block, options);
+    block
+}
+
+/// A block over `body_src` whose statements have not been parsed yet, so
+/// callers can lift clauses out of it first.
+fn unparsed_block(body_src: &str, base: usize) -> ast::Block {
+    let raw = body_src.trim();
+    let start = base + offset_in(body_src, raw);
+    ast::Block {
+        raw: raw.to_string(),
+        statements: Vec::new(),
+        span: start..start + raw.len(),
+        lifted: Vec::new(),
+    }
+}
+
+fn parse_unless_lazy(block: &mut ast::Block, options: &ParseOptions) {
+    if !options.lazy_bodies {
+        block.statements = block.parse();
+    }
+}
+
+/// Parses the statements of a block body, skipping those whose spans are in
+/// `lifted`. Annotations decorate the next statement, or belong to it when it
+/// is a nested `fn`; any left at the end of the body stay raw.
+pub(crate) fn parse_block_statements(
+    body_src: &str,
+    base: usize,
+    lifted: &[ast::Span],
+) -> Vec<ast::Statement> {
+    let mut statements = Vec::new();
+    let mut pending: Vec<ast::Annotation> = Vec::new();
+    for stmt in split_statements(body_src) {
+        let stmt_base = base + offset_in(body_src, stmt);
+        if lifted.contains(&(stmt_base..stmt_base + stmt.len())) {
             continue;
         }
+        let (annotations, next) = parse_annotations(stmt, 0, stmt_base);
+        pending.extend(annotations);
+        let rest = stmt[next..].trim_start();
+        if rest.is_empty() {
+            continue;
+        }
+        let statement = parse_statement(rest, stmt_base + offset_in(stmt, rest));
+        statements.push(match statement {
+            ast::Statement::FnDecl(mut decl) => {
+                decl.annotations = std::mem::take(&mut pending);
+                ast::Statement::FnDecl(decl)
+            }
+            statement if pending.is_empty() => statement,
+            statement => ast::Statement::Annotated {
+                annotations: std::mem::take(&mut pending),
+                statement: Box::new(statement),
+            },
+        });
+    }
+    if let (Some(first), Some(last)) = (pending.first(), pending.last()) {
+        let text = &body_src[first.span.start - base..last.span.end - base];
+        statements.push(ast::Statement::Expr(ast::Expression::Raw(text.to_string())));
+    }
+    statements
+}
+
+/// Byte offset of `inner` within `outer`, which must contain it.
+fn offset_in(outer: &str, inner: &str) -> usize {
+    inner.as_ptr() as usize - outer.as_ptr() as usize
+}
 
-        buffer.push(' ');
-        buffer.push_str(trimmed);
-        let (brace_delta, _, _) = nesting_deltas(trimmed);
-        brace_balance += brace_delta;
-        if brace_balance <= 0 {
-            statements.push(parse_statement(&buffer));
-            buffer.clear();
-            brace_balance = 0;
+/// Splits a block body into statement slices.
+///
+/// Newlines and `;` end a statement only outside of `()`, `[]`, and `{}`, so
+/// nested blocks and bracketed literals stay whole. A newline also does not end
+/// a statement when the line ends with a binary operator (`total +`) or the next
+/// line starts with one (`|> map(...)`, `.field`, `else`). String literals and
+/// comments are skipped; comments never become statements.
+fn split_statements(src: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut idx = 0;
+    while idx < src.len() {
+        let rest = &src[idx..];
+        if depth == 0 && (rest.starts_with("//") || rest.starts_with("/*")) {
+            let next = skip_comment_or_string(src, idx).unwrap_or(src.len());
+            if src[start..idx].trim().is_empty() {
+                start = next;
+            } else if rest.starts_with("//") && !statement_continues(src, &src[start..idx], next) {
+                push_statement(&mut statements, &src[start..idx]);
+                start = next;
+            }
+            idx = next;
+            continue;
+        }
+        if let Some(next) = skip_comment_or_string(src, idx) {
+            idx = next;
+            continue;
+        }
+        let Some(ch) = peek_char(src, idx) else {
+            break;
+        };
+        match ch {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ';' if depth == 0 => {
+                push_statement(&mut statements, &src[start..idx]);
+                start = idx + 1;
+            }
+            '\n' if depth == 0 && !statement_continues(src, &src[start..idx], idx + 1) => {
+                push_statement(&mut statements, &src[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
         }
+        idx += ch.len_utf8();
     }
+    push_statement(&mut statements, &src[start..]);
+    statements
+}
 
-    if !buffer.trim().is_empty() {
-        statements.push(parse_statement(&buffer));
+const TRAILING_CONTINUATIONS: &[&str] = &[
+    "|>", "&&", "||", "??", "=", "+", "-", "*", "/", "%", ".", ",", "->", "=>",
+];
+const LEADING_CONTINUATIONS: &[&str] = &["?.", ".", "|>", "&&", "||", "??", "->", "=>"];
+/// Arithmetic operators continue the previous line only when followed by
+/// whitespace, so a unary `-x` or a `//` comment still starts a new statement.
+const LEADING_ARITHMETIC: &[&str] = &["+", "-", "*", "/", "%"];
+const CONTINUATION_KEYWORDS: &[&str] = &["and", "or", "else", "catch", "finally"];
+
+/// Whether the statement text so far carries on past the line break before `next`.
+fn statement_continues(src: &str, pending: &str, next: usize) -> bool {
+    let mut pending = pending.trim_end();
+    while pending.ends_with("*/")
+        && let Some(open) = pending.rfind("/*")
+    {
+        pending = pending[..open].trim_end();
+    }
+    if pending.trim_start().is_empty() {
+        return false;
     }
+    if TRAILING_CONTINUATIONS
+        .iter()
+        .any(|op| pending.ends_with(op))
+    {
+        return true;
+    }
+    let last_word = pending
+        .rsplit(|c: char| !is_ident_continue(Some(c)))
+        .next()
+        .unwrap_or_default();
+    if matches!(last_word, "and" | "or") {
+        return true;
+    }
+
+    let idx = skip_ws(src, next);
+    let rest = &src[idx..];
+    LEADING_CONTINUATIONS.iter().any(|op| rest.starts_with(op))
+        || LEADING_ARITHMETIC.iter().any(|op| {
+            rest.strip_prefix(op)
+                .is_some_and(|after| after.starts_with(char::is_whitespace))
+        })
+        || CONTINUATION_KEYWORDS
+            .iter()
+            .any(|keyword| starts_with_keyword(src, idx, keyword))
+}
 
-    ast::Block { raw, statements }
+fn push_statement<'a>(statements: &mut Vec<&'a str>, text: &'a str) {
+    let text = text.trim();
+    if !text.is_empty() {
+        statements.push(text);
+    }
 }
 
-fn nesting_deltas(line: &str) -> (i32, i32, i32) {
-    let mut brace = 0;
-    let mut bracket = 0;
-    let mut paren = 0;
-    let mut in_string = false;
-    let mut escape = false;
-    for ch in line.chars() {
-        if in_string {
-            if escape {
-                escape = false;
-                continue;
+fn max_nesting_depth(src: &str) -> usize {
+    let mut depth = 0usize;
+    let mut max = 0usize;
+    let mut idx = 0;
+    while idx < src.len() {
+        if let Some(next) = skip_comment_or_string(src, idx) {
+            idx = next;
+            continue;
+        }
+        let Some(ch) = peek_char(src, idx) else {
+            break;
+        };
+        match ch {
+            '{' | '[' | '(' => {
+                depth += 1;
+                max = max.max(depth);
+            }
+            '}' | ']' | ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        idx += ch.len_utf8();
+    }
+    max
+}
+
+fn collect_comments(src: &str) -> Vec<ast::Comment> {
+    let mut comments = Vec::new();
+    let mut idx = 0;
+    while idx < src.len() {
+        let rest = &src[idx..];
+        let kind = if rest.starts_with("///") {
+            Some(ast::CommentKind::Doc)
+        } else if rest.starts_with("//") {
+            Some(ast::CommentKind::Line)
+        } else if rest.starts_with("/*") {
+            Some(ast::CommentKind::Block)
+        } else {
+            None
+        };
+        if let Some(kind) = kind {
+            let end = match kind {
+                ast::CommentKind::Block => skip_block_comment(src, idx + 2),
+                _ => {
+                    let end = skip_line_comment(src, idx + 2);
+                    if src[..end].ends_with('\n') {
+                        end - 1
+                    } else {
+                        end
+                    }
+                }
+            };
+            comments.push(ast::Comment {
+                kind,
+                text: src[idx..end].trim_end().to_string(),
+                span: idx..end,
+            });
+            idx = end;
+            continue;
+        }
+        if let Some(next) = skip_comment_or_string(src, idx) {
+            idx = next;
+            continue;
+        }
+        idx += peek_char(src, idx).map_or(1, char::len_utf8);
+    }
+    comments
+}
+
+/// Opens and closes a multi-line string literal.
+const TRIPLE_QUOTE: &str = "\"\"\"";
+
+/// Returns the offset just past a comment or string literal starting at `idx`.
+pub(crate) fn skip_comment_or_string(src: &str, idx: usize) -> Option<usize> {
+    let rest = &src[idx..];
+    if rest.starts_with("//") {
+        Some(skip_line_comment(src, idx + 2))
+    } else if rest.starts_with("/*") {
+        Some(skip_block_comment(src, idx + 2))
+    } else if rest.starts_with(TRIPLE_QUOTE) {
+        let mut end = idx + 3;
+        let mut escape = false;
+        while let Some(ch) = peek_char(src, end) {
+            if !escape && src[end..].starts_with(TRIPLE_QUOTE) {
+                return Some(end + 3);
             }
+            end += ch.len_utf8();
+            escape = !escape && ch == '\\';
+        }
+        Some(end)
+    } else if rest.starts_with('"') {
+        let mut end = idx + 1;
+        let mut escape = false;
+        while let Some(ch) = peek_char(src, end) {
+            end += ch.len_utf8();
             match ch {
+                _ if escape => escape = false,
                 '\\' => escape = true,
-                '"' => in_string = false,
+                '"' => break,
                 _ => {}
             }
+        }
+        Some(end)
+    } else {
+        None
+    }
+}
+
+/// Whether a comment or string literal opened in `src[start..end]` is still
+/// open at `end`.
+pub(crate) fn ends_inside_comment_or_string(src: &str, start: usize, end: usize) -> bool {
+    let mut idx = start;
+    while let Some(ch) = peek_char(src, idx).filter(|_| idx < end) {
+        match skip_comment_or_string(src, idx) {
+            Some(next) if next > end => return true,
+            Some(next) => idx = next,
+            None => idx += ch.len_utf8(),
+        }
+    }
+    false
+}
+
+fn parse_statement(line: &str, base: usize) -> ast::Statement {
+    if let Some(rest) = strip_keyword(line, "let") {
+        let rest = rest.trim();
+        return parse_let_statement(rest, base + offset_in(line, rest));
+    }
+    if let Some(rest) = strip_keyword(line, "return") {
+        let value = rest.trim();
+        return ast::Statement::Return {
+            value: if value.is_empty() {
+                None
+            } else {
+                Some(parse_expression(value, base + offset_in(line, value)))
+            },
+        };
+    }
+    if let Some(rest) = strip_keyword(line, "assert") {
+        let condition = rest.trim();
+        if !condition.is_empty() {
+            return ast::Statement::Assert(parse_expression(
+                condition,
+                base + offset_in(line, condition),
+            ));
+        }
+    }
+    if let Some(rest) = strip_keyword(line, "throw") {
+        let value = rest.trim();
+        if !value.is_empty() {
+            return ast::Statement::Throw(parse_expression(value, base + offset_in(line, value)));
+        }
+    }
+    if let Some(keyword) = method_keyword(line, 0)
+        && let Some((decl, end)) = parse_callable(line, 0, base, keyword, &ParseOptions::default())
+        && end == line.len()
+    {
+        return ast::Statement::FnDecl(decl);
+    }
+    if let Some(statement) = parse_for_statement(line, base) {
+        return statement;
+    }
+    if let Some(statement) = parse_while_statement(line, base) {
+        return statement;
+    }
+    if let Some(statement) = parse_try_statement(line, base) {
+        return statement;
+    }
+    if let Some(statement) = parse_guard_statement(line, base) {
+        return statement;
+    }
+    if let Some(statement) = parse_parallel_statement(line, base) {
+        return statement;
+    }
+    if let Some(statement) = parse_branch_statement(line, base) {
+        return statement;
+    }
+    if let Some((label, body)) = parse_block_statement(line) {
+        return ast::Statement::Block {
+            label,
+            body: build_block(body, base + offset_in(line, body), &ParseOptions::default()),
+        };
+    }
+    ast::Statement::Expr(parse_expression(line, base))
+}
+
+/// Recognises `branch on subject { pattern => step, else => step }`. Arms are
+/// separated by newlines or commas, and every target must be a plain name.
+fn parse_branch_statement(line: &str, base: usize) -> Option<ast::Statement> {
+    let rest = strip_keyword(line, "branch")?;
+    let on = line.len() - rest.trim_start().len();
+    let rest = strip_keyword(&line[on..], "on")?;
+    let subject_start = line.len() - rest.len();
+    let open = trailing_block(line, subject_start)?;
+    let subject = line[subject_start..open].trim();
+    if subject.is_empty() {
+        return None;
+    }
+    let body = &line[open + 1..line.len() - 1];
+
+    let mut arms = Vec::new();
+    let mut otherwise = None;
+    // Arms are read line by line: unlike statements, an `else` line never
+    // continues the one before it.
+    for arm in body.lines().flat_map(split_args) {
+        if skip_ws(arm, 0) == arm.len() {
+            continue;
+        }
+        let arrow = find_top_level(arm, "=>")?;
+        let head = arm[..arrow].trim();
+        let (target, end) = take_ident(arm, skip_ws(arm, arrow + 2))?;
+        if skip_ws(arm, end) != arm.len() {
+            return None;
+        }
+        if head == "else" {
+            if otherwise.replace(target).is_some() {
+                return None;
+            }
+        } else {
+            arms.push(ast::BranchArm {
+                pattern: parse_pattern(head),
+                target,
+            });
+        }
+    }
+    Some(ast::Statement::Branch {
+        subject: parse_expression(subject, base + offset_in(line, subject)),
+        arms,
+        otherwise,
+    })
+}
+
+/// Recognises `parallel { ... }`, splitting its body into branches.
+fn parse_parallel_statement(line: &str, base: usize) -> Option<ast::Statement> {
+    let rest = strip_keyword(line, "parallel")?;
+    let open = line.len() - rest.trim_start().len();
+    if !line[open..].starts_with('{') {
+        return None;
+    }
+    let (body, consumed) = extract_balanced(line, open, '{', '}')?;
+    if consumed != line.len() {
+        return None;
+    }
+    let body_base = base + open + 1;
+    let mut branches = Vec::new();
+    // Annotations on their own lines belong to the next branch.
+    let mut annotated_from = None;
+    for stmt in split_statements(&body) {
+        let start = offset_in(&body, stmt);
+        let (annotations, next) = parse_annotations(stmt, 0, 0);
+        if !annotations.is_empty() && stmt[next..].trim().is_empty() {
+            annotated_from.get_or_insert(start);
+            continue;
+        }
+        let from = annotated_from.take().unwrap_or(start);
+        let text = &body[from..start + stmt.len()];
+        branches.push(match parse_block_statement(text) {
+            Some((None, inner)) => build_block(
+                inner,
+                body_base + from + offset_in(text, inner),
+                &ParseOptions::default(),
+            ),
+            _ => build_block(text, body_base + from, &ParseOptions::default()),
+        });
+    }
+    Some(ast::Statement::Parallel(branches))
+}
+
+/// Recognises `{ ... }` and `label { ... }` statements.
+///
+/// A labelled block whose body opens with `name:` is left to the expression
+/// parser, since it reads as a struct literal.
+fn parse_block_statement(line: &str) -> Option<(Option<ast::Ident>, &str)> {
+    let (label, open) = match take_ident(line, 0) {
+        Some((label, idx)) => {
+            if is_reserved_keyword(&label) {
+                return None;
+            }
+            (Some(label), skip_ws_spaces(line, idx))
+        }
+        None => (None, 0),
+    };
+    if !line[open..].starts_with('{') {
+        return None;
+    }
+    let (_, consumed) = extract_balanced(line, open, '{', '}')?;
+    if consumed != line.len() {
+        return None;
+    }
+    let body = &line[open + 1..consumed - 1];
+    if label.is_some() && looks_like_struct_body(body) {
+        return None;
+    }
+    Some((label, body))
+}
+
+/// Recognises `for item in items { ... }` and its
+/// `for item in items parallel(max: limit) { ... }` form.
+///
+/// The iterable runs up to the body, or up to the last `parallel` clause at
+/// bracket depth 0, so calls and literals in it may span several tokens.
+fn parse_for_statement(line: &str, base: usize) -> Option<ast::Statement> {
+    let rest = strip_keyword(line, "for")?;
+    let idx = skip_ws_spaces(line, line.len() - rest.len());
+    let (binding, idx) = take_ident(line, idx)?;
+    let idx = skip_ws_spaces(line, idx);
+    if !starts_with_keyword(line, idx, "in") {
+        return None;
+    }
+    let iterable_start = idx + "in".len();
+    let open = trailing_block(line, iterable_start)?;
+    let clause = last_top_level_keyword(&line[..open], iterable_start, "parallel");
+    let iterable = line[iterable_start..clause.unwrap_or(open)].trim();
+    if iterable.is_empty() {
+        return None;
+    }
+    let iterable = parse_expression(iterable, base + offset_in(line, iterable));
+    let body = build_block(
+        &line[open + 1..line.len() - 1],
+        base + open + 1,
+        &ParseOptions::default(),
+    );
+
+    let Some(clause) = clause else {
+        return Some(ast::Statement::For {
+            binding,
+            iterable,
+            body,
+        });
+    };
+    let args = line[clause + "parallel".len()..open].trim();
+    let limit = if args.is_empty() {
+        None
+    } else {
+        let args = args.strip_prefix('(')?.strip_suffix(')')?;
+        let value = args.trim().strip_prefix("max")?.trim_start();
+        let value = value.strip_prefix(':')?;
+        Some(parse_expression(value, base + offset_in(line, value)))
+    };
+    Some(ast::Statement::ParallelFor {
+        binding,
+        iterable,
+        limit,
+        body,
+    })
+}
+
+/// Recognises `while condition { ... }`.
+fn parse_while_statement(line: &str, base: usize) -> Option<ast::Statement> {
+    let rest = strip_keyword(line, "while")?;
+    let condition_start = line.len() - rest.len();
+    let open = trailing_block(line, condition_start)?;
+    let condition = line[condition_start..open].trim();
+    if condition.is_empty() {
+        return None;
+    }
+    Some(ast::Statement::While {
+        condition: parse_expression(condition, base + offset_in(line, condition)),
+        body: build_block(
+            &line[open + 1..line.len() - 1],
+            base + open + 1,
+            &ParseOptions::default(),
+        ),
+    })
+}
+
+/// Recognises `guard condition else { ... }`, splitting at the last `else`
+/// outside brackets.
+fn parse_guard_statement(line: &str, base: usize) -> Option<ast::Statement> {
+    let rest = strip_keyword(line, "guard")?;
+    let condition_start = line.len() - rest.len();
+    let open = trailing_block(line, condition_start)?;
+    let clause = last_top_level_keyword(&line[..open], condition_start, "else")?;
+    if !line[clause + "else".len()..open].trim().is_empty() {
+        return None;
+    }
+    let condition = line[condition_start..clause].trim();
+    if condition.is_empty() {
+        return None;
+    }
+    Some(ast::Statement::Guard {
+        condition: parse_expression(condition, base + offset_in(line, condition)),
+        else_body: build_block(
+            &line[open + 1..line.len() - 1],
+            base + open + 1,
+            &ParseOptions::default(),
+        ),
+    })
+}
+
+/// Recognises `try { ... }` followed by any `catch (e: Type) { ... }` clauses
+/// and an optional `finally { ... }`, at least one of which must be present.
+fn parse_try_statement(line: &str, base: usize) -> Option<ast::Statement> {
+    let rest = strip_keyword(line, "try")?;
+    let (body, mut idx) = block_at(line, skip_ws(line, line.len() - rest.len()), base)?;
+    let mut catches = Vec::new();
+    loop {
+        idx = skip_ws(line, idx);
+        if !starts_with_keyword(line, idx, "catch") {
+            break;
+        }
+        idx = skip_ws(line, idx + "catch".len());
+        let (binding, ty) = if line[idx..].starts_with('(') {
+            let (inner, end) = extract_balanced(line, idx, '(', ')')?;
+            let (name, ty) = match inner.split_once(':') {
+                Some((name, ty)) => (name.trim(), Some(parse_type_expr(ty.trim()))),
+                None => (inner.trim(), None),
+            };
+            if ident_at(name, 0) != Some(name) {
+                return None;
+            }
+            idx = skip_ws(line, end);
+            (Some(name.to_string()), ty)
+        } else {
+            (None, None)
+        };
+        let (body, end) = block_at(line, idx, base)?;
+        catches.push(ast::CatchClause { binding, ty, body });
+        idx = end;
+    }
+    let finally = if starts_with_keyword(line, idx, "finally") {
+        let (block, end) = block_at(line, skip_ws(line, idx + "finally".len()), base)?;
+        idx = end;
+        Some(block)
+    } else {
+        None
+    };
+    (idx == line.len() && (!catches.is_empty() || finally.is_some())).then_some(
+        ast::Statement::Try {
+            body,
+            catches,
+            finally,
+        },
+    )
+}
+
+/// The `{ ... }` block opening at `idx` of `line`, and the offset just past it.
+fn block_at(line: &str, idx: usize, base: usize) -> Option<(ast::Block, usize)> {
+    let (_, end) = extract_balanced(line, idx, '{', '}')?;
+    let body = build_block(
+        &line[idx + 1..end - 1],
+        base + idx + 1,
+        &ParseOptions::default(),
+    );
+    Some((body, end))
+}
+
+/// Offset of the `{` opening the block that ends `line`, scanning from
+/// `from`. Brackets inside strings and comments are ignored.
+fn trailing_block(line: &str, from: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut open = None;
+    let mut idx = from;
+    while idx < line.len() {
+        if let Some(next) = skip_comment_or_string(line, idx) {
+            idx = next;
             continue;
         }
+        let ch = peek_char(line, idx)?;
         match ch {
-            '"' => in_string = true,
-            '{' => brace += 1,
-            '}' => brace -= 1,
-            '[' => bracket += 1,
-            ']' => bracket -= 1,
-            '(' => paren += 1,
-            ')' => paren -= 1,
+            '{' if depth == 0 => {
+                open = Some(idx);
+                depth += 1;
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
             _ => {}
         }
+        idx += ch.len_utf8();
+    }
+    (depth == 0 && line.ends_with('}')).then_some(open?)
+}
+
+/// Offset of the last `keyword` in `src[from..]` that stands alone at bracket
+/// depth 0.
+fn last_top_level_keyword(src: &str, from: usize, keyword: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut found = None;
+    let mut idx = from;
+    while idx < src.len() {
+        if let Some(next) = skip_comment_or_string(src, idx) {
+            idx = next;
+            continue;
+        }
+        let ch = peek_char(src, idx)?;
+        match ch {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ if depth == 0
+                && src[..idx].ends_with(char::is_whitespace)
+                && starts_with_keyword(src, idx, keyword) =>
+            {
+                found = Some(idx);
+            }
+            _ => {}
+        }
+        idx += ch.len_utf8();
+    }
+    found
+}
+
+fn looks_like_struct_body(body: &str) -> bool {
+    let idx = skip_ws(body, 0);
+    let Some((_, idx)) = take_ident(body, idx) else {
+        return false;
+    };
+    let idx = skip_ws_spaces(body, idx);
+    let idx = if body[idx..].starts_with('?') {
+        idx + 1
+    } else {
+        idx
+    };
+    body[idx..].starts_with(':')
+}
+
+fn strip_keyword<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+    if starts_with_keyword(line, 0, keyword) {
+        Some(&line[keyword.len()..])
+    } else {
+        None
     }
-    (brace, bracket, paren)
 }
 
-fn parse_statement(line: &str) -> ast::Statement {
-    if let Some(rest) = line.strip_prefix("let ") {
-        return parse_let_statement(rest.trim());
-    }
-    if let Some(rest) = line.strip_prefix("return") {
-        let value = rest.trim();
-        return ast::Statement::Return {
-            value: if value.is_empty() {
-                None
-            } else {
-                Some(parse_expression(value))
-            },
-        };
-    }
-    ast::Statement::Expr(parse_expression(line))
+/// Reserved words from `hilo/spec/LANGUAGE_SPEC.md` section 2.
+pub(crate) const RESERVED_KEYWORDS: &[&str] = &[
+    "module",
+    "import",
+    "as",
+    "from",
+    "export",
+    "const",
+    "let",
+    "var",
+    "func",
+    "return",
+    "async",
+    "await",
+    "yield",
+    "record",
+    "enum",
+    "type",
+    "trait",
+    "interface",
+    "class",
+    "extends",
+    "new",
+    "prop",
+    "if",
+    "else",
+    "for",
+    "while",
+    "match",
+    "in",
+    "break",
+    "continue",
+    "try",
+    "catch",
+    "throw",
+    "defer",
+    "using",
+    "agent",
+    "policy",
+    "tools",
+    "capabilities",
+    "profile",
+    "task",
+    "workflow",
+    "test",
+    "hook",
+    "true",
+    "false",
+    "null",
+    "and",
+    "or",
+    "not",
+    "spawn",
+    "channel",
+    "send",
+    "recv",
+    "select",
+    "timeout",
+    "with",
+    "where",
+    "impl",
+    "pub",
+    "internal",
+];
+
+fn is_reserved_keyword(word: &str) -> bool {
+    RESERVED_KEYWORDS.contains(&word)
 }
 
-fn parse_let_statement(rest: &str) -> ast::Statement {
+fn parse_let_statement(rest: &str, base: usize) -> ast::Statement {
     let mut name_part = rest;
     let mut value_part = None;
     if let Some((lhs, rhs)) = rest.split_once('=') {
         name_part = lhs.trim();
-        value_part = Some(rhs.trim().to_string());
+        value_part = Some(rhs.trim());
     }
 
     let (name, ty) = if let Some((name, ty_str)) = name_part.split_once(':') {
@@ -451,146 +2088,284 @@ fn parse_let_statement(rest: &str) -> ast::Statement {
     ast::Statement::Let {
         name,
         ty,
-        value: value_part.map(|v| parse_expression(&v)),
+        value: value_part.map(|value| parse_expression(value, base + offset_in(rest, value))),
     }
 }
 
-fn parse_expression(src: &str) -> ast::Expression {
-    let trimmed = src.trim();
+/// Parses `src`, which starts at byte `base` of the module source; blocks
+/// nested in the expression get absolute spans from it.
+fn parse_expression(src: &str, base: usize) -> ast::Expression {
+    let code = strip_comments(src);
+    let trimmed = code.trim();
     if trimmed.is_empty() {
         return ast::Expression::Raw(String::new());
     }
-    if let Some((type_name, fields)) = parse_struct_literal(trimmed) {
-        return ast::Expression::StructLiteral {
-            type_name,
-            fields: fields
-                .into_iter()
-                .map(|(name, expr)| (name.to_string(), parse_expression(expr)))
-                .collect(),
-        };
-    }
-    if let Some((target, args)) = parse_index_expression(trimmed) {
-        return ast::Expression::Index {
-            target: Box::new(parse_expression(target)),
-            index: Box::new(parse_expression(args)),
-        };
-    }
-    if let Some((target, args)) = parse_call_expression(trimmed) {
-        return ast::Expression::Call {
-            target: Box::new(parse_expression(target)),
-            args: args.into_iter().map(parse_expression).collect(),
-        };
-    }
-    if let Some((left, op, right)) = parse_binary_expression(trimmed) {
-        return ast::Expression::Binary {
-            left: Box::new(parse_expression(left)),
-            op: op.to_string(),
-            right: Box::new(parse_expression(right)),
-        };
-    }
-    if let Some((target, property)) = parse_optional_chain(trimmed) {
-        return ast::Expression::OptionalChain {
-            target: Box::new(parse_expression(target)),
-            property: property.to_string(),
-        };
-    }
-    if let Some((target, property)) = parse_member_expression(trimmed) {
-        return ast::Expression::Member {
-            target: Box::new(parse_expression(target)),
-            property: property.to_string(),
-        };
+    let at = base + offset_in(&code, trimmed);
+    if let Some(expr) = parse_match_expression(trimmed, at) {
+        return expr;
     }
-    if is_identifier(trimmed) {
-        return ast::Expression::Identifier(trimmed.to_string());
+    if let Some(expr) = parse_lambda(trimmed, at) {
+        return expr;
     }
-    if is_literal(trimmed) {
-        return ast::Expression::Literal(trimmed.to_string());
+    if let Some(expr) = expression::parse_operators(trimmed, at) {
+        return expr;
     }
     ast::Expression::Raw(trimmed.to_string())
 }
 
-fn parse_call_expression(src: &str) -> Option<(&str, Vec<&str>)> {
-    let open_paren = src.find('(')?;
-    let close_paren = src.rfind(')')?;
-    if close_paren < open_paren {
+/// Recognises `match subject { arm ... }`. Arms are separated by newlines or
+/// commas and read `pattern => value`, `pattern if guard => value`, or
+/// `pattern => { ... }`; any arm without `=>` leaves the whole match raw.
+fn parse_match_expression(src: &str, base: usize) -> Option<ast::Expression> {
+    let rest = strip_keyword(src, "match")?;
+    let subject_start = src.len() - rest.len();
+    let open = trailing_block(src, subject_start)?;
+    let subject = src[subject_start..open].trim();
+    if subject.is_empty() {
         return None;
     }
-    let target = src[..open_paren].trim();
-    if target.is_empty() {
-        return None;
+    let body = &src[open + 1..src.len() - 1];
+    let at = |inner: &str| base + offset_in(src, inner);
+
+    let mut arms = Vec::new();
+    for arm in split_statements(body).into_iter().flat_map(split_args) {
+        let arrow = find_top_level(arm, "=>")?;
+        let head = arm[..arrow].trim();
+        let value = arm[arrow + 2..].trim();
+        let (pattern, guard) = match last_top_level_keyword(head, 0, "if") {
+            Some(guard) => {
+                let condition = head[guard + "if".len()..].trim();
+                (
+                    head[..guard].trim(),
+                    Some(parse_expression(condition, at(condition))),
+                )
+            }
+            None => (head, None),
+        };
+        arms.push(ast::MatchArm {
+            pattern: parse_pattern(pattern),
+            guard,
+            body: parse_arm_body(value, at(value)),
+        });
+    }
+    Some(ast::Expression::Match {
+        subject: Box::new(parse_expression(subject, at(subject))),
+        arms,
+    })
+}
+
+/// The text after `=>`: a `{ ... }` block when braces enclose all of it,
+/// otherwise a single expression.
+fn parse_arm_body(value: &str, base: usize) -> ast::ArmBody {
+    match extract_balanced(value, 0, '{', '}') {
+        Some((_, end)) if end == value.len() => {
+            let inner = &value[1..value.len() - 1];
+            let at = base + offset_in(value, inner);
+            ast::ArmBody::Block(build_block(inner, at, &ParseOptions::default()))
+        }
+        _ => ast::ArmBody::Expr(parse_expression(value, base)),
     }
-    let args_str = &src[open_paren + 1..close_paren];
-    let args = split_args(args_str);
-    Some((target, args))
 }
 
-fn parse_struct_literal(src: &str) -> Option<(Vec<String>, Vec<(&str, &str)>)> {
-    if !src.contains('{') || !src.ends_with('}') {
+/// Recognises `(a, b: Int) => body` and the single-parameter shorthand
+/// `x => body`, where the body is an expression or a `{ ... }` block.
+fn parse_lambda(src: &str, base: usize) -> Option<ast::Expression> {
+    let arrow = find_top_level(src, "=>")?;
+    let head = src[..arrow].trim();
+    let value = src[arrow + 2..].trim();
+    if value.is_empty() {
         return None;
     }
-    let open_brace = src.find('{')?;
-    let target = src[..open_brace].trim();
-    if target.is_empty() {
-        return None;
+    let params = match extract_balanced(head, 0, '(', ')') {
+        Some((inner, end)) if end == head.len() => split_args(&inner)
+            .into_iter()
+            .map(|param| {
+                let (name, ty) = match param.split_once(':') {
+                    Some((name, ty)) => (name.trim(), Some(parse_type_expr(ty.trim()))),
+                    None => (param, None),
+                };
+                (ident_at(name, 0) == Some(name) && !is_reserved_keyword(name)).then(|| {
+                    ast::LambdaParam {
+                        name: name.to_string(),
+                        ty,
+                    }
+                })
+            })
+            .collect::<Option<Vec<_>>>()?,
+        _ if ident_at(head, 0) == Some(head) && !is_reserved_keyword(head) => {
+            vec![ast::LambdaParam {
+                name: head.to_string(),
+                ty: None,
+            }]
+        }
+        _ => return None,
+    };
+    Some(ast::Expression::Lambda {
+        params,
+        body: Box::new(parse_arm_body(value, base + offset_in(src, value))),
+    })
+}
+
+/// Offset of the first `needle` in `src` outside brackets, strings, and
+/// comments.
+fn find_top_level(src: &str, needle: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut idx = 0;
+    while idx < src.len() {
+        if let Some(next) = skip_comment_or_string(src, idx) {
+            idx = next;
+            continue;
+        }
+        if depth == 0 && src[idx..].starts_with(needle) {
+            return Some(idx);
+        }
+        let ch = peek_char(src, idx)?;
+        match ch {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        idx += ch.len_utf8();
     }
-    let type_name: Vec<String> = target
-        .split('.')
-        .map(|part| part.trim().to_string())
-        .filter(|part| !part.is_empty())
-        .collect();
-    if type_name.is_empty() {
-        return None;
+    None
+}
+
+fn parse_pattern(src: &str) -> ast::Pattern {
+    let src = src.trim();
+    if src == "_" {
+        return ast::Pattern::Wildcard;
     }
-    let body = &src[open_brace + 1..src.len() - 1];
-    let entries = split_args(body)
-        .into_iter()
-        .filter_map(|entry| entry.split_once(':'))
-        .map(|(name, expr)| (name.trim(), expr.trim()))
-        .collect::<Vec<_>>();
-    if entries.is_empty() {
-        return None;
+    if let Some(literal) = parse_literal(src) {
+        return ast::Pattern::Literal(literal);
+    }
+    if let Some(inner) = src
+        .strip_prefix('(')
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        return ast::Pattern::Tuple(split_args(inner).into_iter().map(parse_pattern).collect());
     }
-    Some((type_name, entries))
-}
 
-fn parse_index_expression(src: &str) -> Option<(&str, &str)> {
-    if !src.ends_with(']') {
-        return None;
+    let mut path = Vec::new();
+    let mut idx = 0;
+    while let Some((segment, next)) = take_ident(src, idx) {
+        path.push(segment);
+        idx = skip_ws_spaces(src, next);
+        if !src[idx..].starts_with('.') {
+            break;
+        }
+        idx = skip_ws_spaces(src, idx + 1);
     }
-    let mut depth = 0;
-    let chars: Vec<char> = src.chars().collect();
-    for (idx, ch) in chars.iter().enumerate().rev() {
-        match ch {
-            ']' => depth += 1,
-            '[' => {
-                depth -= 1;
-                if depth == 0 {
-                    let target = src[..idx].trim();
-                    let index = src[idx + 1..src.len() - 1].trim();
-                    if !target.is_empty() && !index.is_empty() {
-                        return Some((target, index));
+    if path.is_empty() {
+        return ast::Pattern::Raw(src.to_string());
+    }
+    let rest = &src[idx..];
+    if rest.is_empty() {
+        if let [name] = path.as_slice()
+            && !name.starts_with(char::is_uppercase)
+        {
+            return ast::Pattern::Binding(path.remove(0));
+        }
+        return ast::Pattern::Variant {
+            path,
+            fields: Vec::new(),
+        };
+    }
+    match extract_balanced(rest, 0, '(', ')') {
+        Some((inner, end)) if end == rest.len() => {
+            return ast::Pattern::Variant {
+                path,
+                fields: split_args(&inner).into_iter().map(parse_pattern).collect(),
+            };
+        }
+        _ => {}
+    }
+    match extract_balanced(rest, 0, '{', '}') {
+        Some((inner, end)) if end == rest.len() => {
+            let fields = split_args(&inner)
+                .into_iter()
+                .map(|field| match field.split_once(':') {
+                    Some((name, pattern)) => {
+                        (name.trim().to_string(), Some(parse_pattern(pattern)))
                     }
-                }
+                    None => (field.to_string(), None),
+                })
+                .collect();
+            ast::Pattern::Record { name: path, fields }
+        }
+        _ => ast::Pattern::Raw(src.to_string()),
+    }
+}
+
+/// Replaces comments with whitespace, leaving string literals untouched.
+///
+/// Line breaks are kept and every other comment byte becomes a space, so
+/// offsets into the result match offsets into `src`.
+fn strip_comments(src: &str) -> Cow<'_, str> {
+    if !src.contains("//") && !src.contains("/*") {
+        return Cow::Borrowed(src);
+    }
+    let mut out = String::with_capacity(src.len());
+    let mut copied = 0;
+    let mut idx = 0;
+    while idx < src.len() {
+        let rest = &src[idx..];
+        let is_comment = rest.starts_with("//") || rest.starts_with("/*");
+        match skip_comment_or_string(src, idx) {
+            Some(next) if is_comment => {
+                out.push_str(&src[copied..idx]);
+                out.extend(
+                    src[idx..next]
+                        .bytes()
+                        .map(|byte| if byte == b'\n' { '\n' } else { ' ' }),
+                );
+                copied = next;
+                idx = next;
             }
-            _ => {}
+            Some(next) => idx = next,
+            None => idx += peek_char(src, idx).map_or(1, char::len_utf8),
         }
     }
-    None
+    out.push_str(&src[copied..]);
+    Cow::Owned(out)
+}
+
+/// Copy of `src` with every byte of each string literal and comment replaced
+/// by `_`.
+///
+/// Structural scans run over the mask so quoted or commented commas, braces,
+/// and operators are ignored, while byte offsets still index the original text.
+fn mask_strings(src: &str) -> Cow<'_, str> {
+    if !src.contains(['"', '/']) {
+        return Cow::Borrowed(src);
+    }
+    let mut out = String::with_capacity(src.len());
+    let mut copied = 0;
+    let mut idx = 0;
+    while idx < src.len() {
+        let rest = &src[idx..];
+        if rest.starts_with('"') || rest.starts_with("//") || rest.starts_with("/*") {
+            let end = skip_comment_or_string(src, idx).unwrap_or(src.len());
+            out.push_str(&src[copied..idx]);
+            out.extend(std::iter::repeat_n('_', end - idx));
+            copied = end;
+            idx = end;
+            continue;
+        }
+        idx += peek_char(src, idx).map_or(1, char::len_utf8);
+    }
+    out.push_str(&src[copied..]);
+    Cow::Owned(out)
 }
 
 fn split_args(src: &str) -> Vec<&str> {
+    let code = mask_strings(src);
     let mut args = Vec::new();
     let mut depth = 0;
     let mut start = 0;
-    let chars: Vec<char> = src.chars().collect();
-    for (idx, ch) in chars.iter().enumerate() {
+    for (idx, ch) in code.char_indices() {
         match ch {
             '(' | '{' | '[' => depth += 1,
-            ')' | '}' | ']' => {
-                if depth > 0 {
-                    depth -= 1
-                }
-            }
+            ')' | '}' | ']' if depth > 0 => depth -= 1,
             ',' if depth == 0 => {
                 args.push(src[start..idx].trim());
                 start = idx + 1;
@@ -605,146 +2380,411 @@ fn split_args(src: &str) -> Vec<&str> {
     args
 }
 
-fn parse_member_expression(src: &str) -> Option<(&str, &str)> {
-    let mut depth = 0;
-    let chars: Vec<char> = src.chars().collect();
-    for (idx, ch) in chars.iter().enumerate().rev() {
-        match ch {
-            ')' | ']' | '}' => depth += 1,
-            '(' | '[' | '{' => depth -= 1,
-            '.' if depth == 0 => {
-                let target = src[..idx].trim();
-                let property = src[idx + 1..].trim();
-                if !target.is_empty() && is_identifier(property) {
-                    return Some((target, property));
+fn parse_literal(s: &str) -> Option<ast::Literal> {
+    match s {
+        "true" => return Some(ast::Literal::Bool(true)),
+        "false" => return Some(ast::Literal::Bool(false)),
+        "null" => return Some(ast::Literal::Null),
+        _ => {}
+    }
+    if s.starts_with(TRIPLE_QUOTE) {
+        return (skip_comment_or_string(s, 0) == Some(s.len()) && s.len() >= 6)
+            .then(|| ast::Literal::Str(decode_string(&dedent(&s[3..s.len() - 3]))));
+    }
+    if s.starts_with('"') {
+        return (skip_comment_or_string(s, 0) == Some(s.len()) && s.len() >= 2)
+            .then(|| ast::Literal::Str(decode_string(&s[1..s.len() - 1])));
+    }
+    if !s.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') {
+        return None;
+    }
+    if let Some(duration) = parse_duration(s) {
+        return Some(ast::Literal::Duration(duration));
+    }
+    let digits: Cow<str> = if s.contains('_') {
+        Cow::Owned(s.replace('_', ""))
+    } else {
+        Cow::Borrowed(s)
+    };
+    if let Ok(value) = digits.parse::<i64>() {
+        return Some(ast::Literal::Int(value));
+    }
+    digits
+        .parse::<f64>()
+        .ok()
+        .filter(|value| !value.is_nan())
+        .map(ast::Literal::Float)
+}
+
+/// Parses `[0-9]+(ms|s|m|h|d)`.
+fn parse_duration(s: &str) -> Option<std::time::Duration> {
+    let split = s.find(|c: char| !c.is_ascii_digit())?;
+    let (digits, unit) = s.split_at(split);
+    let value: u64 = digits.parse().ok()?;
+    let seconds = match unit {
+        "ms" => return Some(std::time::Duration::from_millis(value)),
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(std::time::Duration::from_secs(value.checked_mul(seconds)?))
+}
+
+/// Body of a `"""` string as written, without the layout around it.
+///
+/// A line break right after the opening quotes and a whitespace-only last line
+/// are dropped, then the indentation common to all non-blank lines is removed.
+fn dedent(body: &str) -> String {
+    let body = body
+        .strip_prefix("\r\n")
+        .or_else(|| body.strip_prefix('\n'))
+        .unwrap_or(body);
+    let body = match body.rfind('\n') {
+        Some(last) if body[last + 1..].trim().is_empty() => body[..last].trim_end_matches('\r'),
+        _ => body,
+    };
+    let indent = body
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    body.lines()
+        .map(|line| line.get(indent..).unwrap_or_else(|| line.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Resolves `\"`, `\'`, `\\`, `\n`, `\r`, `\t`, `\0`, and `\u{...}` with one to
+/// six hex digits naming a Unicode scalar value. Other escapes, including
+/// malformed `\u`, are kept verbatim.
+fn decode_string(body: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(pos) = rest.find('\\') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+        let Some(ch) = rest.chars().next() else {
+            out.push('\\');
+            break;
+        };
+        let decoded = match ch {
+            '"' | '\'' | '\\' => Some(ch),
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            '0' => Some('\0'),
+            'u' => {
+                if let Some((scalar, len)) = decode_unicode_escape(rest) {
+                    out.push(scalar);
+                    rest = &rest[len..];
+                    continue;
                 }
+                None
+            }
+            _ => None,
+        };
+        match decoded {
+            Some(decoded) => out.push(decoded),
+            None => {
+                out.push('\\');
+                out.push(ch);
             }
-            _ => {}
         }
+        rest = &rest[ch.len_utf8()..];
     }
-    None
+    out.push_str(rest);
+    out
 }
 
-fn parse_optional_chain(src: &str) -> Option<(&str, &str)> {
-    let mut depth = 0;
-    let chars: Vec<char> = src.chars().collect();
-    for idx in (0..chars.len()).rev() {
-        match chars[idx] {
-            ')' | ']' | '}' => depth += 1,
-            '(' | '[' | '{' => depth -= 1,
-            '?' if depth == 0 && idx + 1 < chars.len() && chars[idx + 1] == '.' => {
-                let target = src[..idx].trim();
-                let property = src[idx + 2..].trim();
-                if !target.is_empty() && is_identifier(property) {
-                    return Some((target, property));
+/// Decodes `u{XXXX}` at the start of `src`, returning the character and the
+/// length of the escape after the backslash.
+fn decode_unicode_escape(src: &str) -> Option<(char, usize)> {
+    let digits = src.strip_prefix("u{")?;
+    let close = digits.find('}')?;
+    let hex = &digits[..close];
+    if hex.is_empty() || hex.len() > 6 || !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        return None;
+    }
+    let scalar = char::from_u32(u32::from_str_radix(hex, 16).ok()?)?;
+    Some((scalar, "u{".len() + close + 1))
+}
+
+fn parse_record_fields(src: &str, segments: &[ast::Span]) -> Vec<ast::RecordField> {
+    let mut fields = Vec::new();
+    for segment in segments {
+        let mut docs = Vec::new();
+        let mut annotations = Vec::new();
+        let mut idx = segment.start;
+        while idx < segment.end {
+            let line_end = src[idx..segment.end]
+                .find('\n')
+                .map_or(segment.end, |pos| idx + pos);
+            if let Some(text) = src[idx..line_end].trim_start().strip_prefix("///") {
+                docs.push(doc_line(text));
+                idx = line_end + 1;
+                continue;
+            }
+            if src[idx..line_end].trim_start().starts_with('@') {
+                let (found, next) = parse_annotations(&src[..segment.end], idx, 0);
+                if !found.is_empty() {
+                    annotations.extend(found);
+                    idx = next;
+                    continue;
                 }
             }
-            _ => {}
+            if let Some(field) =
+                parse_record_field(&src[idx..line_end], idx, &mut docs, &mut annotations)
+            {
+                fields.push(field);
+            }
+            idx = line_end + 1;
         }
     }
-    None
+    fields
 }
 
-fn parse_binary_expression(src: &str) -> Option<(&str, &str, &str)> {
-    let ops = [
-        "==", "!=", "<=", ">=", "&&", "||", "+", "-", "*", "/", "%", "<", ">",
-    ];
-    let mut depth = 0;
-    let chars: Vec<char> = src.chars().collect();
-    for idx in (0..chars.len()).rev() {
-        let ch = chars[idx];
+/// One `name?: Type = default` line starting at byte `line_start`, taking the
+/// doc lines and annotations collected since the previous field.
+fn parse_record_field(
+    line: &str,
+    line_start: usize,
+    docs: &mut Vec<String>,
+    annotations: &mut Vec<ast::Annotation>,
+) -> Option<ast::RecordField> {
+    let trimmed = line.trim();
+    if trimmed.is_empty()
+        || trimmed.starts_with("//")
+        || trimmed.starts_with("/*")
+        || trimmed.starts_with("}")
+    {
+        return None;
+    }
+    let (name_part, rest) = trimmed.split_once(':')?;
+    let mut name = name_part.trim().to_string();
+    let optional = name.ends_with('?');
+    if optional {
+        name.pop();
+    }
+    name = name.trim_end_matches('?').trim().to_string();
+    let ty_str = rest
+        .split_once('=')
+        .map(|(ty, _)| ty)
+        .unwrap_or(rest)
+        .trim()
+        .trim_end_matches(',')
+        .trim();
+    let field = trimmed.trim_end_matches(',').trim_end();
+    let start = line_start + offset_in(line, field);
+    let docs = std::mem::take(docs);
+    Some(ast::RecordField {
+        docs: (!docs.is_empty()).then(|| docs.join("\n")),
+        annotations: std::mem::take(annotations),
+        name,
+        optional,
+        ty: parse_type_expr(ty_str),
+        span: start..start + field.len(),
+    })
+}
+
+/// `pub` or `internal` at `idx`, returning the offset of the word after it.
+fn parse_visibility(src: &str, idx: usize) -> (ast::Visibility, usize) {
+    for (keyword, visibility) in [
+        ("pub", ast::Visibility::Public),
+        ("internal", ast::Visibility::Internal),
+    ] {
+        if starts_with_keyword(src, idx, keyword) {
+            return (visibility, skip_ws(src, idx + keyword.len()));
+        }
+    }
+    (ast::Visibility::Unspecified, idx)
+}
+
+/// Parses `<T, U: Bound + Other>` at `idx` if present, returning the
+/// parameters and the offset past any whitespace that follows.
+fn parse_type_params(src: &str, idx: usize) -> Option<(Vec<ast::TypeParam>, usize)> {
+    if !src[idx..].starts_with('<') {
+        return Some((Vec::new(), idx));
+    }
+    let (params_src, consumed) = extract_balanced(src, idx, '<', '>')?;
+    let params = split_args(&params_src)
+        .into_iter()
+        .map(parse_type_param)
+        .collect::<Option<_>>()?;
+    Some((params, skip_ws(src, consumed)))
+}
+
+/// Parses `where A: Mergeable[B], B: Hashable` at `idx` if present, up to the
+/// `{` that opens the body or a `retry` or `timeout` clause. Every entry must
+/// name at least one bound.
+fn parse_where_clause(src: &str, idx: usize) -> Option<(Vec<ast::TypeParam>, usize)> {
+    if !starts_with_keyword(src, idx, "where") {
+        return Some((Vec::new(), idx));
+    }
+    let start = idx + "where".len();
+    let mut end = start;
+    let mut depth = 0usize;
+    while let Some(ch) = peek_char(src, end) {
         match ch {
-            ')' | ']' | '}' => depth += 1,
-            '(' | '[' | '{' => depth -= 1,
-            _ if depth == 0 => {
-                for op in ops.iter() {
-                    if idx + 1 >= op.len() {
-                        let candidate = &src[idx + 1 - op.len()..=idx];
-                        if candidate == *op {
-                            let left = src[..idx + 1 - op.len()].trim();
-                            let right = src[idx + 1..].trim();
-                            if !left.is_empty() && !right.is_empty() {
-                                return Some((left, *op, right));
-                            }
-                        }
-                    }
-                }
-            }
+            '{' if depth == 0 => break,
+            _ if depth == 0 && starts_policy_clause(src, end) => break,
+            '(' | '[' | '<' => depth += 1,
+            ')' | ']' | '>' => depth = depth.saturating_sub(1),
             _ => {}
         }
+        end += ch.len_utf8();
     }
-    None
+    let constraints: Vec<ast::TypeParam> = split_args(&src[start..end])
+        .into_iter()
+        .map(parse_type_param)
+        .collect::<Option<_>>()?;
+    if constraints.is_empty() || constraints.iter().any(|c| c.bounds.is_empty()) {
+        return None;
+    }
+    Some((constraints, end))
+}
+
+/// Whether a `retry` or `timeout` clause starts a new word at `idx`.
+fn starts_policy_clause(src: &str, idx: usize) -> bool {
+    src[..idx].ends_with(char::is_whitespace)
+        && (starts_with_keyword(src, idx, "retry") || starts_with_keyword(src, idx, "timeout"))
+}
+
+/// Parses `timeout 45s` at `idx` if present.
+fn parse_timeout_clause(src: &str, idx: usize) -> Option<(Option<std::time::Duration>, usize)> {
+    if !starts_with_keyword(src, idx, "timeout") {
+        return Some((None, idx));
+    }
+    let start = skip_ws(src, idx + "timeout".len());
+    let end = src[start..]
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .map_or(src.len(), |len| start + len);
+    let timeout = parse_duration(&src[start..end])?;
+    Some((Some(timeout), skip_ws(src, end)))
 }
 
-fn is_identifier(s: &str) -> bool {
-    let mut chars = s.chars();
-    match chars.next() {
-        Some(ch) if ch == '_' || ch.is_alphabetic() => {
-            chars.all(|c| c == '_' || c.is_alphanumeric())
+/// Parses `retry(max: 3, backoff: exponential(2s))` at `idx` if present.
+/// Unknown or repeated arguments reject the whole declaration.
+fn parse_retry_clause(
+    src: &str,
+    idx: usize,
+    base: usize,
+) -> Option<(Option<ast::RetryPolicy>, usize)> {
+    if !starts_with_keyword(src, idx, "retry") {
+        return Some((None, idx));
+    }
+    let open = skip_ws(src, idx + "retry".len());
+    if !src[open..].starts_with('(') {
+        return None;
+    }
+    let (args, consumed) = extract_balanced(src, open, '(', ')')?;
+    let mut policy = ast::RetryPolicy {
+        max: None,
+        backoff: None,
+        span: base + idx..base + consumed,
+    };
+    for arg in split_args(&args) {
+        let colon = find_top_level(arg, ":")?;
+        let value = parse_expression(arg[colon + 1..].trim(), 0);
+        match arg[..colon].trim() {
+            "max" if policy.max.is_none() => {
+                let ast::Expression::Literal(ast::Literal::Int(max)) = value else {
+                    return None;
+                };
+                policy.max = Some(u32::try_from(max).ok()?);
+            }
+            "backoff" if policy.backoff.is_none() => {
+                policy.backoff = Some(parse_backoff(&value)?);
+            }
+            _ => return None,
         }
-        _ => false,
     }
+    if policy.max.is_none() && policy.backoff.is_none() {
+        return None;
+    }
+    Some((Some(policy), skip_ws(src, consumed)))
 }
 
-fn is_literal(s: &str) -> bool {
-    s.starts_with('"') && s.ends_with('"')
-        || s.parse::<f64>().is_ok()
-        || matches!(s, "true" | "false")
+/// `fixed(1s)`, `linear(1s)`, or `exponential(2s)`.
+fn parse_backoff(value: &ast::Expression) -> Option<ast::Backoff> {
+    let ast::Expression::Call { target, args } = value else {
+        return None;
+    };
+    let (ast::Expression::Identifier(kind), [arg]) = (target.as_ref(), args.as_slice()) else {
+        return None;
+    };
+    let (None, ast::Expression::Literal(ast::Literal::Duration(delay))) = (&arg.name, &arg.value)
+    else {
+        return None;
+    };
+    match kind.as_str() {
+        "fixed" => Some(ast::Backoff::Fixed(*delay)),
+        "linear" => Some(ast::Backoff::Linear(*delay)),
+        "exponential" => Some(ast::Backoff::Exponential(*delay)),
+        _ => None,
+    }
 }
 
-fn parse_record_fields(body: &str) -> Vec<ast::RecordField> {
-    body.lines()
-        .filter_map(|line| {
-            let trimmed = line.trim();
-            if trimmed.is_empty()
-                || trimmed.starts_with("//")
-                || trimmed.starts_with("/*")
-                || trimmed.starts_with("}")
-            {
-                return None;
-            }
-            let (name_part, rest) = trimmed.split_once(':')?;
-            let mut name = name_part.trim().to_string();
-            let optional = name.ends_with('?');
-            if optional {
-                name.pop();
+/// `T` or `T: Bound + Other`.
+fn parse_type_param(src: &str) -> Option<ast::TypeParam> {
+    let (name, bounds) = match src.split_once(':') {
+        Some((name, bounds)) => (name.trim(), split_bounds(bounds)),
+        None => (src.trim(), Vec::new()),
+    };
+    Some(ast::TypeParam {
+        name: (ident_at(name, 0) == Some(name)).then(|| name.to_string())?,
+        bounds,
+    })
+}
+
+/// `A + B[C]` as the list of types joined by top-level `+`.
+fn split_bounds(src: &str) -> Vec<ast::TypeExpr> {
+    let mut bounds = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (idx, ch) in src.char_indices() {
+        match ch {
+            '(' | '[' | '<' | '{' => depth += 1,
+            ')' | ']' | '>' | '}' => depth = depth.saturating_sub(1),
+            '+' if depth == 0 => {
+                bounds.push(parse_type_expr(&src[start..idx]));
+                start = idx + 1;
             }
-            name = name.trim_end_matches('?').trim().to_string();
-            let ty_str = rest
-                .split_once('=')
-                .map(|(ty, _)| ty)
-                .unwrap_or(rest)
-                .trim()
-                .trim_end_matches(',')
-                .trim();
-            Some(ast::RecordField {
-                name,
-                optional,
-                ty: parse_type_expr(ty_str),
-            })
-        })
-        .collect()
+            _ => {}
+        }
+    }
+    bounds.push(parse_type_expr(&src[start..]));
+    bounds
 }
 
-fn parse_params(src: &str) -> Vec<ast::Param> {
-    src.split(',')
-        .filter_map(|part| {
-            let trimmed = part.trim();
+/// Parses a parameter list whose text starts at byte `base` of the source.
+fn parse_params(src: &str, base: usize) -> Vec<ast::Param> {
+    split_args(src)
+        .into_iter()
+        .filter_map(|trimmed| {
+            let (docs, decl_start) = take_doc_comments(trimmed, 0);
+            let trimmed = &trimmed[decl_start..];
             if trimmed.is_empty() {
                 return None;
             }
             let (name_part, rest) = trimmed.split_once(':')?;
             let name = name_part.trim().to_string();
             let rest = rest.trim();
+            let start = base + offset_in(src, trimmed);
             let (ty_part, default) = if let Some((ty, default)) = rest.split_once('=') {
-                (ty.trim(), Some(default.trim().to_string()))
+                let value = parse_expression(default, base + offset_in(src, default));
+                (ty.trim(), Some(value))
             } else {
                 (rest, None)
             };
             Some(ast::Param {
+                docs,
                 name,
                 ty: parse_type_expr(ty_part),
                 default,
+                span: start..start + trimmed.len(),
             })
         })
         .collect()
@@ -802,17 +2842,41 @@ impl<'a> TypeParser<'a> {
 
         if self.peek_char() == Some('{') {
             self.idx += 1;
-            let fields = self.parse_struct_fields();
+            let mut fields = self.parse_struct_fields();
+            // `{ String: Int }`: a single capitalised key names a type, not a field.
+            if let [field] = fields.as_slice()
+                && !field.optional
+                && field.name.starts_with(char::is_uppercase)
+            {
+                let field = fields.remove(0);
+                return Some(ast::TypeExpr::Map {
+                    key: Box::new(ast::TypeExpr::Simple(vec![field.name])),
+                    value: Box::new(field.ty),
+                });
+            }
             return Some(ast::TypeExpr::Struct(fields));
         }
 
+        if self.consume('(') {
+            let mut elements = self.parse_type_arguments(')');
+            return match elements.len() {
+                0 => None,
+                1 => elements.pop(),
+                _ => Some(ast::TypeExpr::Tuple(elements)),
+            };
+        }
+
         let base = self.parse_qualified_identifier();
         if base.is_empty() {
             return None;
         }
+        let is_map = base.len() == 1 && base[0] == "Map";
 
         self.skip_ws();
         if self.consume('<') {
+            if is_map {
+                return Some(self.parse_map_arguments(base, '>'));
+            }
             let args = self.parse_type_arguments('>');
             return Some(ast::TypeExpr::Generic {
                 base,
@@ -836,6 +2900,17 @@ impl<'a> TypeParser<'a> {
                     ty
                 };
                 return Some(ast::TypeExpr::List(Box::new(elem_ty)));
+            } else if is_map {
+                return Some(self.parse_map_arguments(base, ']'));
+            } else if base.len() == 1 && base[0] == "Tuple" {
+                let elements = self.parse_type_arguments(']');
+                if elements.len() >= 2 {
+                    return Some(ast::TypeExpr::Tuple(elements));
+                }
+                return Some(ast::TypeExpr::Generic {
+                    base,
+                    arguments: elements,
+                });
             } else {
                 let args = self.parse_type_arguments(']');
                 return Some(ast::TypeExpr::Generic {
@@ -857,15 +2932,11 @@ impl<'a> TypeParser<'a> {
                 break;
             }
 
-            let mut name = self.parse_identifier();
+            let name = self.parse_identifier();
             if name.is_empty() {
                 break;
             }
-            let mut optional = false;
-            if name.ends_with('?') {
-                name = name.trim_end_matches('?').to_string();
-                optional = true;
-            }
+            let optional = self.consume('?');
 
             self.skip_ws();
             if !self.consume(':') {
@@ -889,6 +2960,35 @@ impl<'a> TypeParser<'a> {
         fields
     }
 
+    /// `K, V` or `K -> V` followed by `closing`; other argument lists stay
+    /// generic.
+    fn parse_map_arguments(&mut self, base: ast::QualifiedName, closing: char) -> ast::TypeExpr {
+        let start = self.idx;
+        if let Some(key) = self.parse_type_with_optional() {
+            self.skip_ws();
+            let separated = if self.src[self.idx..].starts_with("->") {
+                self.idx += 2;
+                true
+            } else {
+                self.consume(',')
+            };
+            if separated
+                && let Some(value) = self.parse_type_with_optional()
+                && self.consume(closing)
+            {
+                return ast::TypeExpr::Map {
+                    key: Box::new(key),
+                    value: Box::new(value),
+                };
+            }
+        }
+        self.idx = start;
+        ast::TypeExpr::Generic {
+            base,
+            arguments: self.parse_type_arguments(closing),
+        }
+    }
+
     fn parse_type_arguments(&mut self, closing: char) -> Vec<ast::TypeExpr> {
         let mut args = Vec::new();
         loop {
@@ -897,6 +2997,7 @@ impl<'a> TypeParser<'a> {
                 self.idx += closing.len_utf8();
                 break;
             }
+            let before = self.idx;
             let arg = self
                 .parse_type_with_optional()
                 .unwrap_or(ast::TypeExpr::Unknown(String::new()));
@@ -905,7 +3006,10 @@ impl<'a> TypeParser<'a> {
             if self.consume(closing) {
                 break;
             }
-            let _ = self.consume(',');
+            // Stop at the end of input or on text that is not a type.
+            if !self.consume(',') && self.idx == before {
+                break;
+            }
         }
         args
     }
@@ -930,11 +3034,11 @@ impl<'a> TypeParser<'a> {
         self.skip_ws();
         let start = self.idx;
         while self.idx < self.src.len() {
-            if let Some(ch) = self.peek_char() {
-                if ch == '_' || ch.is_alphanumeric() || ch == '?' {
-                    self.idx += ch.len_utf8();
-                    continue;
-                }
+            if let Some(ch) = self.peek_char()
+                && (ch == '_' || ch.is_alphanumeric())
+            {
+                self.idx += ch.len_utf8();
+                continue;
             }
             break;
         }
@@ -943,11 +3047,11 @@ impl<'a> TypeParser<'a> {
 
     fn skip_ws(&mut self) {
         while self.idx < self.src.len() {
-            if let Some(ch) = self.peek_char() {
-                if ch.is_whitespace() {
-                    self.idx += ch.len_utf8();
-                    continue;
-                }
+            if let Some(ch) = self.peek_char()
+                && ch.is_whitespace()
+            {
+                self.idx += ch.len_utf8();
+                continue;
             }
             break;
         }
@@ -976,6 +3080,51 @@ fn starts_with_keyword(src: &str, idx: usize, keyword: &str) -> bool {
     !is_ident_continue(peek_char(src, next))
 }
 
+/// The `///` lines from `idx` on, with the offset just past them. Plain
+/// comments between doc lines are skipped.
+fn take_doc_comments(src: &str, mut idx: usize) -> (Option<String>, usize) {
+    let mut lines = Vec::new();
+    loop {
+        idx = skip_ws_spaces(src, idx);
+        let rest = &src[idx..];
+        if rest.starts_with("///") {
+            let end = skip_line_comment(src, idx + 3);
+            lines.push(doc_line(&src[idx + 3..end]));
+            idx = end;
+        } else if rest.starts_with("//") {
+            idx = skip_line_comment(src, idx + 2);
+        } else if rest.starts_with("/*") {
+            idx = skip_block_comment(src, idx + 2);
+        } else {
+            break;
+        }
+    }
+    ((!lines.is_empty()).then(|| lines.join("\n")), idx)
+}
+
+/// Text of a doc line after its `///`, without the conventional leading space.
+fn doc_line(text: &str) -> String {
+    let text = text.trim_end();
+    text.strip_prefix(' ').unwrap_or(text).to_string()
+}
+
+/// Steps back from `idx` over the `///` lines directly above it, so the item
+/// scanner starts on a declaration's doc comments rather than skipping them.
+pub(crate) fn rewind_doc_comments(src: &str, idx: usize) -> usize {
+    let mut start = idx;
+    let mut end = src[..idx].trim_end().len();
+    while end > 0 {
+        let line_start = src[..end].rfind('\n').map_or(0, |pos| pos + 1);
+        let line = &src[line_start..end];
+        if !line.trim_start().starts_with("///") {
+            break;
+        }
+        start = end - line.trim_start().len();
+        end = src[..line_start].trim_end().len();
+    }
+    start
+}
+
 fn skip_doc_comments(src: &str, mut idx: usize) -> usize {
     loop {
         idx = skip_ws_spaces(src, idx);
@@ -1077,6 +3226,18 @@ fn take_ident(src: &str, start: usize) -> Option<(String, usize)> {
     Some((src[start..end].to_string(), end))
 }
 
+fn ident_at(src: &str, start: usize) -> Option<&str> {
+    let first = peek_char(src, start)?;
+    if !is_ident_start(first) {
+        return None;
+    }
+    let end = src[start..]
+        .char_indices()
+        .find(|&(_, ch)| !is_ident_continue(Some(ch)))
+        .map_or(src.len(), |(offset, _)| start + offset);
+    Some(&src[start..end])
+}
+
 fn take_string_literal(src: &str, start: usize) -> Option<(String, usize)> {
     if start >= src.len() {
         return None;
@@ -1106,6 +3267,8 @@ fn take_string_literal(src: &str, start: usize) -> Option<(String, usize)> {
     None
 }
 
+/// Content between `open` at `start` and its matching `close`, plus the offset
+/// just past `close`. Delimiters inside strings and comments do not count.
 fn extract_balanced(src: &str, start: usize, open: char, close: char) -> Option<(String, usize)> {
     if start >= src.len() || peek_char(src, start)? != open {
         return None;
@@ -1113,34 +3276,21 @@ fn extract_balanced(src: &str, start: usize, open: char, close: char) -> Option<
     let mut depth = 1;
     let mut idx = start + open.len_utf8();
     let content_start = idx;
-    let mut in_string = false;
-    let mut escape = false;
     while idx < src.len() {
-        let ch = peek_char(src, idx)?;
-        idx += ch.len_utf8();
-        if in_string {
-            if escape {
-                escape = false;
-                continue;
-            }
-            match ch {
-                '\\' => escape = true,
-                '"' => in_string = false,
-                _ => {}
-            }
+        if let Some(next) = skip_comment_or_string(src, idx) {
+            idx = next;
             continue;
         }
-        match ch {
-            '"' => in_string = true,
-            _ if ch == open => depth += 1,
-            _ if ch == close => {
-                depth -= 1;
-                if depth == 0 {
-                    let content = &src[content_start..idx - close.len_utf8()];
-                    return Some((content.to_string(), idx));
-                }
+        let ch = peek_char(src, idx)?;
+        idx += ch.len_utf8();
+        if ch == open {
+            depth += 1;
+        } else if ch == close {
+            depth -= 1;
+            if depth == 0 {
+                let content = &src[content_start..idx - close.len_utf8()];
+                return Some((content.to_string(), idx));
             }
-            _ => {}
         }
     }
     None
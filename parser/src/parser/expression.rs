@@ -0,0 +1,392 @@
+//! Precedence-climbing parser for operator expressions.
+//!
+//! The source is first split into tokens, with every bracketed group kept as a
+//! single token whose contents go back through [`parse_expression`]. A
+//! malformed call argument or struct field therefore degrades to `Raw` on its
+//! own instead of taking the surrounding expression with it.
+
+use super::*;
+
+/// Binary operators and their precedence levels, loosest first. Every binary
+/// operator is left-associative.
+///
+/// | Level | Operators             |
+/// |-------|-----------------------|
+/// | 1     | `\|\|` `or`           |
+/// | 2     | `&&` `and`            |
+/// | 3     | `==` `!=`             |
+/// | 4     | `<` `<=` `>` `>=`     |
+/// | 5     | `??`                  |
+/// | 6     | `+` `-`               |
+/// | 7     | `*` `/` `%`           |
+///
+/// Prefix operators ([`PREFIX_LEVEL`]) bind tighter than all of them, and the
+/// postfix forms (calls, indexing, member access, optional chaining, and
+/// struct literals) tighter still, so `-a.b * c` reads as `(-(a.b)) * c`.
+/// `a ?? b` yields `b` when `a` is null; it sits between comparison and
+/// arithmetic so `hit?.score ?? 0 > 5` compares the defaulted score.
+/// A spread (`...rest`) is not an operator: it may only prefix a whole call
+/// argument or list element.
+const PRECEDENCE: &[(&str, u8)] = &[
+    ("||", 1),
+    ("or", 1),
+    ("&&", 2),
+    ("and", 2),
+    ("==", 3),
+    ("!=", 3),
+    ("<", 4),
+    ("<=", 4),
+    (">", 4),
+    (">=", 4),
+    ("??", 5),
+    ("+", 6),
+    ("-", 6),
+    ("*", 7),
+    ("/", 7),
+    ("%", 7),
+];
+
+pub(crate) const PREFIX_LEVEL: u8 = 8;
+
+/// Binary operators, longest spellings first so `<=` is not read as `<`.
+pub(crate) const BINARY_OPERATORS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "??", "+", "-", "*", "/", "%", "<", ">", "and", "or",
+];
+
+pub(crate) const UNARY_OPERATORS: &[&str] = &["!", "-", "not"];
+
+/// Operator spellings made of punctuation, longest first.
+const SYMBOLS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "??", "+", "-", "*", "/", "%", "<", ">", "!",
+];
+
+/// Precedence level of the binary operator `op`, from [`PRECEDENCE`].
+pub(crate) fn binary_precedence(op: &str) -> Option<u8> {
+    PRECEDENCE
+        .iter()
+        .find(|(candidate, _)| *candidate == op)
+        .map(|(_, level)| *level)
+}
+
+/// Parses all of `code`, which starts at byte `base` of the module source.
+/// Returns `None` when any part of it is not an operator expression.
+pub(super) fn parse_operators(code: &str, base: usize) -> Option<ast::Expression> {
+    let mut parser = Climber {
+        code,
+        base,
+        tokens: tokenize(code)?,
+        pos: 0,
+    };
+    let expr = parser.expression(0)?;
+    (parser.pos == parser.tokens.len()).then_some(expr)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Token<'a> {
+    /// An identifier or keyword.
+    Word(&'a str),
+    /// A string, number, or duration, still in its source spelling.
+    Literal(&'a str),
+    Symbol(&'a str),
+    Dot,
+    OptionalDot,
+    /// Everything between a bracket and its match, brackets excluded.
+    Group {
+        open: char,
+        inner: &'a str,
+    },
+}
+
+/// Splits `code` into tokens paired with their offsets, or `None` at the first
+/// character no expression can contain.
+fn tokenize(code: &str) -> Option<Vec<(usize, Token<'_>)>> {
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while let Some(ch) = peek_char(code, idx) {
+        if ch.is_whitespace() {
+            idx += ch.len_utf8();
+            continue;
+        }
+        let start = idx;
+        let token = match ch {
+            '"' => {
+                idx = skip_comment_or_string(code, idx)?;
+                Token::Literal(&code[start..idx])
+            }
+            '0'..='9' => {
+                idx = number_end(code, idx);
+                Token::Literal(&code[start..idx])
+            }
+            '(' | '[' | '{' => {
+                let close = match ch {
+                    '(' => ')',
+                    '[' => ']',
+                    _ => '}',
+                };
+                let (_, end) = extract_balanced(code, idx, ch, close)?;
+                idx = end;
+                Token::Group {
+                    open: ch,
+                    inner: &code[start + 1..end - 1],
+                }
+            }
+            '.' => {
+                idx += 1;
+                Token::Dot
+            }
+            '?' if code[idx..].starts_with("?.") => {
+                idx += 2;
+                Token::OptionalDot
+            }
+            _ if is_ident_start(ch) => {
+                let word = ident_at(code, idx)?;
+                idx += word.len();
+                Token::Word(word)
+            }
+            _ => {
+                let symbol = SYMBOLS.iter().find(|op| code[idx..].starts_with(**op))?;
+                idx += symbol.len();
+                Token::Symbol(symbol)
+            }
+        };
+        tokens.push((start, token));
+    }
+    Some(tokens)
+}
+
+/// End of the number or duration starting at `start`: digits, underscores,
+/// unit letters, and at most one decimal point followed by a digit.
+fn number_end(code: &str, start: usize) -> usize {
+    let bytes = code.as_bytes();
+    let mut idx = start;
+    let mut seen_point = false;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z' | b'_' => idx += 1,
+            b'.' if !seen_point && bytes.get(idx + 1).is_some_and(u8::is_ascii_digit) => {
+                seen_point = true;
+                idx += 1;
+            }
+            _ => break,
+        }
+    }
+    idx
+}
+
+struct Climber<'a> {
+    code: &'a str,
+    base: usize,
+    tokens: Vec<(usize, Token<'a>)>,
+    pos: usize,
+}
+
+impl<'a> Climber<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).map(|(_, token)| *token)
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.peek()?;
+        self.pos += 1;
+        Some(token)
+    }
+
+    /// Parses the contents of a group token through [`parse_expression`].
+    fn nested(&self, inner: &str) -> ast::Expression {
+        parse_expression(inner, self.base + offset_in(self.code, inner))
+    }
+
+    /// Comma-separated list elements, any of which may be spread with a
+    /// leading `...`.
+    fn elements(&self, inner: &str) -> Vec<ast::Expression> {
+        split_args(inner)
+            .into_iter()
+            .map(|element| self.element(element))
+            .collect()
+    }
+
+    fn element(&self, element: &str) -> ast::Expression {
+        match element.strip_prefix("...") {
+            Some(spread) => ast::Expression::Spread(Box::new(self.nested(spread))),
+            None => self.nested(element),
+        }
+    }
+
+    /// Call arguments: list elements, or `name: value` (also spelled
+    /// `name = value`) for a named argument.
+    fn call_args(&self, inner: &str) -> Vec<ast::CallArg> {
+        split_args(inner)
+            .into_iter()
+            .map(|arg| {
+                if let Some(name) = ident_at(arg, 0) {
+                    let rest = arg[name.len()..].trim_start();
+                    let value = rest.strip_prefix(':').or_else(|| {
+                        rest.strip_prefix('=')
+                            .filter(|value| !value.starts_with(['=', '>']))
+                    });
+                    if let Some(value) = value {
+                        return ast::CallArg {
+                            name: Some(name.to_string()),
+                            value: self.nested(value),
+                        };
+                    }
+                }
+                ast::CallArg {
+                    name: None,
+                    value: self.element(arg),
+                }
+            })
+            .collect()
+    }
+
+    /// Parses an operand followed by every binary operator that binds tighter
+    /// than `min_level`.
+    fn expression(&mut self, min_level: u8) -> Option<ast::Expression> {
+        let mut left = self.postfix()?;
+        while let Some(Token::Symbol(op) | Token::Word(op)) = self.peek() {
+            let Some(level) = binary_precedence(op).filter(|level| *level > min_level) else {
+                break;
+            };
+            self.pos += 1;
+            let right = self.expression(level)?;
+            left = ast::Expression::Binary {
+                left: Box::new(left),
+                op: op.to_string(),
+                right: Box::new(right),
+            };
+        }
+        Some(left)
+    }
+
+    fn postfix(&mut self) -> Option<ast::Expression> {
+        let mut expr = self.prefix()?;
+        loop {
+            expr = match self.peek() {
+                Some(Token::Dot) => {
+                    self.pos += 1;
+                    let Token::Word(property) = self.next()? else {
+                        return None;
+                    };
+                    ast::Expression::Member {
+                        target: Box::new(expr),
+                        property: property.to_string(),
+                    }
+                }
+                Some(Token::OptionalDot) => {
+                    self.pos += 1;
+                    let Token::Word(property) = self.next()? else {
+                        return None;
+                    };
+                    ast::Expression::OptionalChain {
+                        target: Box::new(expr),
+                        property: property.to_string(),
+                    }
+                }
+                Some(Token::Group { open: '(', inner }) => {
+                    self.pos += 1;
+                    ast::Expression::Call {
+                        target: Box::new(expr),
+                        args: self.call_args(inner),
+                    }
+                }
+                Some(Token::Group { open: '[', inner }) if !inner.trim().is_empty() => {
+                    self.pos += 1;
+                    ast::Expression::Index {
+                        target: Box::new(expr),
+                        index: Box::new(self.nested(inner)),
+                    }
+                }
+                Some(Token::Group { open: '{', inner }) => {
+                    let type_name = qualified_name(&expr)?;
+                    self.pos += 1;
+                    self.struct_literal(type_name, inner)?
+                }
+                _ => return Some(expr),
+            };
+        }
+    }
+
+    fn prefix(&mut self) -> Option<ast::Expression> {
+        let (start, token) = *self.tokens.get(self.pos)?;
+        self.pos += 1;
+        match token {
+            Token::Literal(text) => parse_literal(text).map(ast::Expression::Literal),
+            Token::Symbol(op @ ("!" | "-")) | Token::Word(op @ "not") => {
+                // `-3` is a negative literal, but `- 3` and `-x` are negations.
+                if op == "-"
+                    && let Some(&(next, Token::Literal(digits))) = self.tokens.get(self.pos)
+                    && next == start + 1
+                    && let Some(literal) = parse_literal(&self.code[start..next + digits.len()])
+                {
+                    self.pos += 1;
+                    return Some(ast::Expression::Literal(literal));
+                }
+                let operand = self.expression(PREFIX_LEVEL)?;
+                Some(ast::Expression::Unary {
+                    op: op.to_string(),
+                    operand: Box::new(operand),
+                })
+            }
+            Token::Word("await") => {
+                let operand = self.expression(PREFIX_LEVEL)?;
+                Some(ast::Expression::Await(Box::new(operand)))
+            }
+            Token::Word(word) => match parse_literal(word) {
+                Some(literal) => Some(ast::Expression::Literal(literal)),
+                None if is_reserved_keyword(word) => None,
+                None => Some(ast::Expression::Identifier(word.to_string())),
+            },
+            Token::Group { open: '[', inner } => Some(ast::Expression::List(self.elements(inner))),
+            Token::Group { open: '(', inner } => match split_args(inner).as_slice() {
+                [] => None,
+                [_] => match self.nested(inner) {
+                    ast::Expression::Raw(_) => None,
+                    expr => Some(expr),
+                },
+                elements => Some(ast::Expression::Tuple(
+                    elements
+                        .iter()
+                        .map(|element| self.nested(element))
+                        .collect(),
+                )),
+            },
+            _ => None,
+        }
+    }
+
+    /// `Name { field: value, ... }`; every entry needs a `name:` prefix.
+    fn struct_literal(
+        &self,
+        type_name: ast::QualifiedName,
+        inner: &str,
+    ) -> Option<ast::Expression> {
+        let entries = split_args(inner);
+        if entries.is_empty() {
+            return None;
+        }
+        let fields = entries
+            .into_iter()
+            .map(|entry| {
+                let colon = find_top_level(entry, ":")?;
+                let name = entry[..colon].trim();
+                let value = entry[colon + 1..].trim();
+                (ident_at(name, 0) == Some(name) && !value.is_empty())
+                    .then(|| (name.to_string(), self.nested(value)))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(ast::Expression::StructLiteral { type_name, fields })
+    }
+}
+
+/// The dotted name spelled by `expr`, if it is a chain of plain identifiers.
+fn qualified_name(expr: &ast::Expression) -> Option<ast::QualifiedName> {
+    match expr {
+        ast::Expression::Identifier(name) => Some(vec![name.clone()]),
+        ast::Expression::Member { target, property } => {
+            let mut path = qualified_name(target)?;
+            path.push(property.clone());
+            Some(path)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,847 @@
+//! Renders an AST back to HILO source.
+//!
+//! Output uses the style guide's layout (two-space indentation, one
+//! declaration per line). Spans, comments, and `Block::raw` are ignored, so the
+//! printed text reflects the tree rather than the original formatting.
+
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use crate::{
+    ast,
+    parser::{PREFIX_LEVEL, binary_precedence},
+    transform,
+};
+
+/// Binding level of calls, member access, and everything else that never
+/// needs parentheses.
+const POSTFIX: u8 = PREFIX_LEVEL + 1;
+
+/// `///` lines for `docs` at `depth`, one per line of text.
+fn print_docs(out: &mut String, depth: usize, docs: Option<&str>) {
+    for line in docs.into_iter().flat_map(|docs| docs.split('\n')) {
+        indent(out, depth);
+        out.push_str(&doc_comment(line));
+        out.push('\n');
+    }
+}
+
+fn doc_comment(line: &str) -> String {
+    match line {
+        "" => "///".to_string(),
+        line => format!("/// {line}"),
+    }
+}
+
+/// Print `module` as source text.
+pub fn print_module(module: &ast::Module) -> String {
+    let mut out = String::new();
+    if let Some(name) = &module.name {
+        print_docs(&mut out, 0, module.docs.as_deref());
+        let _ = writeln!(out, "module {}", name.join("."));
+    }
+    if !module.imports.is_empty() {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&transform::render_imports(&module.imports));
+    }
+    for item in &module.items {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        print_item(&mut out, item);
+    }
+    out
+}
+
+fn print_item(out: &mut String, item: &ast::Item) {
+    match item {
+        ast::Item::Record(decl) => {
+            print_docs(out, 0, decl.docs.as_deref());
+            print_annotations(out, 0, &decl.annotations);
+            let _ = write!(
+                out,
+                "{}record {}{}",
+                print_visibility(decl.visibility),
+                decl.name,
+                print_type_params(&decl.type_params)
+            );
+            if let Some(parent) = &decl.extends {
+                let _ = write!(out, " extends {}", parent.join("."));
+            }
+            out.push_str(&print_where_clause(&decl.constraints));
+            out.push_str(" {\n");
+            for field in &decl.fields {
+                print_docs(out, 1, field.docs.as_deref());
+                print_annotations(out, 1, &field.annotations);
+                let optional = if field.optional { "?" } else { "" };
+                let _ = writeln!(out, "  {}{optional}: {}", field.name, print_type(&field.ty));
+            }
+            for method in &decl.methods {
+                print_callable(out, 1, "fn", method);
+            }
+            out.push_str("}\n");
+        }
+        ast::Item::Interface(decl) => {
+            let _ = writeln!(out, "interface {} {{", decl.name);
+            for method in &decl.methods {
+                indent(out, 1);
+                print_signature(
+                    out,
+                    1,
+                    "task",
+                    &format!("{}{}", method.name, print_type_params(&method.type_params)),
+                    method.is_async,
+                    &method.params,
+                    &method.return_type,
+                );
+                out.push('\n');
+            }
+            out.push_str("}\n");
+        }
+        ast::Item::Task(decl) => print_callable(out, 0, "task", decl),
+        ast::Item::Workflow(decl) => {
+            print_docs(out, 0, decl.docs.as_deref());
+            print_annotations(out, 0, &decl.annotations);
+            let _ = write!(
+                out,
+                "{}workflow {} ",
+                print_visibility(decl.visibility),
+                decl.name
+            );
+            print_workflow_body(out, 0, &decl.body, &decl.steps, decl.on_error.as_ref());
+            out.push('\n');
+        }
+        ast::Item::Test(decl) => {
+            let _ = write!(out, "test {} ", quote(&decl.name));
+            print_block(out, 0, &decl.body);
+            out.push('\n');
+        }
+        ast::Item::Hook(decl) => {
+            let _ = write!(
+                out,
+                "hook {} {} task {} ",
+                decl.name,
+                decl.position.as_str(),
+                decl.pattern
+            );
+            print_block(out, 0, &decl.body);
+            out.push('\n');
+        }
+        ast::Item::Prompt(decl) => {
+            let _ = writeln!(out, "prompt {} {{", decl.name);
+            for section in &decl.sections {
+                let _ = writeln!(out, "  {}: {}", section.name, quote(&section.text));
+            }
+            out.push_str("}\n");
+        }
+        ast::Item::Tool(decl) => {
+            print_signature(
+                out,
+                0,
+                "tool",
+                &decl.name.join("."),
+                false,
+                &decl.params,
+                &decl.return_type,
+            );
+            out.push('\n');
+        }
+        ast::Item::Trigger(decl) => {
+            let _ = writeln!(out, "trigger {} {{", decl.name);
+            let _ = writeln!(out, "  cron: {}", quote(&decl.cron));
+            let _ = writeln!(out, "  run: {}", decl.run.join("."));
+            out.push_str("}\n");
+        }
+        ast::Item::Secret(decl) => {
+            let _ = write!(out, "secret {}", decl.name);
+            if let Some(source) = &decl.source {
+                let _ = write!(out, " from {}", print_expression(source));
+            }
+            out.push('\n');
+        }
+        ast::Item::Config(decl) => {
+            out.push_str("config {\n");
+            for entry in &decl.entries {
+                let _ = writeln!(out, "  {}: {}", entry.key, print_expression(&entry.value));
+            }
+            out.push_str("}\n");
+        }
+        ast::Item::EventHandler(decl) => {
+            let _ = write!(out, "on event {} ", quote(&decl.pattern));
+            if let Some(binding) = &decl.binding {
+                let _ = write!(out, "({binding}) ");
+            }
+            print_block(out, 0, &decl.body);
+            out.push('\n');
+        }
+        ast::Item::Export(decl) => {
+            let _ = writeln!(out, "export {{ {} }}", decl.names.join(", "));
+        }
+        ast::Item::ReExport(decl) => {
+            let path = decl.path.join(".");
+            let _ = match &decl.members {
+                Some(members) => writeln!(out, "export {path} {{ {} }}", members.join(", ")),
+                None => writeln!(out, "export * from {path}"),
+            };
+        }
+        ast::Item::Module(module) => {
+            let name = module.name.as_deref().unwrap_or_default().join(".");
+            print_docs(out, 0, module.docs.as_deref());
+            let _ = writeln!(out, "module {name} {{");
+            // Items print at the left margin; nesting only adds indentation,
+            // and string literals never span lines once printed.
+            for (idx, item) in module.items.iter().enumerate() {
+                if idx > 0 {
+                    out.push('\n');
+                }
+                let mut text = String::new();
+                print_item(&mut text, item);
+                for line in text.lines() {
+                    if !line.is_empty() {
+                        indent(out, 1);
+                    }
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+            out.push_str("}\n");
+        }
+        ast::Item::Extension(item) => {
+            out.push_str(&item.keyword);
+            if !item.header.is_empty() {
+                let _ = write!(out, " {}", item.header);
+            }
+            let _ = writeln!(out, " {{{}}}", item.body);
+        }
+        ast::Item::Other(text) => {
+            out.push_str(text);
+            out.push('\n');
+        }
+    }
+}
+
+fn print_callable(out: &mut String, depth: usize, keyword: &str, decl: &ast::TaskDecl) {
+    print_docs(out, depth, decl.docs.as_deref());
+    print_annotations(out, depth, &decl.annotations);
+    indent(out, depth);
+    out.push_str(&print_visibility(decl.visibility));
+    print_signature(
+        out,
+        depth,
+        keyword,
+        &format!("{}{}", decl.name, print_type_params(&decl.type_params)),
+        decl.is_async,
+        &decl.params,
+        &decl.return_type,
+    );
+    out.push_str(&print_where_clause(&decl.constraints));
+    if let Some(retry) = &decl.retry {
+        out.push_str(&print_retry(retry));
+    }
+    if let Some(timeout) = decl.timeout {
+        let _ = write!(out, " timeout {}", print_duration(timeout));
+    }
+    out.push(' ');
+    print_block(out, depth, &decl.body);
+    out.push('\n');
+}
+
+/// ` retry(max: 3, backoff: exponential(2s))`, with its leading space.
+fn print_retry(policy: &ast::RetryPolicy) -> String {
+    let mut args = Vec::new();
+    if let Some(max) = policy.max {
+        args.push(format!("max: {max}"));
+    }
+    if let Some(backoff) = policy.backoff {
+        let (kind, delay) = match backoff {
+            ast::Backoff::Fixed(delay) => ("fixed", delay),
+            ast::Backoff::Linear(delay) => ("linear", delay),
+            ast::Backoff::Exponential(delay) => ("exponential", delay),
+        };
+        args.push(format!("backoff: {kind}({})", print_duration(delay)));
+    }
+    format!(" retry({})", args.join(", "))
+}
+
+/// Prints each annotation on its own line at `depth`.
+fn print_annotations(out: &mut String, depth: usize, annotations: &[ast::Annotation]) {
+    for annotation in annotations {
+        indent(out, depth);
+        out.push_str(&print_annotation(annotation, depth));
+        out.push('\n');
+    }
+}
+
+/// [`print_docs`] ahead of a statement whose line is already indented,
+/// leaving the cursor indented for the statement itself.
+fn print_leading_docs(out: &mut String, depth: usize, docs: Option<&str>) {
+    for line in docs.into_iter().flat_map(|docs| docs.split('\n')) {
+        out.push_str(&doc_comment(line));
+        out.push('\n');
+        indent(out, depth);
+    }
+}
+
+/// Prints annotations ahead of a statement whose line is already indented,
+/// leaving the cursor indented for the statement itself.
+fn print_leading_annotations(out: &mut String, depth: usize, annotations: &[ast::Annotation]) {
+    for annotation in annotations {
+        out.push_str(&print_annotation(annotation, depth));
+        out.push('\n');
+        indent(out, depth);
+    }
+}
+
+fn print_annotation(annotation: &ast::Annotation, depth: usize) -> String {
+    let mut out = format!("@{}", annotation.name);
+    if !annotation.args.is_empty() {
+        let args: Vec<String> = annotation
+            .args
+            .iter()
+            .map(|arg| match &arg.name {
+                Some(name) => format!("{name}: {}", expression(&arg.value, depth)),
+                None => expression(&arg.value, depth),
+            })
+            .collect();
+        let _ = write!(out, "({})", args.join(", "));
+    }
+    out
+}
+
+/// Prints `async? keyword name(params) -> Type` without a trailing newline.
+/// Prints a signature whose line starts at `depth`. Parameters with doc
+/// comments put the whole list one per line.
+fn print_signature(
+    out: &mut String,
+    depth: usize,
+    keyword: &str,
+    name: &str,
+    is_async: bool,
+    params: &[ast::Param],
+    return_type: &Option<ast::TypeExpr>,
+) {
+    let texts: Vec<String> = params
+        .iter()
+        .map(|param| {
+            let mut text = format!("{}: {}", param.name, print_type(&param.ty));
+            if let Some(default) = &param.default {
+                let _ = write!(text, " = {}", expression(default, 0));
+            }
+            text
+        })
+        .collect();
+    if is_async {
+        out.push_str("async ");
+    }
+    if params.iter().any(|param| param.docs.is_some()) {
+        let _ = writeln!(out, "{keyword} {name}(");
+        for (param, text) in params.iter().zip(&texts) {
+            print_docs(out, depth + 1, param.docs.as_deref());
+            indent(out, depth + 1);
+            let _ = writeln!(out, "{text},");
+        }
+        indent(out, depth);
+        out.push(')');
+    } else {
+        let _ = write!(out, "{keyword} {name}({})", texts.join(", "));
+    }
+    if let Some(ty) = return_type {
+        let _ = write!(out, " -> {}", print_type(ty));
+    }
+}
+
+/// Prints `{ ... }` with statements one level deeper than `depth`; the caller
+/// positions the opening brace.
+fn print_block(out: &mut String, depth: usize, block: &ast::Block) {
+    if block.statements.is_empty() {
+        out.push_str("{}");
+        return;
+    }
+    out.push_str("{\n");
+    for statement in &block.statements {
+        indent(out, depth + 1);
+        print_statement(out, depth + 1, statement);
+        out.push('\n');
+    }
+    indent(out, depth);
+    out.push('}');
+}
+
+/// A workflow or step body: its statements, then its steps, then its
+/// `on_error` clause.
+fn print_workflow_body(
+    out: &mut String,
+    depth: usize,
+    body: &ast::Block,
+    steps: &[ast::StepDecl],
+    on_error: Option<&ast::ErrorHandler>,
+) {
+    if steps.is_empty() && on_error.is_none() {
+        print_block(out, depth, body);
+        return;
+    }
+    out.push_str("{\n");
+    for statement in &body.statements {
+        indent(out, depth + 1);
+        print_statement(out, depth + 1, statement);
+        out.push('\n');
+    }
+    for step in steps {
+        indent(out, depth + 1);
+        let _ = write!(out, "step {}", step.name);
+        if !step.inputs.is_empty() {
+            let _ = write!(out, "({})", step.inputs.join(", "));
+        }
+        if let Some(retry) = &step.retry {
+            out.push_str(&print_retry(retry));
+        }
+        if let Some(timeout) = step.timeout {
+            let _ = write!(out, " timeout {}", print_duration(timeout));
+        }
+        out.push(' ');
+        print_workflow_body(out, depth + 1, &step.body, &[], step.on_error.as_ref());
+        out.push('\n');
+    }
+    if let Some(handler) = on_error {
+        indent(out, depth + 1);
+        out.push_str("on_error");
+        if let Some(binding) = &handler.binding {
+            let _ = write!(out, "({binding})");
+        }
+        out.push(' ');
+        print_block(out, depth + 1, &handler.body);
+        out.push('\n');
+    }
+    indent(out, depth);
+    out.push('}');
+}
+
+fn print_statement(out: &mut String, depth: usize, statement: &ast::Statement) {
+    match statement {
+        ast::Statement::Let { name, ty, value } => {
+            let _ = write!(out, "let {name}");
+            if let Some(ty) = ty {
+                let _ = write!(out, ": {}", print_type(ty));
+            }
+            if let Some(value) = value {
+                let _ = write!(out, " = {}", expression(value, depth));
+            }
+        }
+        ast::Statement::Return { value } => {
+            out.push_str("return");
+            if let Some(value) = value {
+                let _ = write!(out, " {}", expression(value, depth));
+            }
+        }
+        ast::Statement::Throw(value) => {
+            let _ = write!(out, "throw {}", expression(value, depth));
+        }
+        ast::Statement::Assert(condition) => {
+            let _ = write!(out, "assert {}", expression(condition, depth));
+        }
+        ast::Statement::Block { label, body } => {
+            if let Some(label) = label {
+                let _ = write!(out, "{label} ");
+            }
+            print_block(out, depth, body);
+        }
+        ast::Statement::For {
+            binding,
+            iterable,
+            body,
+        } => {
+            let _ = write!(out, "for {binding} in {} ", expression(iterable, depth));
+            print_block(out, depth, body);
+        }
+        ast::Statement::While { condition, body } => {
+            let _ = write!(out, "while {} ", expression(condition, depth));
+            print_block(out, depth, body);
+        }
+        ast::Statement::Guard {
+            condition,
+            else_body,
+        } => {
+            let _ = write!(out, "guard {} else ", expression(condition, depth));
+            print_block(out, depth, else_body);
+        }
+        ast::Statement::Try {
+            body,
+            catches,
+            finally,
+        } => {
+            out.push_str("try ");
+            print_block(out, depth, body);
+            for catch in catches {
+                out.push_str(" catch ");
+                match (&catch.binding, &catch.ty) {
+                    (Some(binding), Some(ty)) => {
+                        let _ = write!(out, "({binding}: {}) ", print_type(ty));
+                    }
+                    (Some(binding), None) => {
+                        let _ = write!(out, "({binding}) ");
+                    }
+                    (None, _) => {}
+                }
+                print_block(out, depth, &catch.body);
+            }
+            if let Some(finally) = finally {
+                out.push_str(" finally ");
+                print_block(out, depth, finally);
+            }
+        }
+        ast::Statement::Branch {
+            subject,
+            arms,
+            otherwise,
+        } => {
+            let _ = writeln!(out, "branch on {} {{", expression(subject, depth));
+            for arm in arms {
+                indent(out, depth + 1);
+                let _ = writeln!(out, "{} => {},", print_pattern(&arm.pattern), arm.target);
+            }
+            if let Some(target) = otherwise {
+                indent(out, depth + 1);
+                let _ = writeln!(out, "else => {target},");
+            }
+            indent(out, depth);
+            out.push('}');
+        }
+        ast::Statement::Parallel(branches) => {
+            if branches.is_empty() {
+                out.push_str("parallel {}");
+                return;
+            }
+            out.push_str("parallel {\n");
+            for branch in branches {
+                indent(out, depth + 1);
+                match branch.statements.as_slice() {
+                    // A lone statement needs no braces, unless it is a bare
+                    // block, which would read as the branch's own braces.
+                    [statement]
+                        if !matches!(statement, ast::Statement::Block { label: None, .. }) =>
+                    {
+                        print_statement(out, depth + 1, statement)
+                    }
+                    _ => print_block(out, depth + 1, branch),
+                }
+                out.push('\n');
+            }
+            indent(out, depth);
+            out.push('}');
+        }
+        ast::Statement::ParallelFor {
+            binding,
+            iterable,
+            limit,
+            body,
+        } => {
+            let _ = write!(
+                out,
+                "for {binding} in {} parallel",
+                expression(iterable, depth)
+            );
+            if let Some(limit) = limit {
+                let _ = write!(out, "(max: {})", expression(limit, depth));
+            }
+            out.push(' ');
+            print_block(out, depth, body);
+        }
+        ast::Statement::FnDecl(decl) => {
+            print_leading_docs(out, depth, decl.docs.as_deref());
+            print_leading_annotations(out, depth, &decl.annotations);
+            print_signature(
+                out,
+                depth,
+                "fn",
+                &format!("{}{}", decl.name, print_type_params(&decl.type_params)),
+                decl.is_async,
+                &decl.params,
+                &decl.return_type,
+            );
+            out.push_str(&print_where_clause(&decl.constraints));
+            out.push(' ');
+            print_block(out, depth, &decl.body);
+        }
+        ast::Statement::Annotated {
+            annotations,
+            statement,
+        } => {
+            print_leading_annotations(out, depth, annotations);
+            print_statement(out, depth, statement);
+        }
+        ast::Statement::Expr(expr) => out.push_str(&expression(expr, depth)),
+    }
+}
+
+/// Print a single expression.
+pub fn print_expression(expr: &ast::Expression) -> String {
+    expression(expr, 0)
+}
+
+/// Prints `expr` for a statement at `depth`; only match arms span lines.
+fn expression(expr: &ast::Expression, depth: usize) -> String {
+    let sub = |expr: &ast::Expression| expression(expr, depth);
+    match expr {
+        ast::Expression::Identifier(name) => name.clone(),
+        ast::Expression::Literal(literal) => print_literal(literal),
+        ast::Expression::Call { target, args } => {
+            let args: Vec<String> = args
+                .iter()
+                .map(|arg| match &arg.name {
+                    Some(name) => format!("{name}: {}", sub(&arg.value)),
+                    None => sub(&arg.value),
+                })
+                .collect();
+            format!("{}({})", operand(target, depth, POSTFIX), args.join(", "))
+        }
+        ast::Expression::Member { target, property } => {
+            format!("{}.{property}", operand(target, depth, POSTFIX))
+        }
+        ast::Expression::Index { target, index } => {
+            format!("{}[{}]", operand(target, depth, POSTFIX), sub(index))
+        }
+        ast::Expression::OptionalChain { target, property } => {
+            format!("{}?.{property}", operand(target, depth, POSTFIX))
+        }
+        ast::Expression::StructLiteral { type_name, fields } => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(name, value)| format!("{name}: {}", sub(value)))
+                .collect();
+            format!("{} {{ {} }}", type_name.join("."), fields.join(", "))
+        }
+        ast::Expression::Unary { op, operand: inner } => {
+            let mut inner = operand(inner, depth, PREFIX_LEVEL);
+            // `-(3)` must not read back as the literal `-3`.
+            if op == "-" && inner.starts_with(|c: char| c.is_ascii_digit()) {
+                inner = format!("({inner})");
+            }
+            let space = if op == "not" { " " } else { "" };
+            format!("{op}{space}{inner}")
+        }
+        ast::Expression::List(elements) => {
+            let elements: Vec<String> = elements.iter().map(sub).collect();
+            format!("[{}]", elements.join(", "))
+        }
+        ast::Expression::Tuple(elements) => {
+            let elements: Vec<String> = elements.iter().map(sub).collect();
+            format!("({})", elements.join(", "))
+        }
+        ast::Expression::Spread(inner) => format!("...{}", sub(inner)),
+        ast::Expression::Await(inner) => format!("await {}", operand(inner, depth, PREFIX_LEVEL)),
+        ast::Expression::Binary { left, op, right } => {
+            let level = binary_precedence(op).unwrap_or(0);
+            format!(
+                "{} {op} {}",
+                operand(left, depth, level),
+                operand(right, depth, level + 1)
+            )
+        }
+        ast::Expression::Match { subject, arms } => {
+            let mut out = format!("match {} {{\n", sub(subject));
+            for arm in arms {
+                indent(&mut out, depth + 1);
+                out.push_str(&print_pattern(&arm.pattern));
+                if let Some(guard) = &arm.guard {
+                    let _ = write!(out, " if {}", expression(guard, depth + 1));
+                }
+                out.push_str(" => ");
+                print_arm_body(&mut out, depth + 1, &arm.body);
+                out.push('\n');
+            }
+            indent(&mut out, depth);
+            out.push('}');
+            out
+        }
+        ast::Expression::Lambda { params, body } => {
+            let params: Vec<String> = params
+                .iter()
+                .map(|param| match &param.ty {
+                    Some(ty) => format!("{}: {}", param.name, print_type(ty)),
+                    None => param.name.clone(),
+                })
+                .collect();
+            let mut out = format!("({}) => ", params.join(", "));
+            print_arm_body(&mut out, depth, body);
+            out
+        }
+        ast::Expression::Raw(text) => text.clone(),
+    }
+}
+
+fn print_arm_body(out: &mut String, depth: usize, body: &ast::ArmBody) {
+    match body {
+        ast::ArmBody::Expr(value) => out.push_str(&expression(value, depth)),
+        ast::ArmBody::Block(body) => print_block(out, depth, body),
+    }
+}
+
+/// Prints `expr`, parenthesised when it binds looser than `level`.
+fn operand(expr: &ast::Expression, depth: usize, level: u8) -> String {
+    let text = expression(expr, depth);
+    if binding_level(expr) < level {
+        format!("({text})")
+    } else {
+        text
+    }
+}
+
+/// How tightly `expr` holds together, on the scale of `binary_precedence`.
+fn binding_level(expr: &ast::Expression) -> u8 {
+    match expr {
+        ast::Expression::Binary { op, .. } => binary_precedence(op).unwrap_or(0),
+        ast::Expression::Unary { .. } | ast::Expression::Await(_) => PREFIX_LEVEL,
+        ast::Expression::Match { .. }
+        | ast::Expression::Lambda { .. }
+        | ast::Expression::Spread(_) => 0,
+        _ => POSTFIX,
+    }
+}
+
+fn print_pattern(pattern: &ast::Pattern) -> String {
+    match pattern {
+        ast::Pattern::Wildcard => "_".to_string(),
+        ast::Pattern::Binding(name) => name.clone(),
+        ast::Pattern::Literal(literal) => print_literal(literal),
+        ast::Pattern::Variant { path, fields } if fields.is_empty() => path.join("."),
+        ast::Pattern::Variant { path, fields } => {
+            let fields: Vec<String> = fields.iter().map(print_pattern).collect();
+            format!("{}({})", path.join("."), fields.join(", "))
+        }
+        ast::Pattern::Record { name, fields } => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(field, pattern)| match pattern {
+                    Some(pattern) => format!("{field}: {}", print_pattern(pattern)),
+                    None => field.clone(),
+                })
+                .collect();
+            format!("{} {{ {} }}", name.join("."), fields.join(", "))
+        }
+        ast::Pattern::Tuple(items) => {
+            let items: Vec<String> = items.iter().map(print_pattern).collect();
+            format!("({})", items.join(", "))
+        }
+        ast::Pattern::Raw(text) => text.clone(),
+    }
+}
+
+fn print_literal(literal: &ast::Literal) -> String {
+    match literal {
+        ast::Literal::Str(text) => quote(text),
+        ast::Literal::Int(value) => value.to_string(),
+        ast::Literal::Float(value) => format!("{value:?}"),
+        ast::Literal::Bool(value) => value.to_string(),
+        ast::Literal::Null => "null".to_string(),
+        ast::Literal::Duration(value) => print_duration(*value),
+    }
+}
+
+/// Uses the largest unit that represents `value` exactly; sub-millisecond
+/// precision has no literal form and is truncated.
+fn print_duration(value: Duration) -> String {
+    if value.subsec_nanos() != 0 {
+        return format!("{}ms", value.as_millis());
+    }
+    let seconds = value.as_secs();
+    for (unit, size) in [("d", 86_400), ("h", 3_600), ("m", 60)] {
+        if seconds != 0 && seconds.is_multiple_of(size) {
+            return format!("{}{unit}", seconds / size);
+        }
+    }
+    format!("{seconds}s")
+}
+
+/// Print generic parameters, e.g. `<T: Serializable + Hashable, U>`, or
+/// nothing when there are none.
+pub fn print_type_params(params: &[ast::TypeParam]) -> String {
+    if params.is_empty() {
+        return String::new();
+    }
+    let params: Vec<String> = params.iter().map(print_type_param).collect();
+    format!("<{}>", params.join(", "))
+}
+
+/// `pub ` or `internal `, including the separating space.
+fn print_visibility(visibility: ast::Visibility) -> String {
+    visibility
+        .keyword()
+        .map_or_else(String::new, |keyword| format!("{keyword} "))
+}
+
+/// ` where A: Mergeable[B]`, or nothing without constraints.
+fn print_where_clause(constraints: &[ast::TypeParam]) -> String {
+    if constraints.is_empty() {
+        return String::new();
+    }
+    let constraints: Vec<String> = constraints.iter().map(print_type_param).collect();
+    format!(" where {}", constraints.join(", "))
+}
+
+fn print_type_param(param: &ast::TypeParam) -> String {
+    let bounds: Vec<String> = param.bounds.iter().map(print_type).collect();
+    match bounds.is_empty() {
+        true => param.name.clone(),
+        false => format!("{}: {}", param.name, bounds.join(" + ")),
+    }
+}
+
+/// Print a type in source syntax, e.g. `List[Map[String, Int]]?`.
+pub fn print_type(ty: &ast::TypeExpr) -> String {
+    match ty {
+        ast::TypeExpr::Simple(path) => path.join("."),
+        ast::TypeExpr::Generic { base, arguments } => {
+            let args: Vec<String> = arguments.iter().map(print_type).collect();
+            format!("{}[{}]", base.join("."), args.join(", "))
+        }
+        ast::TypeExpr::List(item) => format!("List[{}]", print_type(item)),
+        ast::TypeExpr::Map { key, value } => {
+            format!("Map[{}, {}]", print_type(key), print_type(value))
+        }
+        ast::TypeExpr::Struct(fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|field| {
+                    let optional = if field.optional { "?" } else { "" };
+                    format!("{}{optional}: {}", field.name, print_type(&field.ty))
+                })
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+        ast::TypeExpr::Tuple(elements) => {
+            let elements: Vec<String> = elements.iter().map(print_type).collect();
+            format!("({})", elements.join(", "))
+        }
+        ast::TypeExpr::Optional(inner) => format!("{}?", print_type(inner)),
+        ast::TypeExpr::Unknown(raw) => raw.clone(),
+    }
+}
+
+fn quote(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\0' => out.push_str("\\0"),
+            _ if ch.is_control() => {
+                let _ = write!(out, "\\u{{{:x}}}", ch as u32);
+            }
+            _ => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
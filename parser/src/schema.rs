@@ -0,0 +1,177 @@
+//! JSON schemas describing tasks as LLM tool calls.
+//!
+//! Each task becomes `{ "name", "description", "parameters" }`, where
+//! `parameters` is a JSON Schema object built from the parameter types. Record
+//! types declared in the same module are expanded into nested objects.
+
+use serde_json::{Map, Value, json};
+
+use crate::{ast, semantic};
+
+/// Tool schemas for every task in `module`, in declaration order.
+///
+/// Descriptions come from the `///` comments above each task and parameter.
+pub fn tool_schemas(module: &ast::Module) -> Vec<Value> {
+    module
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ast::Item::Task(task) => Some(tool_schema(module, task)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Tool schema for `task`, resolving record parameter types against `module`.
+pub fn tool_schema(module: &ast::Module, task: &ast::TaskDecl) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for param in &task.params {
+        let (ty, optional) = unwrap_optional(&param.ty);
+        let mut property = type_schema(module, ty, &mut Vec::new());
+        if let Some(ast::Expression::Literal(literal)) = &param.default
+            && let Some(default) = literal_value(literal)
+            && let Value::Object(property) = &mut property
+        {
+            property.insert("default".into(), default);
+        }
+        if let Some(docs) = &param.docs
+            && let Value::Object(property) = &mut property
+        {
+            property.insert("description".into(), docs.clone().into());
+        }
+        properties.insert(param.name.clone(), property);
+        if !optional && param.default.is_none() {
+            required.push(Value::from(param.name.clone()));
+        }
+    }
+
+    let mut schema = Map::new();
+    schema.insert("name".into(), task.name.clone().into());
+    if let Some(docs) = &task.docs {
+        schema.insert("description".into(), docs.clone().into());
+    }
+    schema.insert(
+        "parameters".into(),
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        }),
+    );
+    Value::Object(schema)
+}
+
+/// JSON value of a literal default; durations have no JSON spelling.
+fn literal_value(literal: &ast::Literal) -> Option<Value> {
+    match literal {
+        ast::Literal::Str(text) => Some(text.clone().into()),
+        ast::Literal::Int(value) => Some((*value).into()),
+        ast::Literal::Float(value) => Some((*value).into()),
+        ast::Literal::Bool(value) => Some((*value).into()),
+        ast::Literal::Null => Some(Value::Null),
+        ast::Literal::Duration(_) => None,
+    }
+}
+
+fn unwrap_optional(ty: &ast::TypeExpr) -> (&ast::TypeExpr, bool) {
+    match ty {
+        ast::TypeExpr::Optional(inner) => (inner, true),
+        other => (other, false),
+    }
+}
+
+/// Schema for `ty`; `expanding` holds the records being expanded, so
+/// self-referential records stop at an unconstrained schema.
+fn type_schema<'a>(
+    module: &'a ast::Module,
+    ty: &ast::TypeExpr,
+    expanding: &mut Vec<&'a str>,
+) -> Value {
+    match ty {
+        ast::TypeExpr::Simple(path) => {
+            let name = path.last().map(String::as_str).unwrap_or_default();
+            match name {
+                "String" => json!({ "type": "string" }),
+                "Int" => json!({ "type": "integer" }),
+                "Float" | "Number" => json!({ "type": "number" }),
+                "Bool" => json!({ "type": "boolean" }),
+                _ => record_schema(module, name, expanding),
+            }
+        }
+        ast::TypeExpr::List(item) => {
+            json!({ "type": "array", "items": type_schema(module, item, expanding) })
+        }
+        ast::TypeExpr::Generic { base, arguments } => {
+            match (base.last().map(String::as_str), arguments.as_slice()) {
+                (Some("List"), [item]) => {
+                    json!({ "type": "array", "items": type_schema(module, item, expanding) })
+                }
+                _ => json!({}),
+            }
+        }
+        ast::TypeExpr::Map { value, .. } => json!({
+            "type": "object",
+            "additionalProperties": type_schema(module, value, expanding),
+        }),
+        ast::TypeExpr::Struct(fields) => object_schema(
+            fields
+                .iter()
+                .map(|field| (field.name.as_str(), field.optional, &field.ty)),
+            module,
+            expanding,
+        ),
+        ast::TypeExpr::Tuple(elements) => {
+            let items: Vec<Value> = elements
+                .iter()
+                .map(|element| type_schema(module, element, expanding))
+                .collect();
+            json!({
+                "type": "array",
+                "prefixItems": items,
+                "minItems": elements.len(),
+                "maxItems": elements.len(),
+            })
+        }
+        ast::TypeExpr::Optional(inner) => type_schema(module, inner, expanding),
+        ast::TypeExpr::Unknown(_) => json!({}),
+    }
+}
+
+fn record_schema<'a>(module: &'a ast::Module, name: &str, expanding: &mut Vec<&'a str>) -> Value {
+    let Some(record) = semantic::record(module, name) else {
+        return json!({});
+    };
+    if expanding.contains(&record.name.as_str()) {
+        return json!({});
+    }
+    expanding.push(&record.name);
+    // Inherited fields come first, as they do in `semantic::record_fields`.
+    let fields = semantic::record_fields(module, name).unwrap_or_default();
+    let schema = object_schema(
+        fields
+            .into_iter()
+            .map(|field| (field.name.as_str(), field.optional, &field.ty)),
+        module,
+        expanding,
+    );
+    expanding.pop();
+    schema
+}
+
+fn object_schema<'a, 'f>(
+    fields: impl Iterator<Item = (&'f str, bool, &'f ast::TypeExpr)>,
+    module: &'a ast::Module,
+    expanding: &mut Vec<&'a str>,
+) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for (name, optional, ty) in fields {
+        let (ty, optional_type) = unwrap_optional(ty);
+        properties.insert(name.to_string(), type_schema(module, ty, expanding));
+        if !optional && !optional_type {
+            required.push(Value::from(name));
+        }
+    }
+    json!({ "type": "object", "properties": properties, "required": required })
+}
@@ -0,0 +1,323 @@
+//! Queries and checks that look at several items of a module together.
+//!
+//! Names resolve within the module only: a record whose parent is imported
+//! from elsewhere is checked against nothing and flattens to its own fields.
+
+use crate::{ast, lint::Diagnostic};
+
+/// The record declared as `name` in `module`.
+pub fn record<'a>(module: &'a ast::Module, name: &str) -> Option<&'a ast::RecordDecl> {
+    module.items.iter().find_map(|item| match item {
+        ast::Item::Record(decl) if decl.name == name => Some(decl),
+        _ => None,
+    })
+}
+
+/// The tool declared as `name`, a dotted path such as `web.search`, in `module`.
+pub fn tool<'a>(module: &'a ast::Module, name: &str) -> Option<&'a ast::ToolDecl> {
+    module.items.iter().find_map(|item| match item {
+        ast::Item::Tool(decl) if decl.name.iter().map(String::as_str).eq(name.split('.')) => {
+            Some(decl)
+        }
+        _ => None,
+    })
+}
+
+/// Every `secret` declared in `module` or its nested modules, in source order,
+/// for auditing which secrets a program can read.
+pub fn secrets(module: &ast::Module) -> Vec<&ast::SecretDecl> {
+    module
+        .items
+        .iter()
+        .flat_map(|item| match item {
+            ast::Item::Secret(decl) => vec![decl],
+            ast::Item::Module(nested) => secrets(nested),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+/// Whether modules importing `module` can see its item `name`, declared with
+/// `visibility`. `internal` items are never visible; once the module has an
+/// `export` list, only listed items and those marked `pub` are.
+pub fn is_exported(module: &ast::Module, name: &str, visibility: ast::Visibility) -> bool {
+    if !visibility.is_exported() {
+        return false;
+    }
+    let mut lists = export_lists(module).peekable();
+    lists.peek().is_none()
+        || visibility == ast::Visibility::Public
+        || lists.any(|decl| decl.names.iter().any(|listed| listed == name))
+}
+
+fn export_lists(module: &ast::Module) -> impl Iterator<Item = &ast::ExportDecl> {
+    module.items.iter().filter_map(|item| match item {
+        ast::Item::Export(decl) => Some(decl),
+        _ => None,
+    })
+}
+
+/// All fields of record `name`, inherited ones first in ancestor order.
+///
+/// A field the child redeclares replaces the inherited one in place. Parents
+/// that are not declared in `module`, and inheritance cycles, end the chain.
+pub fn record_fields<'a>(module: &'a ast::Module, name: &str) -> Option<Vec<&'a ast::RecordField>> {
+    let chain = ancestry(module, record(module, name)?);
+    let mut fields: Vec<&ast::RecordField> = Vec::new();
+    for decl in chain.iter().rev() {
+        for field in &decl.fields {
+            match fields.iter_mut().find(|known| known.name == field.name) {
+                Some(known) => *known = field,
+                None => fields.push(field),
+            }
+        }
+    }
+    Some(fields)
+}
+
+/// `decl` followed by its ancestors declared in `module`, stopping before a
+/// record repeats.
+fn ancestry<'a>(module: &'a ast::Module, decl: &'a ast::RecordDecl) -> Vec<&'a ast::RecordDecl> {
+    let mut chain = vec![decl];
+    let mut current = decl;
+    while let Some(parent) = local_parent(module, current) {
+        if chain.iter().any(|seen| seen.name == parent.name) {
+            break;
+        }
+        chain.push(parent);
+        current = parent;
+    }
+    chain
+}
+
+fn local_parent<'a>(
+    module: &'a ast::Module,
+    decl: &ast::RecordDecl,
+) -> Option<&'a ast::RecordDecl> {
+    match decl.extends.as_deref() {
+        Some([parent]) => record(module, parent),
+        _ => None,
+    }
+}
+
+/// Report problems with `extends` clauses:
+///
+/// - `records::unknown-parent`: the parent is neither declared nor imported.
+/// - `records::inheritance-cycle`: a record is its own ancestor.
+/// - `records::field-conflict`: a field is redeclared with a different type
+///   or optionality than the inherited one.
+pub fn check_records(module: &ast::Module) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for item in &module.items {
+        let ast::Item::Record(decl) = item else {
+            continue;
+        };
+        let Some(parent_name) = &decl.extends else {
+            continue;
+        };
+        let Some(parent) = local_parent(module, decl) else {
+            if let [name] = parent_name.as_slice()
+                && !is_imported(module, name)
+            {
+                diagnostics.push(Diagnostic {
+                    rule: "records::unknown-parent",
+                    message: format!("record `{}` extends unknown record `{name}`", decl.name),
+                    span: decl.span.clone(),
+                    fix: None,
+                });
+            }
+            continue;
+        };
+
+        let chain = ancestry(module, parent);
+        if chain.iter().any(|ancestor| ancestor.name == decl.name) {
+            diagnostics.push(Diagnostic {
+                rule: "records::inheritance-cycle",
+                message: format!("record `{}` inherits from itself", decl.name),
+                span: decl.span.clone(),
+                fix: None,
+            });
+            continue;
+        }
+
+        for field in &decl.fields {
+            let inherited = chain.iter().find_map(|ancestor| {
+                ancestor
+                    .fields
+                    .iter()
+                    .find(|other| other.name == field.name)
+                    .map(|other| (ancestor, other))
+            });
+            let Some((ancestor, inherited)) = inherited else {
+                continue;
+            };
+            if inherited.ty != field.ty || inherited.optional != field.optional {
+                diagnostics.push(Diagnostic {
+                    rule: "records::field-conflict",
+                    message: format!(
+                        "field `{}` of `{}` conflicts with the field inherited from `{}`",
+                        field.name, decl.name, ancestor.name
+                    ),
+                    span: field.span.clone(),
+                    fix: None,
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Report `export` lists that cannot be honoured:
+///
+/// - `exports::unknown-item`: the name is not declared in the module.
+/// - `exports::internal-item`: the named item is marked `internal`.
+pub fn check_exports(module: &ast::Module) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for decl in export_lists(module) {
+        for name in &decl.names {
+            let declared = module
+                .items
+                .iter()
+                .find(|item| declared_name(item) == Some(name.as_str()));
+            let (rule, message) = match declared {
+                None => (
+                    "exports::unknown-item",
+                    format!("`{name}` is exported but not declared in this module"),
+                ),
+                Some(item) if !item.visibility().is_exported() => (
+                    "exports::internal-item",
+                    format!("`{name}` is exported but marked `internal`"),
+                ),
+                Some(_) => continue,
+            };
+            diagnostics.push(Diagnostic {
+                rule,
+                message,
+                span: decl.span.clone(),
+                fix: None,
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Report `branch on` statements that route to steps their workflow lacks:
+///
+/// - `workflows::unknown-step`: an arm or `else` names neither a `step` nor a
+///   labelled block of the workflow.
+///
+/// Branches are looked for at the top level of the workflow body and of each
+/// step body.
+pub fn check_workflows(module: &ast::Module) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for item in &module.items {
+        let ast::Item::Workflow(decl) = item else {
+            continue;
+        };
+        let statements = || {
+            decl.body
+                .statements
+                .iter()
+                .chain(decl.steps.iter().flat_map(|step| &step.body.statements))
+                .map(|statement| match statement {
+                    ast::Statement::Annotated { statement, .. } => statement.as_ref(),
+                    statement => statement,
+                })
+        };
+        let steps: Vec<&str> = decl
+            .steps
+            .iter()
+            .map(|step| step.name.as_str())
+            .chain(statements().filter_map(|statement| match statement {
+                ast::Statement::Block {
+                    label: Some(label), ..
+                } => Some(label.as_str()),
+                _ => None,
+            }))
+            .collect();
+        for statement in statements() {
+            let ast::Statement::Branch {
+                arms, otherwise, ..
+            } = statement
+            else {
+                continue;
+            };
+            let targets = arms.iter().map(|arm| &arm.target).chain(otherwise);
+            for target in targets.filter(|target| !steps.contains(&target.as_str())) {
+                diagnostics.push(Diagnostic {
+                    rule: "workflows::unknown-step",
+                    message: format!("workflow `{}` has no step `{target}`", decl.name),
+                    span: decl.span.clone(),
+                    fix: None,
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Report `trigger` declarations that cannot fire:
+///
+/// - `triggers::invalid-cron`: the schedule does not have the five fields
+///   minute, hour, day of month, month, and day of week.
+/// - `triggers::unknown-target`: `run` names neither a workflow nor a task of
+///   the module, nor an imported member. Qualified targets are not checked.
+pub fn check_triggers(module: &ast::Module) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for item in &module.items {
+        let ast::Item::Trigger(decl) = item else {
+            continue;
+        };
+        if decl.cron.split_whitespace().count() != 5 {
+            diagnostics.push(Diagnostic {
+                rule: "triggers::invalid-cron",
+                message: format!(
+                    "trigger `{}` has schedule `{}`, which is not a five-field cron expression",
+                    decl.name, decl.cron
+                ),
+                span: decl.span.clone(),
+                fix: None,
+            });
+        }
+        let [target] = decl.run.as_slice() else {
+            continue;
+        };
+        let runnable = module.items.iter().any(|item| match item {
+            ast::Item::Workflow(workflow) => &workflow.name == target,
+            ast::Item::Task(task) => &task.name == target,
+            _ => false,
+        });
+        if !runnable && !is_imported(module, target) {
+            diagnostics.push(Diagnostic {
+                rule: "triggers::unknown-target",
+                message: format!(
+                    "trigger `{}` runs `{target}`, which is not a workflow or task",
+                    decl.name
+                ),
+                span: decl.span.clone(),
+                fix: None,
+            });
+        }
+    }
+    diagnostics
+}
+
+fn declared_name(item: &ast::Item) -> Option<&str> {
+    match item {
+        ast::Item::Record(decl) => Some(&decl.name),
+        ast::Item::Interface(decl) => Some(&decl.name),
+        ast::Item::Task(decl) => Some(&decl.name),
+        ast::Item::Workflow(decl) => Some(&decl.name),
+        ast::Item::Prompt(decl) => Some(&decl.name),
+        _ => None,
+    }
+}
+
+fn is_imported(module: &ast::Module, name: &str) -> bool {
+    module.imports.iter().any(|import| {
+        import
+            .members
+            .as_ref()
+            .is_some_and(|members| members.iter().any(|member| member == name))
+    })
+}
@@ -0,0 +1,31 @@
+//! Declarations of the built-in `core.*` modules, embedded as HILO source.
+//!
+//! Task bodies are empty: the host runtime supplies the implementations. The
+//! declarations give tooling the names, parameters, and return types.
+
+use crate::{ast, error::HiloParseError, options::ParseOptions, parser};
+
+const MODULES: &[(&str, &str)] = &[
+    ("core.http", include_str!("../stdlib/core/http.hilo")),
+    ("core.io", include_str!("../stdlib/core/io.hilo")),
+    ("core.json", include_str!("../stdlib/core/json.hilo")),
+    ("core.text", include_str!("../stdlib/core/text.hilo")),
+];
+
+/// Dotted names of the embedded modules, e.g. `core.text`.
+pub fn module_names() -> impl Iterator<Item = &'static str> {
+    MODULES.iter().map(|(name, _)| *name)
+}
+
+/// HILO source of the embedded module `name`.
+pub fn source(name: &str) -> Option<&'static str> {
+    MODULES
+        .iter()
+        .find(|(module, _)| *module == name)
+        .map(|(_, source)| *source)
+}
+
+/// Parse the embedded module `name`.
+pub fn load(name: &str) -> Option<Result<ast::Module, HiloParseError>> {
+    source(name).map(|source| parser::parse_module(source, &ParseOptions::default()))
+}
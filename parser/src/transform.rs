@@ -0,0 +1,80 @@
+//! Source-level rewrites of parsed modules.
+
+use std::fmt::Write as _;
+
+use crate::ast;
+
+/// Sort imports by path, merge imports of the same path and alias, and sort
+/// and dedupe member lists.
+///
+/// `import a.b` and `import a.b { x }` are kept apart: the first binds the
+/// module itself, the second only its members.
+pub fn organize_imports(module: &mut ast::Module) {
+    organize_imports_with(module, |_, _| true);
+}
+
+/// [`organize_imports`], additionally dropping every member for which
+/// `is_used(path, member)` returns `false`. Imports left with no members are
+/// removed entirely.
+pub fn organize_imports_with<F>(module: &mut ast::Module, mut is_used: F)
+where
+    F: FnMut(&ast::QualifiedName, &str) -> bool,
+{
+    let mut imports = std::mem::take(&mut module.imports);
+    imports.sort_by(|a, b| {
+        (&a.path, &a.version, &a.alias, a.members.is_some()).cmp(&(
+            &b.path,
+            &b.version,
+            &b.alias,
+            b.members.is_some(),
+        ))
+    });
+
+    let mut merged: Vec<ast::Import> = Vec::with_capacity(imports.len());
+    for import in imports {
+        match merged.last_mut() {
+            Some(last)
+                if last.path == import.path
+                    && last.version == import.version
+                    && last.alias == import.alias
+                    && last.members.is_some() == import.members.is_some() =>
+            {
+                if let (Some(members), Some(more)) = (&mut last.members, import.members) {
+                    members.extend(more);
+                }
+            }
+            _ => merged.push(import),
+        }
+    }
+
+    merged.retain_mut(|import| {
+        let Some(members) = &mut import.members else {
+            return true;
+        };
+        members.sort();
+        members.dedup();
+        members.retain(|member| is_used(&import.path, member));
+        !members.is_empty()
+    });
+    module.imports = merged;
+}
+
+/// Render imports in canonical form, one per line:
+/// `import path @ "version" { a, b } as Alias`.
+pub fn render_imports(imports: &[ast::Import]) -> String {
+    let mut out = String::new();
+    for import in imports {
+        let _ = write!(out, "import {}", import.path_text());
+        if let Some(version) = &import.version {
+            let _ = write!(out, " @ {version:?}");
+        }
+        if let Some(members) = &import.members {
+            let _ = write!(out, " {{ {} }}", members.join(", "));
+        }
+        if let Some(alias) = &import.alias {
+            let _ = write!(out, " as {alias}");
+        }
+        out.push('\n');
+    }
+    out
+}